@@ -24,12 +24,47 @@
 
 //! Abstract syntax tree implementation.
 
+use crate::binary;
+use crate::errors::SecelError;
+use crate::evaluator::EvaluatorConfig;
+use crate::optimizer::{is_constant, value_to_node};
+use crate::parser::{Parser, ParserConfig};
+use crate::types::ExprType;
+use crate::values::Value;
 use crate::IndexKey;
 use ascii_tree::{write_tree, Tree};
+#[cfg(feature = "chrono")]
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::de::{self, EnumAccess, VariantAccess};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap, HashSet};
+#[cfg(feature = "serde")]
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
 
 /// Node of the abstract syntax tree.
-#[derive(Debug)]
+///
+/// Cloning, comparing and dropping the tree recurses on every node, like most of its methods,
+/// so very deep trees built without the parser (which limits the nesting depth) may overflow the stack.
+/// Methods [AstNode::depth], [AstNode::node_count] and [AstNode::fold] do not recurse.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AstNode {
+  /// Node representing `abs` function, evaluates to the absolute value of the operand.
+  Abs(
+    /// Node representing the operand.
+    Box<AstNode>,
+  ),
+  /// Node representing arithmetic operator `+`.
+  Add(
+    /// Node representing left-side operand.
+    Box<AstNode>,
+    /// Node representing right-side operand.
+    Box<AstNode>,
+  ),
   /// Node representing conjunction operator `and`.
   And(
     /// Node representing left-side operand.
@@ -37,6 +72,43 @@ pub enum AstNode {
     /// Node representing right-side operand.
     Box<AstNode>,
   ),
+  /// Node representing `avg()` function, evaluates to the average of all numbers in the context, `null` when there are none.
+  Avg,
+  /// Node representing range operator `between`, the range is inclusive on both ends.
+  Between(
+    /// Node representing the tested value.
+    Box<AstNode>,
+    /// Node representing the lower bound of the range.
+    Box<AstNode>,
+    /// Node representing the upper bound of the range.
+    Box<AstNode>,
+  ),
+  /// Node representing boolean literal `true` or `false`.
+  Bool(bool),
+  /// Node representing the call of a user-registered function, see [crate::EvaluatorConfig::with_functions].
+  Call(
+    /// Name of the called function.
+    String,
+    /// Nodes representing the arguments.
+    Vec<AstNode>,
+  ),
+  /// Node representing `coalesce` function, evaluates to the first operand that is not `null`.
+  Coalesce(
+    /// Nodes representing the operands.
+    Vec<AstNode>,
+  ),
+  /// Node representing date literal, available with `chrono` feature.
+  #[cfg(feature = "chrono")]
+  DateLiteral(NaiveDate),
+  /// Node representing `count()` function, evaluates to the number of all numbers in the context.
+  Count,
+  /// Node representing arithmetic operator `/`.
+  Div(
+    /// Node representing left-side operand.
+    Box<AstNode>,
+    /// Node representing right-side operand.
+    Box<AstNode>,
+  ),
   /// Node representing comparison operator `=`.
   Eq(
     /// Node representing left-side operand.
@@ -67,6 +139,23 @@ pub enum AstNode {
     /// Node representing expression invoked when the condition is false.
     Box<AstNode>,
   ),
+  /// Node representing list membership operator `in`.
+  In(
+    /// Node representing the tested value.
+    Box<AstNode>,
+    /// Nodes representing the list elements.
+    Vec<AstNode>,
+  ),
+  /// Node representing `is not null` test.
+  IsNotNull(
+    /// Node representing the tested value.
+    Box<AstNode>,
+  ),
+  /// Node representing `is null` test.
+  IsNull(
+    /// Node representing the tested value.
+    Box<AstNode>,
+  ),
   /// Node representing comparison operator `<`.
   Le(
     /// Node representing left-side operand.
@@ -74,6 +163,8 @@ pub enum AstNode {
     /// Node representing right-side operand.
     Box<AstNode>,
   ),
+  /// Node representing decimal number literal.
+  Literal(Decimal),
   /// Node representing comparison operator `<=`.
   Lt(
     /// Node representing left-side operand.
@@ -81,6 +172,23 @@ pub enum AstNode {
     /// Node representing right-side operand.
     Box<AstNode>,
   ),
+  /// Node representing `max` function, evaluates to the greatest operand.
+  Max(
+    /// Nodes representing the operands.
+    Vec<AstNode>,
+  ),
+  /// Node representing `min` function, evaluates to the least operand.
+  Min(
+    /// Nodes representing the operands.
+    Vec<AstNode>,
+  ),
+  /// Node representing arithmetic operator `*`.
+  Mul(
+    /// Node representing left-side operand.
+    Box<AstNode>,
+    /// Node representing right-side operand.
+    Box<AstNode>,
+  ),
   /// Node representing disjunction operator `or`.
   Or(
     /// Node representing left-side operand.
@@ -92,6 +200,11 @@ pub enum AstNode {
   Null,
   /// Node representing result index.
   Number(IndexKey),
+  /// Node representing unary arithmetic operator `-`.
+  Neg(
+    /// Node representing the negated operand.
+    Box<AstNode>,
+  ),
   /// Node representing comparison operator `<>`.
   Nq(
     /// Node representing left-side operand.
@@ -99,6 +212,781 @@ pub enum AstNode {
     /// Node representing right-side operand.
     Box<AstNode>,
   ),
+  /// Node representing `round` function, rounds the operand to the number of decimal places
+  /// specified by the scale, midpoint values are rounded to the nearest even number (banker's rounding).
+  Round(
+    /// Node representing the rounded operand.
+    Box<AstNode>,
+    /// Node representing the scale, must be a non-negative integer literal.
+    Box<AstNode>,
+  ),
+  /// Node representing string literal.
+  StrLiteral(String),
+  /// Node representing `sum()` function, evaluates to the sum of all numbers in the context, `0` when there are none.
+  Sum,
+  /// Node representing arithmetic operator `-`.
+  Sub(
+    /// Node representing left-side operand.
+    Box<AstNode>,
+    /// Node representing right-side operand.
+    Box<AstNode>,
+  ),
+  /// Node representing exclusive disjunction operator `xor`,
+  /// has the same precedence as `or` and is left-associative.
+  Xor(
+    /// Node representing left-side operand.
+    Box<AstNode>,
+    /// Node representing right-side operand.
+    Box<AstNode>,
+  ),
+}
+
+impl AstNode {
+  /// Returns the index of the node kind in [VARIANTS], used as the variant index in serialization
+  /// and as the tag in binary encoding.
+  pub(crate) fn variant_index(&self) -> usize {
+    let kind = self.kind();
+    VARIANTS
+      .iter()
+      .position(|variant| *variant == kind)
+      .expect("every node kind is listed in VARIANTS")
+  }
+
+  /// Returns the name of the node kind, e.g. `"Add"` for [AstNode::Add].
+  pub fn kind(&self) -> &'static str {
+    match self {
+      AstNode::Abs(_) => "Abs",
+      AstNode::Add(..) => "Add",
+      AstNode::And(..) => "And",
+      AstNode::Avg => "Avg",
+      AstNode::Between(..) => "Between",
+      AstNode::Bool(_) => "Bool",
+      AstNode::Call(..) => "Call",
+      AstNode::Coalesce(_) => "Coalesce",
+      AstNode::Count => "Count",
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(_) => "DateLiteral",
+      AstNode::Div(..) => "Div",
+      AstNode::Eq(..) => "Eq",
+      AstNode::Ge(..) => "Ge",
+      AstNode::Gt(..) => "Gt",
+      AstNode::If(..) => "If",
+      AstNode::In(..) => "In",
+      AstNode::IsNotNull(_) => "IsNotNull",
+      AstNode::IsNull(_) => "IsNull",
+      AstNode::Le(..) => "Le",
+      AstNode::Literal(_) => "Literal",
+      AstNode::Lt(..) => "Lt",
+      AstNode::Max(_) => "Max",
+      AstNode::Min(_) => "Min",
+      AstNode::Mul(..) => "Mul",
+      AstNode::Neg(_) => "Neg",
+      AstNode::Null => "Null",
+      AstNode::Number(_) => "Number",
+      AstNode::Or(..) => "Or",
+      AstNode::Nq(..) => "Nq",
+      AstNode::Round(..) => "Round",
+      AstNode::StrLiteral(_) => "StrLiteral",
+      AstNode::Sum => "Sum",
+      AstNode::Sub(..) => "Sub",
+      AstNode::Xor(..) => "Xor",
+    }
+  }
+
+  /// Returns the depth of the tree rooted at this node, computed without recursion.
+  pub fn depth(&self) -> usize {
+    let mut max_depth = 0;
+    let mut nodes = vec![(self, 1)];
+    while let Some((node, depth)) = nodes.pop() {
+      max_depth = max_depth.max(depth);
+      nodes.extend(node.children().into_iter().map(|child| (child, depth + 1)));
+    }
+    max_depth
+  }
+
+  /// Returns the number of nodes in the tree rooted at this node, computed without recursion.
+  pub fn node_count(&self) -> usize {
+    self.fold(0, |count, _| count + 1)
+  }
+
+  /// Reduces all nodes of the tree rooted at this node in preorder (see [AstNode::iter]),
+  /// starting with the initial accumulator value, computed without recursion.
+  pub fn fold<T>(&self, init: T, f: impl FnMut(T, &AstNode) -> T) -> T {
+    self.iter().fold(init, f)
+  }
+
+  /// Returns an iterator over the nodes of the tree rooted at this node, in preorder
+  /// (node before its children, children in order of operands).
+  pub fn iter(&self) -> AstIter<'_> {
+    AstIter { nodes: vec![self] }
+  }
+
+  /// Encodes this node into compact binary form, see [AstNode::from_bytes] for the reverse operation.
+  ///
+  /// Nodes are encoded in preorder, each as a tag byte (position of the node kind in the order of declaration
+  /// of variants) followed by the payload: operands, `u32` little-endian counts of list operands,
+  /// `u16` little-endian indexes, single byte booleans and `u32` length-prefixed UTF-8 text
+  /// of names, strings, decimals (preserving scale) and dates.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    binary::encode(self)
+  }
+
+  /// Decodes the node from bytes produced by [AstNode::to_bytes], reports an error
+  /// when the bytes are truncated, malformed, not fully consumed or nested deeper than [crate::DEFAULT_MAX_DEPTH].
+  pub fn from_bytes(bytes: &[u8]) -> Result<AstNode, SecelError> {
+    binary::decode(bytes)
+  }
+
+  /// Returns references to the direct child nodes, in order of operands.
+  pub(crate) fn children(&self) -> Vec<&AstNode> {
+    match self {
+      AstNode::Abs(mhs) | AstNode::IsNotNull(mhs) | AstNode::IsNull(mhs) | AstNode::Neg(mhs) => vec![mhs],
+      AstNode::Add(lhs, rhs)
+      | AstNode::And(lhs, rhs)
+      | AstNode::Div(lhs, rhs)
+      | AstNode::Eq(lhs, rhs)
+      | AstNode::Ge(lhs, rhs)
+      | AstNode::Gt(lhs, rhs)
+      | AstNode::Le(lhs, rhs)
+      | AstNode::Lt(lhs, rhs)
+      | AstNode::Mul(lhs, rhs)
+      | AstNode::Nq(lhs, rhs)
+      | AstNode::Or(lhs, rhs)
+      | AstNode::Round(lhs, rhs)
+      | AstNode::Sub(lhs, rhs)
+      | AstNode::Xor(lhs, rhs) => vec![lhs, rhs],
+      AstNode::Between(lhs, mhs, rhs) | AstNode::If(lhs, mhs, rhs) => vec![lhs, mhs, rhs],
+      AstNode::In(lhs, rhs) => Some(lhs.as_ref()).into_iter().chain(rhs.iter()).collect(),
+      AstNode::Call(_, mhs) | AstNode::Coalesce(mhs) | AstNode::Max(mhs) | AstNode::Min(mhs) => mhs.iter().collect(),
+      AstNode::Avg | AstNode::Bool(_) | AstNode::Count | AstNode::Literal(_) | AstNode::Null | AstNode::Number(_) | AstNode::StrLiteral(_) | AstNode::Sum => {
+        vec![]
+      }
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(_) => vec![],
+    }
+  }
+
+  /// Returns mutable references to the direct child nodes, in order of operands.
+  pub(crate) fn children_mut(&mut self) -> Vec<&mut AstNode> {
+    match self {
+      AstNode::Abs(mhs) | AstNode::IsNotNull(mhs) | AstNode::IsNull(mhs) | AstNode::Neg(mhs) => vec![mhs.as_mut()],
+      AstNode::Add(lhs, rhs)
+      | AstNode::And(lhs, rhs)
+      | AstNode::Div(lhs, rhs)
+      | AstNode::Eq(lhs, rhs)
+      | AstNode::Ge(lhs, rhs)
+      | AstNode::Gt(lhs, rhs)
+      | AstNode::Le(lhs, rhs)
+      | AstNode::Lt(lhs, rhs)
+      | AstNode::Mul(lhs, rhs)
+      | AstNode::Nq(lhs, rhs)
+      | AstNode::Or(lhs, rhs)
+      | AstNode::Round(lhs, rhs)
+      | AstNode::Sub(lhs, rhs)
+      | AstNode::Xor(lhs, rhs) => vec![lhs.as_mut(), rhs.as_mut()],
+      AstNode::Between(lhs, mhs, rhs) | AstNode::If(lhs, mhs, rhs) => vec![lhs.as_mut(), mhs.as_mut(), rhs.as_mut()],
+      AstNode::In(lhs, rhs) => Some(lhs.as_mut()).into_iter().chain(rhs.iter_mut()).collect(),
+      AstNode::Call(_, mhs) | AstNode::Coalesce(mhs) | AstNode::Max(mhs) | AstNode::Min(mhs) => mhs.iter_mut().collect(),
+      AstNode::Avg | AstNode::Bool(_) | AstNode::Count | AstNode::Literal(_) | AstNode::Null | AstNode::Number(_) | AstNode::StrLiteral(_) | AstNode::Sum => {
+        vec![]
+      }
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(_) => vec![],
+    }
+  }
+
+  /// Returns the set of indexes of values read by the expression (all `Number` nodes in the tree).
+  pub fn collect_indices(&self) -> BTreeSet<IndexKey> {
+    let mut collector = IndexCollector(BTreeSet::new());
+    walk(self, &mut collector);
+    collector.0
+  }
+
+  /// Returns the number of reads of each index in the expression (occurrences of `Number` nodes).
+  pub fn index_usage(&self) -> HashMap<IndexKey, usize> {
+    self.fold(HashMap::new(), |mut usage, node| {
+      if let AstNode::Number(key) = node {
+        *usage.entry(*key).or_insert(0) += 1;
+      }
+      usage
+    })
+  }
+
+  /// Returns the number of occurrences of each operator and function in the expression, keyed by [AstNode::kind].
+  /// Literals, `null` and indexes are not counted.
+  pub fn operator_histogram(&self) -> HashMap<&'static str, usize> {
+    let mut histogram = HashMap::new();
+    for node in self.iter() {
+      match node {
+        AstNode::Bool(_) | AstNode::Literal(_) | AstNode::Null | AstNode::Number(_) | AstNode::StrLiteral(_) => {}
+        #[cfg(feature = "chrono")]
+        AstNode::DateLiteral(_) => {}
+        _ => *histogram.entry(node.kind()).or_insert(0) += 1,
+      }
+    }
+    histogram
+  }
+
+  /// Returns a copy of the tree with indexes in all `Number` nodes replaced with the result of specified function.
+  pub fn remap_indices(&self, f: impl Fn(IndexKey) -> IndexKey) -> AstNode {
+    let mut root = self.clone();
+    let mut nodes = vec![&mut root];
+    while let Some(node) = nodes.pop() {
+      match node {
+        AstNode::Number(key) => *key = f(*key),
+        other => nodes.extend(other.children_mut()),
+      }
+    }
+    root
+  }
+
+  /// Returns a copy of the tree with all `Number` nodes reading specified index replaced
+  /// with the literal node representing specified value, see [crate::optimize] for folding the result.
+  pub fn substitute(&self, key: IndexKey, value: Value) -> AstNode {
+    let mut root = self.clone();
+    let mut nodes = vec![&mut root];
+    while let Some(node) = nodes.pop() {
+      match node {
+        AstNode::Number(k) if *k == key => *node = value_to_node(value.clone()),
+        other => nodes.extend(other.children_mut()),
+      }
+    }
+    root
+  }
+
+  /// Returns the branches of `if` expressions that can never be evaluated, because the condition
+  /// is constant after folding with default [crate::EvaluatorConfig] (see [crate::optimize]),
+  /// like the false-branch of `if(1=1;1;2)`. Both branches are dead when the condition is constant `null`.
+  pub fn dead_branches(&self) -> Vec<&AstNode> {
+    let config = EvaluatorConfig::default();
+    let mut branches = vec![];
+    for node in self.iter() {
+      if let AstNode::If(condition, lhs, rhs) = node {
+        match crate::optimizer::fold(condition.as_ref().clone(), &config) {
+          AstNode::Bool(true) => branches.push(rhs.as_ref()),
+          AstNode::Bool(false) => branches.push(lhs.as_ref()),
+          condition if is_constant(&condition) => branches.extend([lhs.as_ref(), rhs.as_ref()]),
+          _ => {}
+        }
+      }
+    }
+    branches
+  }
+
+  /// Infers the type of the expression without evaluating it, reports an error for operands
+  /// of types not making sense for the operator, like comparing boolean expressions.
+  pub fn type_check(&self) -> Result<ExprType, SecelError> {
+    crate::types::type_check(self)
+  }
+
+  /// Returns the type of the expression result, without checking the types of operands
+  /// like [AstNode::type_check] does. Values of indexes are assumed to be numbers.
+  pub fn result_type(&self) -> ExprType {
+    crate::types::result_type(self)
+  }
+
+  /// Simplifies logical operators with boolean literal operands (`x and true` is simplified to `x`),
+  /// the simplified tree evaluates to the same values with default [crate::EvaluatorConfig].
+  /// The language has no `not` operator, so double negations and De Morgan's laws do not apply.
+  pub fn simplify(self) -> AstNode {
+    crate::optimizer::simplify(self)
+  }
+
+  /// Sorts operands of commutative operators (`xor`, `=`, `<>`, `+`, `*`) into deterministic order,
+  /// so trees differing only in the order of such operands are canonicalized into identical trees.
+  /// Operands of `and` and `or` are sorted only with [EvaluatorConfig::three_valued_logic] enabled,
+  /// the default two-valued logic evaluates `null and false` to `Null` but `false and null` to `false`.
+  /// Operands are ordered by their binary encoding (see [AstNode::to_bytes]), other nodes are left untouched.
+  pub fn canonicalize(mut self, config: &EvaluatorConfig) -> AstNode {
+    for child in self.children_mut() {
+      *child = std::mem::replace(child, AstNode::Null).canonicalize(config);
+    }
+    match &mut self {
+      AstNode::Add(lhs, rhs) | AstNode::Eq(lhs, rhs) | AstNode::Mul(lhs, rhs) | AstNode::Nq(lhs, rhs) | AstNode::Xor(lhs, rhs)
+        if lhs.to_bytes() > rhs.to_bytes() =>
+      {
+        std::mem::swap(lhs, rhs)
+      }
+      AstNode::And(lhs, rhs) | AstNode::Or(lhs, rhs) if config.three_valued_logic && lhs.to_bytes() > rhs.to_bytes() => std::mem::swap(lhs, rhs),
+      _ => {}
+    }
+    self
+  }
+
+  /// Converts the tree into S-expression, like `(if (eq (idx 1) (idx 2)) (idx 1) (idx 2))`.
+  /// Operators and functions are named after the node kind in kebab case (`is-not-null`),
+  /// indexes are written as `(idx 1)`, decimal literals as numbers, strings in double quotes
+  /// and calls of user-registered functions as `(call name arguments...)`.
+  pub fn to_sexpr(&self) -> String {
+    match self {
+      AstNode::Bool(value) => value.to_string(),
+      AstNode::Call(name, mhs) => format!("(call {}{})", name, mhs.iter().map(|node| format!(" {}", node.to_sexpr())).collect::<String>()),
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(value) => format!("(date {})", value),
+      AstNode::Literal(value) => value.to_string(),
+      AstNode::Null => "null".to_string(),
+      AstNode::Number(value) => format!("(idx {})", value),
+      AstNode::StrLiteral(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+      _ => {
+        let mut name = String::new();
+        for (i, ch) in self.kind().chars().enumerate() {
+          if ch.is_ascii_uppercase() && i > 0 {
+            name.push('-');
+          }
+          name.push(ch.to_ascii_lowercase());
+        }
+        format!(
+          "({}{})",
+          name,
+          self.children().iter().map(|node| format!(" {}", node.to_sexpr())).collect::<String>()
+        )
+      }
+    }
+  }
+
+  /// Converts the tree back into SECEL source in canonical form, like `if(1=2;1;2)`,
+  /// parentheses are emitted only where required by the precedence of operators
+  /// (with the default [ParserConfig]). Parsing the source of a tree built by the parser
+  /// yields a tree equal to the original one. Conversion recurses on every node.
+  pub fn to_source(&self) -> String {
+    match self {
+      AstNode::And(..)
+      | AstNode::Or(..)
+      | AstNode::Xor(..)
+      | AstNode::Bool(_)
+      | AstNode::Between(..)
+      | AstNode::Eq(..)
+      | AstNode::Ge(..)
+      | AstNode::Gt(..)
+      | AstNode::In(..)
+      | AstNode::IsNotNull(_)
+      | AstNode::IsNull(_)
+      | AstNode::Le(..)
+      | AstNode::Lt(..)
+      | AstNode::Nq(..) => condition_to_source(self, 0),
+      _ => arithmetic_to_source(self, SUM),
+    }
+  }
+}
+
+/// Preorder iterator over the nodes of the abstract syntax tree, see [AstNode::iter].
+pub struct AstIter<'a> {
+  /// Nodes to be visited, the next visited node is on the top.
+  nodes: Vec<&'a AstNode>,
+}
+
+impl<'a> Iterator for AstIter<'a> {
+  type Item = &'a AstNode;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let node = self.nodes.pop()?;
+    self.nodes.extend(node.children().into_iter().rev());
+    Some(node)
+  }
+}
+
+/// Cache of interned trees, returning the same shared [AstNode] for structurally equal trees (hash-consing).
+///
+/// Child nodes of [AstNode] are boxed, so interning shares only the interned trees as a whole,
+/// equal subtrees nested in different trees are not shared. Sharing nested subtrees
+/// would require the tree to hold `Rc<AstNode>` children.
+#[derive(Debug, Default)]
+pub struct AstInterner {
+  /// Interned trees.
+  nodes: HashSet<Rc<AstNode>>,
+}
+
+impl AstInterner {
+  /// Creates an empty cache.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the shared tree equal to specified tree, the tree is added to the cache when not interned yet.
+  pub fn intern(&mut self, node: AstNode) -> Rc<AstNode> {
+    match self.nodes.get(&node) {
+      Some(interned) => interned.clone(),
+      None => {
+        let interned = Rc::new(node);
+        self.nodes.insert(interned.clone());
+        interned
+      }
+    }
+  }
+
+  /// Returns the number of interned trees.
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  /// Returns `true` when no tree is interned.
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+}
+
+/// Visitor of the abstract syntax tree nodes, see [walk].
+pub trait Visitor {
+  /// Visits the node, returns `false` when the children of the node should not be visited.
+  fn visit(&mut self, node: &AstNode) -> bool;
+}
+
+/// Walks the tree rooted at specified node in preorder (node before its children, children
+/// in order of operands), children of nodes for which the visitor returns `false` are skipped.
+pub fn walk<V: Visitor + ?Sized>(node: &AstNode, visitor: &mut V) {
+  let mut nodes = vec![node];
+  while let Some(node) = nodes.pop() {
+    if visitor.visit(node) {
+      nodes.extend(node.children().into_iter().rev());
+    }
+  }
+}
+
+/// Visitor collecting indexes of values from `Number` nodes.
+struct IndexCollector(BTreeSet<IndexKey>);
+
+impl Visitor for IndexCollector {
+  fn visit(&mut self, node: &AstNode) -> bool {
+    if let AstNode::Number(key) = node {
+      self.0.insert(*key);
+    }
+    true
+  }
+}
+
+/// Arithmetic level of `sum` in the grammar, operands of `+` and `-`.
+const SUM: u8 = 0;
+/// Arithmetic level of `product` in the grammar, operands of `*` and `/`.
+const PRODUCT: u8 = 1;
+/// Arithmetic level of `operand` in the grammar, parenthesized sums and values.
+const OPERAND: u8 = 2;
+
+/// Converts the node into the source of a condition, logical operators with precedence
+/// lower than specified are enclosed in parentheses.
+fn condition_to_source(node: &AstNode, min_precedence: u8) -> String {
+  let config = ParserConfig::default();
+  let (precedence, operator, lhs, rhs) = match node {
+    AstNode::And(lhs, rhs) => (config.and_precedence, "and", lhs, rhs),
+    AstNode::Or(lhs, rhs) => (config.or_precedence, "or", lhs, rhs),
+    AstNode::Xor(lhs, rhs) => (config.xor_precedence, "xor", lhs, rhs),
+    AstNode::Bool(value) => return value.to_string(),
+    AstNode::Between(lhs, mhs, rhs) => return format!("{} between {} and {}", sum(lhs), sum(mhs), sum(rhs)),
+    AstNode::Eq(lhs, rhs) => return format!("{}={}", sum(lhs), sum(rhs)),
+    AstNode::Ge(lhs, rhs) => return format!("{}>={}", sum(lhs), sum(rhs)),
+    AstNode::Gt(lhs, rhs) => return format!("{}>{}", sum(lhs), sum(rhs)),
+    AstNode::In(lhs, rhs) => return format!("{} in ({})", sum(lhs), list_to_source(rhs)),
+    AstNode::IsNotNull(mhs) => return format!("{} is not null", sum(mhs)),
+    AstNode::IsNull(mhs) => return format!("{} is null", sum(mhs)),
+    AstNode::Le(lhs, rhs) => return format!("{}<={}", sum(lhs), sum(rhs)),
+    AstNode::Lt(lhs, rhs) => return format!("{}<{}", sum(lhs), sum(rhs)),
+    AstNode::Nq(lhs, rhs) => return format!("{}<>{}", sum(lhs), sum(rhs)),
+    other => return sum(other),
+  };
+  let source = format!(
+    "{} {} {}",
+    condition_to_source(lhs, precedence),
+    operator,
+    condition_to_source(rhs, precedence + 1)
+  );
+  if precedence < min_precedence {
+    format!("({})", source)
+  } else {
+    source
+  }
+}
+
+/// Converts the node into the source of an arithmetic expression at specified level of the grammar,
+/// operators binding looser than the level are enclosed in parentheses.
+fn arithmetic_to_source(node: &AstNode, level: u8) -> String {
+  let (operator_level, operator, lhs, rhs) = match node {
+    AstNode::Add(lhs, rhs) => (SUM, "+", lhs, rhs),
+    AstNode::Sub(lhs, rhs) => (SUM, "-", lhs, rhs),
+    AstNode::Mul(lhs, rhs) => (PRODUCT, "*", lhs, rhs),
+    AstNode::Div(lhs, rhs) => (PRODUCT, "/", lhs, rhs),
+    other => return value_to_source(other),
+  };
+  let source = format!(
+    "{}{}{}",
+    arithmetic_to_source(lhs, operator_level),
+    operator,
+    arithmetic_to_source(rhs, operator_level + 1)
+  );
+  if operator_level < level {
+    format!("({})", source)
+  } else {
+    source
+  }
+}
+
+/// Converts the node into the source of a value, function or `if` expression.
+fn value_to_source(node: &AstNode) -> String {
+  match node {
+    AstNode::Abs(mhs) => format!("abs({})", sum(mhs)),
+    AstNode::Avg => "avg()".to_string(),
+    AstNode::Call(name, mhs) => format!("{}({})", name, list_to_source(mhs)),
+    AstNode::Coalesce(mhs) => format!("coalesce({})", list_to_source(mhs)),
+    AstNode::Count => "count()".to_string(),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(value) => format!("@{}", value),
+    AstNode::If(lhs, mhs, rhs) => format!("if({};{};{})", condition_to_source(lhs, 0), sum(mhs), sum(rhs)),
+    AstNode::Literal(value) if value.scale() == 0 && value.is_sign_positive() => format!("{}.0", value),
+    AstNode::Literal(value) => value.to_string(),
+    AstNode::Max(mhs) => format!("max({})", list_to_source(mhs)),
+    AstNode::Min(mhs) => format!("min({})", list_to_source(mhs)),
+    AstNode::Neg(mhs) if matches!(mhs.as_ref(), AstNode::Number(_)) => format!("- {}", mhs.to_source()),
+    AstNode::Neg(mhs) => format!("-{}", arithmetic_to_source(mhs, OPERAND)),
+    AstNode::Null => "null".to_string(),
+    AstNode::Number(value) => value.to_string(),
+    AstNode::Round(lhs, rhs) => match rhs.as_ref() {
+      AstNode::Literal(scale) if scale.scale() == 0 && *scale >= Decimal::ZERO && *scale <= Decimal::from(IndexKey::MAX) => {
+        format!("round({};{})", sum(lhs), scale)
+      }
+      _ => format!("round({};{})", sum(lhs), sum(rhs)),
+    },
+    AstNode::StrLiteral(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    AstNode::Sum => "sum()".to_string(),
+    other => format!("({})", condition_to_source(other, 0)),
+  }
+}
+
+/// Converts the node into the source of a sum.
+fn sum(node: &AstNode) -> String {
+  arithmetic_to_source(node, SUM)
+}
+
+/// Converts nodes into the source of arguments separated with semicolons.
+fn list_to_source(nodes: &[AstNode]) -> String {
+  nodes.iter().map(sum).collect::<Vec<String>>().join(";")
+}
+
+/// Parses the tree from SECEL source, non-panicking counterpart of [crate::parse_expression].
+impl FromStr for AstNode {
+  type Err = SecelError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Parser::new(s).parse()
+  }
+}
+
+/// Parses the tree from SECEL source, see [FromStr] implementation.
+impl TryFrom<&str> for AstNode {
+  type Error = SecelError;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+/// Names of all [AstNode] variants in order of declaration (which is not alphabetical, e.g. `Or` precedes `Null`),
+/// variant indexes used in serialization and tags in binary encoding are positions in this list (independent of enabled features).
+/// New variants may only be appended, reordering the list changes the encoding.
+pub(crate) const VARIANTS: &[&str] = &[
+  "Abs",
+  "Add",
+  "And",
+  "Avg",
+  "Between",
+  "Bool",
+  "Call",
+  "Coalesce",
+  "DateLiteral",
+  "Count",
+  "Div",
+  "Eq",
+  "Ge",
+  "Gt",
+  "If",
+  "In",
+  "IsNotNull",
+  "IsNull",
+  "Le",
+  "Literal",
+  "Lt",
+  "Max",
+  "Min",
+  "Mul",
+  "Or",
+  "Null",
+  "Number",
+  "Neg",
+  "Nq",
+  "Round",
+  "StrLiteral",
+  "Sum",
+  "Sub",
+  "Xor",
+];
+
+/// Serializes nodes as externally tagged variants named after [AstNode::kind]: nodes without operands
+/// as unit variants (JSON `"Null"`), other nodes as newtype variants holding the operand,
+/// or the tuple of operands (JSON `{"Add":[{"Number":1},{"Number":2}]}`).
+/// Decimal and date literals are serialized as strings, preserving the scale of decimals.
+#[cfg(feature = "serde")]
+impl Serialize for AstNode {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let name = self.kind();
+    let index = self.variant_index() as u32;
+    match self {
+      AstNode::Avg | AstNode::Count | AstNode::Null | AstNode::Sum => serializer.serialize_unit_variant("AstNode", index, name),
+      AstNode::Bool(value) => serializer.serialize_newtype_variant("AstNode", index, name, value),
+      AstNode::Literal(value) => serializer.serialize_newtype_variant("AstNode", index, name, &value.to_string()),
+      AstNode::Number(value) => serializer.serialize_newtype_variant("AstNode", index, name, value),
+      AstNode::StrLiteral(value) => serializer.serialize_newtype_variant("AstNode", index, name, value),
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(value) => serializer.serialize_newtype_variant("AstNode", index, name, &value.to_string()),
+      AstNode::Abs(mhs) | AstNode::IsNotNull(mhs) | AstNode::IsNull(mhs) | AstNode::Neg(mhs) => {
+        serializer.serialize_newtype_variant("AstNode", index, name, mhs)
+      }
+      AstNode::Coalesce(mhs) | AstNode::Max(mhs) | AstNode::Min(mhs) => serializer.serialize_newtype_variant("AstNode", index, name, mhs),
+      AstNode::Call(lhs, rhs) => serializer.serialize_newtype_variant("AstNode", index, name, &(lhs, rhs)),
+      AstNode::In(lhs, rhs) => serializer.serialize_newtype_variant("AstNode", index, name, &(lhs, rhs)),
+      AstNode::Add(lhs, rhs)
+      | AstNode::And(lhs, rhs)
+      | AstNode::Div(lhs, rhs)
+      | AstNode::Eq(lhs, rhs)
+      | AstNode::Ge(lhs, rhs)
+      | AstNode::Gt(lhs, rhs)
+      | AstNode::Le(lhs, rhs)
+      | AstNode::Lt(lhs, rhs)
+      | AstNode::Mul(lhs, rhs)
+      | AstNode::Nq(lhs, rhs)
+      | AstNode::Or(lhs, rhs)
+      | AstNode::Round(lhs, rhs)
+      | AstNode::Sub(lhs, rhs)
+      | AstNode::Xor(lhs, rhs) => serializer.serialize_newtype_variant("AstNode", index, name, &(lhs, rhs)),
+      AstNode::Between(lhs, mhs, rhs) | AstNode::If(lhs, mhs, rhs) => serializer.serialize_newtype_variant("AstNode", index, name, &(lhs, mhs, rhs)),
+    }
+  }
+}
+
+/// Deserializes nodes serialized by [Serialize] implementation.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for AstNode {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_enum("AstNode", VARIANTS, AstNodeVisitor)
+  }
+}
+
+/// Visitor building [AstNode] from deserialized data.
+#[cfg(feature = "serde")]
+struct AstNodeVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> de::Visitor<'de> for AstNodeVisitor {
+  type Value = AstNode;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("AST node")
+  }
+
+  fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<AstNode, A::Error> {
+    let (VariantName(name), variant) = data.variant()?;
+    match name {
+      "Abs" => unary(variant, AstNode::Abs),
+      "Add" => binary(variant, AstNode::Add),
+      "And" => binary(variant, AstNode::And),
+      "Avg" => variant.unit_variant().map(|_| AstNode::Avg),
+      "Between" => ternary(variant, AstNode::Between),
+      "Bool" => variant.newtype_variant().map(AstNode::Bool),
+      "Call" => variant.newtype_variant().map(|(lhs, rhs)| AstNode::Call(lhs, rhs)),
+      "Coalesce" => variant.newtype_variant().map(AstNode::Coalesce),
+      "Count" => variant.unit_variant().map(|_| AstNode::Count),
+      #[cfg(feature = "chrono")]
+      "DateLiteral" => {
+        let text = variant.newtype_variant::<String>()?;
+        NaiveDate::from_str(&text)
+          .map(AstNode::DateLiteral)
+          .map_err(|_| de::Error::custom(format!("invalid date '{}'", text)))
+      }
+      "Div" => binary(variant, AstNode::Div),
+      "Eq" => binary(variant, AstNode::Eq),
+      "Ge" => binary(variant, AstNode::Ge),
+      "Gt" => binary(variant, AstNode::Gt),
+      "If" => ternary(variant, AstNode::If),
+      "In" => variant.newtype_variant().map(|(lhs, rhs)| AstNode::In(lhs, rhs)),
+      "IsNotNull" => unary(variant, AstNode::IsNotNull),
+      "IsNull" => unary(variant, AstNode::IsNull),
+      "Le" => binary(variant, AstNode::Le),
+      "Literal" => {
+        let text = variant.newtype_variant::<String>()?;
+        Decimal::from_str(&text)
+          .map(AstNode::Literal)
+          .map_err(|_| de::Error::custom(format!("invalid decimal number '{}'", text)))
+      }
+      "Lt" => binary(variant, AstNode::Lt),
+      "Max" => variant.newtype_variant().map(AstNode::Max),
+      "Min" => variant.newtype_variant().map(AstNode::Min),
+      "Mul" => binary(variant, AstNode::Mul),
+      "Neg" => unary(variant, AstNode::Neg),
+      "Null" => variant.unit_variant().map(|_| AstNode::Null),
+      "Number" => variant.newtype_variant().map(AstNode::Number),
+      "Or" => binary(variant, AstNode::Or),
+      "Nq" => binary(variant, AstNode::Nq),
+      "Round" => binary(variant, AstNode::Round),
+      "StrLiteral" => variant.newtype_variant().map(AstNode::StrLiteral),
+      "Sum" => variant.unit_variant().map(|_| AstNode::Sum),
+      "Sub" => binary(variant, AstNode::Sub),
+      "Xor" => binary(variant, AstNode::Xor),
+      other => Err(de::Error::unknown_variant(other, VARIANTS)),
+    }
+  }
+}
+
+/// Name of the [AstNode] variant, deserialized from the name (self-describing formats)
+/// or from the index in [VARIANTS] (formats that encode variants by index).
+#[cfg(feature = "serde")]
+struct VariantName(&'static str);
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for VariantName {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_identifier(VariantNameVisitor)
+  }
+}
+
+/// Visitor of the [AstNode] variant identifier.
+#[cfg(feature = "serde")]
+struct VariantNameVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> de::Visitor<'de> for VariantNameVisitor {
+  type Value = VariantName;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("AST node variant name or index")
+  }
+
+  fn visit_u64<E: de::Error>(self, value: u64) -> Result<VariantName, E> {
+    match usize::try_from(value).ok().and_then(|index| VARIANTS.get(index)) {
+      Some(name) => Ok(VariantName(name)),
+      None => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+    }
+  }
+
+  fn visit_str<E: de::Error>(self, value: &str) -> Result<VariantName, E> {
+    match VARIANTS.iter().find(|name| **name == value) {
+      Some(name) => Ok(VariantName(name)),
+      None => Err(E::unknown_variant(value, VARIANTS)),
+    }
+  }
+}
+
+/// Deserializes the operand of a node with single operand.
+#[cfg(feature = "serde")]
+fn unary<'de, V: VariantAccess<'de>>(variant: V, node: fn(Box<AstNode>) -> AstNode) -> Result<AstNode, V::Error> {
+  variant.newtype_variant().map(node)
+}
+
+/// Deserializes the operands of a node with two operands.
+#[cfg(feature = "serde")]
+fn binary<'de, V: VariantAccess<'de>>(variant: V, node: fn(Box<AstNode>, Box<AstNode>) -> AstNode) -> Result<AstNode, V::Error> {
+  variant.newtype_variant().map(|(lhs, rhs)| node(lhs, rhs))
+}
+
+/// Deserializes the operands of a node with three operands.
+#[cfg(feature = "serde")]
+fn ternary<'de, V: VariantAccess<'de>>(variant: V, node: fn(Box<AstNode>, Box<AstNode>, Box<AstNode>) -> AstNode) -> Result<AstNode, V::Error> {
+  variant.newtype_variant().map(|(lhs, mhs, rhs)| node(lhs, mhs, rhs))
 }
 
 impl ToString for AstNode {
@@ -108,32 +996,129 @@ impl ToString for AstNode {
   }
 }
 
+/// Style of the textual tree rendered by [ast_to_tree_with].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeStyle {
+  /// Draws the tree with pure ASCII characters (`|-`, `` `- ``) instead of Unicode box-drawing characters (`├─`, `└─`).
+  pub ascii: bool,
+  /// Number of spaces each line of the tree is indented with.
+  pub indent: usize,
+}
+
+impl Default for TreeStyle {
+  /// Creates the style of [ast_to_tree], Unicode box-drawing characters and indentation of 6 spaces.
+  fn default() -> Self {
+    Self { ascii: false, indent: 6 }
+  }
+}
+
+impl TreeStyle {
+  /// Creates the style drawing the tree with pure ASCII characters, indentation of 6 spaces.
+  pub fn ascii() -> Self {
+    Self {
+      ascii: true,
+      ..Self::default()
+    }
+  }
+}
+
 /// Converts AST into textual tree.
 pub fn ast_to_tree(root: &AstNode) -> String {
+  ast_to_tree_with(root, TreeStyle::default())
+}
+
+/// Converts AST into textual tree drawn in specified style, every line of the tree is preceded by a new line
+/// and indented, the tree is followed by a new line indented by two spaces less (like a raw string literal in tests).
+pub fn ast_to_tree_with(root: &AstNode, style: TreeStyle) -> String {
   let mut ascii_tree = String::new();
   let tree = ast_node_to_tree(root);
   let _ = write_tree(&mut ascii_tree, &tree);
-  let tree = ascii_tree.lines().map(|line| format!("\n      {}", line)).collect::<String>();
-  format!("{}\n    ", tree)
+  if style.ascii {
+    ascii_tree = ascii_tree.replace("├─", "|-").replace("└─", "`-").replace('│', "|");
+  }
+  let indent = " ".repeat(style.indent);
+  let tree = ascii_tree.lines().map(|line| format!("\n{}{}", indent, line)).collect::<String>();
+  format!("{}\n{}", tree, " ".repeat(style.indent.saturating_sub(2)))
+}
+
+/// Converts AST into a directed graph in Graphviz DOT format, with one graph node per AST node
+/// (labeled by the node kind and the value of literals and indexes) and edges from parent
+/// to child nodes in order of operands.
+pub fn ast_to_dot(root: &AstNode) -> String {
+  let mut dot = "digraph AST {\n".to_string();
+  ast_node_to_dot(root, &mut dot, &mut 0);
+  dot.push_str("}\n");
+  dot
+}
+
+/// Writes single AST node and its children in DOT format, returns the identifier of the written node.
+fn ast_node_to_dot(node: &AstNode, dot: &mut String, next_id: &mut usize) -> usize {
+  let id = *next_id;
+  *next_id += 1;
+  let label = match node {
+    AstNode::Bool(value) => format!("{} {}", node.kind(), value),
+    AstNode::Call(name, _) => format!("{} {}", node.kind(), name),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(value) => format!("{} {}", node.kind(), value),
+    AstNode::Literal(value) => format!("{} {}", node.kind(), value),
+    AstNode::Number(value) => format!("{} {}", node.kind(), value),
+    AstNode::StrLiteral(value) => format!("{} {:?}", node.kind(), value),
+    _ => node.kind().to_string(),
+  };
+  dot.push_str(&format!("  n{} [label=\"{}\"];\n", id, label.replace('\\', "\\\\").replace('"', "\\\"")));
+  for child in node.children() {
+    let child_id = ast_node_to_dot(child, dot, next_id);
+    dot.push_str(&format!("  n{} -> n{};\n", id, child_id));
+  }
+  id
 }
 
 /// Converts single AST node into tree.
 fn ast_node_to_tree(node: &AstNode) -> Tree {
   match node {
-    AstNode::And(lhs, rhs) => node_2("And", lhs, rhs),
-    AstNode::Eq(lhs, rhs) => node_2("Eq", lhs, rhs),
-    AstNode::Ge(lhs, rhs) => node_2("Ge", lhs, rhs),
-    AstNode::Gt(lhs, rhs) => node_2("Gt", lhs, rhs),
-    AstNode::If(lhs, mid, rhs) => node_3("If", lhs, mid, rhs),
-    AstNode::Le(lhs, rhs) => node_2("Le", lhs, rhs),
-    AstNode::Lt(lhs, rhs) => node_2("Lt", lhs, rhs),
-    AstNode::Null => leaf("Null"),
-    AstNode::Number(lhs) => node_and_leaf("Number", &format!("`{}`", lhs)),
-    AstNode::Or(lhs, rhs) => node_2("Or", lhs, rhs),
-    AstNode::Nq(lhs, rhs) => node_2("Nq", lhs, rhs),
+    AstNode::Abs(mhs) => node_1(node.kind(), mhs),
+    AstNode::Add(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::And(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Avg => leaf(node.kind()),
+    AstNode::Between(lhs, mid, rhs) => node_3(node.kind(), lhs, mid, rhs),
+    AstNode::Bool(lhs) => node_and_leaf(node.kind(), &format!("`{}`", lhs)),
+    AstNode::Call(name, mhs) => node_list(&format!("{} `{}`", node.kind(), name), mhs),
+    AstNode::Coalesce(mhs) => node_list(node.kind(), mhs),
+    AstNode::Count => leaf(node.kind()),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(lhs) => node_and_leaf(node.kind(), &format!("`{}`", lhs)),
+    AstNode::Div(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Eq(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Ge(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Gt(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::If(lhs, mid, rhs) => node_3(node.kind(), lhs, mid, rhs),
+    AstNode::In(lhs, rhs) => node_n(node.kind(), lhs, rhs),
+    AstNode::IsNotNull(mhs) => node_1(node.kind(), mhs),
+    AstNode::IsNull(mhs) => node_1(node.kind(), mhs),
+    AstNode::Le(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Literal(lhs) => node_and_leaf(node.kind(), &format!("`{}`", lhs)),
+    AstNode::Lt(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Max(mhs) => node_list(node.kind(), mhs),
+    AstNode::Min(mhs) => node_list(node.kind(), mhs),
+    AstNode::Mul(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Neg(mhs) => node_1(node.kind(), mhs),
+    AstNode::Null => leaf(node.kind()),
+    AstNode::Number(lhs) => node_and_leaf(node.kind(), &format!("`{}`", lhs)),
+    AstNode::Or(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Nq(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Round(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::StrLiteral(lhs) => node_and_leaf(node.kind(), &format!("`{:?}`", lhs)),
+    AstNode::Sum => leaf(node.kind()),
+    AstNode::Sub(lhs, rhs) => node_2(node.kind(), lhs, rhs),
+    AstNode::Xor(lhs, rhs) => node_2(node.kind(), lhs, rhs),
   }
 }
 
+///
+fn node_1(name: &str, mhs: &AstNode) -> Tree {
+  Tree::Node(name.to_string(), vec![ast_node_to_tree(mhs)])
+}
+
 ///
 fn node_2(name: &str, lhs: &AstNode, rhs: &AstNode) -> Tree {
   Tree::Node(name.to_string(), vec![ast_node_to_tree(lhs), ast_node_to_tree(rhs)])
@@ -144,6 +1129,16 @@ fn node_3(name: &str, lhs: &AstNode, mid: &AstNode, rhs: &AstNode) -> Tree {
   Tree::Node(name.to_string(), vec![ast_node_to_tree(lhs), ast_node_to_tree(mid), ast_node_to_tree(rhs)])
 }
 
+///
+fn node_n(name: &str, lhs: &AstNode, rhs: &[AstNode]) -> Tree {
+  Tree::Node(name.to_string(), Some(lhs).into_iter().chain(rhs).map(ast_node_to_tree).collect())
+}
+
+///
+fn node_list(name: &str, nodes: &[AstNode]) -> Tree {
+  Tree::Node(name.to_string(), nodes.iter().map(ast_node_to_tree).collect())
+}
+
 ///
 fn node_and_leaf(name: &str, leaf: &str) -> Tree {
   Tree::Node(name.to_string(), vec![Tree::Leaf(vec![leaf.to_string()])])
@@ -167,4 +1162,427 @@ mod tests {
       AstNode::Null.to_string()
     )
   }
+
+  /// Visitor counting `Number` nodes, not descending into `if` branches when `prune_if` is set.
+  struct NumberCounter {
+    count: usize,
+    kinds: Vec<&'static str>,
+    prune_if: bool,
+  }
+
+  impl Visitor for NumberCounter {
+    fn visit(&mut self, node: &AstNode) -> bool {
+      self.kinds.push(node.kind());
+      if let AstNode::Number(_) = node {
+        self.count += 1;
+      }
+      !(self.prune_if && matches!(node, AstNode::If(..)))
+    }
+  }
+
+  #[test]
+  fn test_walk() {
+    let node = Parser::new("1 + 2 * 3 > 4 and if(5=6;7;8) in (9)").parse().unwrap();
+    let mut visitor = NumberCounter {
+      count: 0,
+      kinds: vec![],
+      prune_if: false,
+    };
+    walk(&node, &mut visitor);
+    assert_eq!(9, visitor.count);
+    assert_eq!(
+      vec!["And", "Gt", "Add", "Number", "Mul", "Number", "Number", "Number", "In", "If", "Eq", "Number", "Number", "Number", "Number", "Number"],
+      visitor.kinds
+    );
+    let mut visitor = NumberCounter {
+      count: 0,
+      kinds: vec![],
+      prune_if: true,
+    };
+    walk(&node, &mut visitor);
+    assert_eq!(5, visitor.count);
+  }
+
+  #[test]
+  fn test_from_str() {
+    let expected = AstNode::If(
+      Box::new(AstNode::Eq(Box::new(AstNode::Number(1)), Box::new(AstNode::Number(2)))),
+      Box::new(AstNode::Number(1)),
+      Box::new(AstNode::Number(2)),
+    );
+    assert_eq!(Ok(expected.clone()), "if(1=2;1;2)".parse::<AstNode>());
+    assert_eq!(Ok(expected), AstNode::try_from("if(1=2;1;2)"));
+    assert!("if(1=2;1;2".parse::<AstNode>().is_err());
+    let date = if cfg!(feature = "chrono") { ", `Date`" } else { "" };
+    assert_eq!(
+      format!(
+        "expected one of `Null`, `Number`, `Decimal`, `Str`, `Minus`, `Abs`, `Avg`, `Coalesce`, `Count`, `Max`, `Min`, `Round`, `Sum`, `Identifier`, `LeftParen`, `If`{} but encountered Eof at position 3",
+        date
+      ),
+      AstNode::try_from("1 +").unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn test_ast_to_tree_with() {
+    assert_eq!("\n       Null\n    ", ast_to_tree_with(&AstNode::Null, TreeStyle::ascii()));
+    assert_eq!("\n   Null\n", ast_to_tree_with(&AstNode::Null, TreeStyle { ascii: true, indent: 2 }));
+    assert_eq!(
+      r#"
+       Eq
+       |- Add
+       |  |- Number
+       |  |  `- `1`
+       |  `- Number
+       |     `- `2`
+       `- Null
+    "#,
+      ast_to_tree_with(&Parser::new("1 + 2 = null").parse().unwrap(), TreeStyle::ascii())
+    );
+  }
+
+  #[test]
+  fn test_ast_to_dot() {
+    let dot = ast_to_dot(&Parser::new(r#"if(1=2;"a";null)"#).parse().unwrap());
+    assert_eq!(6, dot.matches("[label=").count());
+    assert_eq!(5, dot.matches(" -> ").count());
+    assert_eq!(
+      r#"digraph AST {
+  n0 [label="If"];
+  n1 [label="Eq"];
+  n2 [label="Number 1"];
+  n1 -> n2;
+  n3 [label="Number 2"];
+  n1 -> n3;
+  n0 -> n1;
+  n4 [label="StrLiteral \"a\""];
+  n0 -> n4;
+  n5 [label="Null"];
+  n0 -> n5;
+}
+"#,
+      dot
+    );
+  }
+
+  #[test]
+  fn test_to_sexpr() {
+    let sexpr = |input: &str| Parser::new(input).parse().unwrap().to_sexpr();
+    assert_eq!("(if (eq (idx 1) (idx 2)) (idx 1) (idx 2))", sexpr("if(1=2;1;2)"));
+    assert_eq!(
+      "(if (or (and (gt (idx 1) 1.5) (is-not-null (idx 2))) (in (idx 3) \"a\" null)) (if (eq (idx 4) (idx 5)) (sum) (call f (idx 1))) (neg (idx 6)))",
+      sexpr(r#"if(1 > 1.5 and 2 is not null or 3 in ("a"; null); if(4 = 5; sum(); f(1)); - 6)"#)
+    );
+    assert_eq!("(add (idx 1) (mul (idx 2) (round (idx 3) 2)))", sexpr("1 + 2 * round(3; 2)"));
+  }
+
+  #[test]
+  fn test_iter() {
+    let node = Parser::new("if(1 = null; coalesce(null; 2; 3); null) in (4; null)").parse().unwrap();
+    assert_eq!(node.node_count(), node.iter().count());
+    assert_eq!(12, node.iter().count());
+    assert_eq!(4, node.iter().filter(|node| matches!(node, AstNode::Null)).count());
+    assert_eq!(
+      vec!["In", "If", "Eq", "Number", "Null", "Coalesce", "Null", "Number", "Number", "Null", "Number", "Null"],
+      node.iter().map(AstNode::kind).collect::<Vec<&str>>()
+    );
+  }
+
+  #[test]
+  fn test_fold() {
+    let node = Parser::new("if(3 > 12; max(7; 2); 5) + 1").parse().unwrap();
+    let max_index = |max: Option<IndexKey>, node: &AstNode| match node {
+      AstNode::Number(key) => max.max(Some(*key)),
+      _ => max,
+    };
+    assert_eq!(Some(12), node.fold(None, max_index));
+    assert_eq!(None, AstNode::Null.fold(None, max_index));
+    assert_eq!(
+      vec!["Add", "If", "Gt"],
+      node.fold(vec![], |mut kinds, node| {
+        kinds.push(node.kind());
+        kinds
+      })[..3]
+    );
+  }
+
+  #[test]
+  fn test_intern() {
+    let node = Parser::new("1 + 2.5 = 1 + 2.5").parse().unwrap();
+    let (lhs, rhs) = match node {
+      AstNode::Eq(lhs, rhs) => (*lhs, *rhs),
+      _ => unreachable!(),
+    };
+    let mut interner = AstInterner::new();
+    let lhs = interner.intern(lhs);
+    let rhs = interner.intern(rhs);
+    assert!(Rc::ptr_eq(&lhs, &rhs));
+    assert_eq!(1, interner.len());
+    assert!(Rc::ptr_eq(&lhs, &interner.intern(Parser::new("1 + 2.50").parse().unwrap())));
+    let other = interner.intern(Parser::new("1 + 2.6").parse().unwrap());
+    assert!(!Rc::ptr_eq(&lhs, &other));
+    assert_eq!(2, interner.len());
+  }
+
+  #[test]
+  fn test_metrics() {
+    assert_eq!(1, AstNode::Null.depth());
+    assert_eq!(1, AstNode::Null.node_count());
+    let node = Parser::new("if(1=2;1;2)").parse().unwrap();
+    assert_eq!(3, node.depth());
+    assert_eq!(6, node.node_count());
+    let node = Parser::new("1 + 2 * 3 > 4 and if(5=6;7;8) in (9)").parse().unwrap();
+    assert_eq!(5, node.depth());
+    assert_eq!(16, node.node_count());
+  }
+
+  #[test]
+  fn test_collect_indices() {
+    let node = Parser::new("if(1>2 and 3=null; 5; 1)").parse().unwrap();
+    assert_eq!(BTreeSet::from([1, 2, 3, 5]), node.collect_indices());
+    assert!(Parser::new("1.5 + null").parse().unwrap().collect_indices().is_empty());
+  }
+
+  #[test]
+  fn test_canonicalize() {
+    let kleene = EvaluatorConfig {
+      three_valued_logic: true,
+      ..EvaluatorConfig::default()
+    };
+    let canonical = |input: &str| input.parse::<AstNode>().unwrap().canonicalize(&EvaluatorConfig::default());
+    let canonical_kleene = |input: &str| input.parse::<AstNode>().unwrap().canonicalize(&kleene);
+    assert_eq!(canonical_kleene("1=2 and 3>4"), canonical_kleene("3>4 and 1=2"));
+    assert_eq!(canonical_kleene("2=1 or 3+4*5=6"), canonical_kleene("6=5*4+3 or 1=2"));
+    assert_ne!(canonical("1=2 and 3>4"), canonical("3>4 and 1=2"));
+    assert_eq!(canonical("2=1 or 3+4*5=6"), canonical("2=1 or 6=5*4+3"));
+    assert_eq!(canonical("1<>2 xor true"), canonical("true xor 2<>1"));
+    assert_ne!(canonical("1>2"), canonical("2<1"));
+    assert_ne!(canonical("1-2=3"), canonical("2-1=3"));
+    assert_eq!("2>1", canonical("2>1").to_source());
+    assert_eq!("if(1=2;2;1)", canonical("if(2=1;2;1)").to_source());
+    let values = crate::IndexedValues::from([(2, Value::Number(Decimal::ONE)), (3, Value::Number(Decimal::new(2, 0)))]);
+    for config in [EvaluatorConfig::default(), kleene] {
+      for input in ["1 > 2 and 3 = 4", "3 = 4 and 1 > 2", "1 > 2 or 2 < 3", "2 < 3 or 1 > 2"] {
+        let node: AstNode = input.parse().unwrap();
+        let expected = crate::evaluator::build_evaluator_with_config(&node, &config).unwrap()(&values);
+        let actual = crate::evaluator::build_evaluator_with_config(&node.canonicalize(&config), &config).unwrap()(&values);
+        assert_eq!(expected, actual, "{}", input);
+      }
+    }
+  }
+
+  #[test]
+  fn test_dead_branches() {
+    let node: AstNode = "if(1=1; 1; 2)".parse().unwrap();
+    assert_eq!(vec![&AstNode::Number(2)], node.dead_branches());
+    let node: AstNode = "if(1>2; 3; if(true or 1=2; 4; 5)) + if(null > 1.0; 6; 7)".parse().unwrap();
+    assert_eq!(vec![&AstNode::Number(5), &AstNode::Number(6), &AstNode::Number(7)], node.dead_branches());
+    assert!("if(1=2; 1; 2)".parse::<AstNode>().unwrap().dead_branches().is_empty());
+  }
+
+  #[test]
+  fn test_index_usage() {
+    let node = Parser::new("if(1>2 and 1<3; 1; 4)").parse().unwrap();
+    assert_eq!(HashMap::from([(1, 3), (2, 1), (3, 1), (4, 1)]), node.index_usage());
+    assert!(AstNode::Null.index_usage().is_empty());
+  }
+
+  #[test]
+  fn test_operator_histogram() {
+    let node = Parser::new("(1=2 or 3>4) and (5=null or abs(6)=sum())").parse().unwrap();
+    assert_eq!(
+      HashMap::from([("And", 1), ("Or", 2), ("Eq", 3), ("Gt", 1), ("Abs", 1), ("Sum", 1)]),
+      node.operator_histogram()
+    );
+    assert!(Parser::new("1.5").parse().unwrap().operator_histogram().is_empty());
+  }
+
+  #[test]
+  fn test_remap_indices() {
+    let node = Parser::new("if(1>2 and 3=null; 5; 1)").parse().unwrap();
+    let remapped = node.remap_indices(|key| key + 10);
+    assert_eq!(BTreeSet::from([11, 12, 13, 15]), remapped.collect_indices());
+    assert_eq!(Parser::new("if(11>12 and 13=null; 15; 11)").parse().unwrap(), remapped);
+    assert_eq!(AstNode::Null, AstNode::Null.remap_indices(|key| key + 10));
+  }
+
+  #[test]
+  fn test_substitute() {
+    let node = Parser::new("if(1 > 2.0; 3; 1)").parse().unwrap();
+    let substituted = node.substitute(1, Value::Number(Decimal::new(5, 0)));
+    assert_eq!(Parser::new("if(5.0 > 2.0; 3; 5.0)").parse().unwrap(), substituted);
+    assert_eq!(AstNode::Number(3), crate::optimize(substituted));
+    assert_eq!(AstNode::Literal(Decimal::ONE), crate::optimize(node.substitute(1, Value::Int(1))));
+    assert_eq!(AstNode::Null, crate::optimize(node.substitute(1, Value::Null)));
+    assert_eq!(node, node.substitute(9, Value::Bool(true)));
+  }
+
+  #[test]
+  fn test_to_source() {
+    let source = |input: &str| Parser::new(input).parse().unwrap().to_source();
+    assert_eq!("if(1=2;1;2)", source("if ( 1 = 2 ; 1 ; 2 )"));
+    assert_eq!("if(1>=2;1;null)", source("if(1 >= 2; 1)"));
+    assert_eq!("if(1=2;1;if(3<4;2;3))", source("if(1=2;1;elseif 3<4;2;else 3)"));
+    assert_eq!("1+2*3", source("1 + (2 * 3)"));
+    assert_eq!("(1+2)*3", source("(1 + 2) * 3"));
+    assert_eq!("1-(2-3)", source("1 - (2 - 3)"));
+    assert_eq!("1-2-3", source("(1 - 2) - 3"));
+    assert_eq!("1/(2*3)", source("1 / (2 * 3)"));
+    assert_eq!("1=1 or 2=2 and 3=3", source("1=1 or (2=2 and 3=3)"));
+    assert_eq!("(1=1 or 2=2) and 3=3", source("(1=1 or 2=2) and 3=3"));
+    assert_eq!("1=1 xor (2=2 or true)", source("1=1 xor (2=2 or true)"));
+    assert_eq!("1 between 2.0 and -3.5 and 4 in (1;2)", source("1 between 2.0 and -3.5 and 4 in (1; 2)"));
+    assert_eq!("1 is not null", source("1 is not null"));
+    assert_eq!(
+      "round(abs(-1);2)+coalesce(null;max(1;min(2));f())",
+      source("round(abs(-1);2)+coalesce(null;max(1;min(2));f())")
+    );
+    assert_eq!(r#""a\"b\\c"<"d""#, source(r#""a\"b\\c" < "d""#));
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn test_serde() {
+    let node = AstNode::If(
+      Box::new(AstNode::Or(
+        Box::new(AstNode::And(
+          Box::new(AstNode::IsNull(Box::new(AstNode::Number(1)))),
+          Box::new(AstNode::Bool(true)),
+        )),
+        Box::new(AstNode::Eq(Box::new(AstNode::Number(2)), Box::new(AstNode::Null))),
+      )),
+      Box::new(AstNode::Literal(Decimal::new(110, 2))),
+      Box::new(AstNode::Null),
+    );
+    let json = serde_json::to_string(&node).unwrap();
+    assert_eq!(
+      r#"{"If":[{"Or":[{"And":[{"IsNull":{"Number":1}},{"Bool":true}]},{"Eq":[{"Number":2},"Null"]}]},{"Literal":"1.10"},"Null"]}"#,
+      json
+    );
+    assert_eq!(node, serde_json::from_str::<AstNode>(&json).unwrap());
+    let node = AstNode::In(
+      Box::new(AstNode::Call("f".to_string(), vec![AstNode::StrLiteral("a".to_string()), AstNode::Sum])),
+      vec![AstNode::Max(vec![AstNode::Number(1), AstNode::Count]), AstNode::Neg(Box::new(AstNode::Avg))],
+    );
+    assert_eq!(node, serde_json::from_str::<AstNode>(&serde_json::to_string(&node).unwrap()).unwrap());
+    assert!(serde_json::from_str::<AstNode>(r#"{"Pow":[{"Number":1},{"Number":2}]}"#).is_err());
+    assert!(serde_json::from_str::<AstNode>(r#"{"Literal":"abc"}"#).is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn test_serde_variant_name() {
+    use serde::de::value::{Error, StrDeserializer, U64Deserializer};
+    for (index, variant) in VARIANTS.iter().enumerate() {
+      let VariantName(name) = VariantName::deserialize(U64Deserializer::<Error>::new(index as u64)).unwrap();
+      assert_eq!(*variant, name);
+      let VariantName(name) = VariantName::deserialize(StrDeserializer::<Error>::new(variant)).unwrap();
+      assert_eq!(*variant, name);
+    }
+    assert_eq!(3, AstNode::Avg.variant_index());
+    assert_eq!(25, AstNode::Null.variant_index());
+    assert!(VariantName::deserialize(U64Deserializer::<Error>::new(34)).is_err());
+    assert!(VariantName::deserialize(StrDeserializer::<Error>::new("Pow")).is_err());
+  }
+
+  /// Deterministic pseudo-random generator (xorshift) used for generating test trees.
+  struct Random(u64);
+
+  impl Random {
+    /// Returns next pseudo-random number less than `bound`.
+    fn next(&mut self, bound: u64) -> u64 {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      self.0 % bound
+    }
+
+    /// Returns boxed pseudo-random node.
+    fn boxed(&mut self, depth: usize) -> Box<AstNode> {
+      Box::new(self.node(depth))
+    }
+
+    /// Returns list of pseudo-random nodes.
+    fn list(&mut self, depth: usize) -> Vec<AstNode> {
+      (0..self.next(4)).map(|_| self.node(depth)).collect()
+    }
+
+    /// Returns pseudo-random node, nested up to specified depth.
+    fn node(&mut self, depth: usize) -> AstNode {
+      let d = depth.saturating_sub(1);
+      match if depth == 0 { self.next(8) } else { self.next(34) } {
+        0 => AstNode::Avg,
+        1 => AstNode::Bool(self.next(2) == 1),
+        2 => AstNode::Count,
+        3 => AstNode::Literal(Decimal::new(self.next(100_000) as i64 - 50_000, self.next(4) as u32)),
+        4 => AstNode::Null,
+        5 => AstNode::Number(self.next(70_000) as IndexKey),
+        6 => AstNode::StrLiteral(["", "a", "zażółć", "\"\\"][self.next(4) as usize].to_string()),
+        7 => AstNode::Sum,
+        8 => AstNode::Abs(self.boxed(d)),
+        9 => AstNode::Add(self.boxed(d), self.boxed(d)),
+        10 => AstNode::And(self.boxed(d), self.boxed(d)),
+        11 => AstNode::Between(self.boxed(d), self.boxed(d), self.boxed(d)),
+        12 => AstNode::Call(["f", "g_1"][self.next(2) as usize].to_string(), self.list(d)),
+        13 => AstNode::Coalesce(self.list(d)),
+        14 => AstNode::Div(self.boxed(d), self.boxed(d)),
+        15 => AstNode::Eq(self.boxed(d), self.boxed(d)),
+        16 => AstNode::Ge(self.boxed(d), self.boxed(d)),
+        17 => AstNode::Gt(self.boxed(d), self.boxed(d)),
+        18 => AstNode::If(self.boxed(d), self.boxed(d), self.boxed(d)),
+        19 => AstNode::In(self.boxed(d), self.list(d)),
+        20 => AstNode::IsNotNull(self.boxed(d)),
+        21 => AstNode::IsNull(self.boxed(d)),
+        22 => AstNode::Le(self.boxed(d), self.boxed(d)),
+        23 => AstNode::Lt(self.boxed(d), self.boxed(d)),
+        24 => AstNode::Max(self.list(d)),
+        25 => AstNode::Min(self.list(d)),
+        26 => AstNode::Mul(self.boxed(d), self.boxed(d)),
+        27 => AstNode::Neg(self.boxed(d)),
+        28 => AstNode::Nq(self.boxed(d), self.boxed(d)),
+        29 => AstNode::Or(self.boxed(d), self.boxed(d)),
+        30 => AstNode::Round(self.boxed(d), self.boxed(d)),
+        31 => AstNode::Sub(self.boxed(d), self.boxed(d)),
+        32 => AstNode::Xor(self.boxed(d), self.boxed(d)),
+        #[cfg(feature = "chrono")]
+        _ => AstNode::DateLiteral(NaiveDate::from_ymd_opt(2000 + self.next(100) as i32, 1 + self.next(12) as u32, 1 + self.next(28) as u32).unwrap()),
+        #[cfg(not(feature = "chrono"))]
+        _ => AstNode::Null,
+      }
+    }
+  }
+
+  #[test]
+  fn test_bytes() {
+    let node = AstNode::Add(Box::new(AstNode::Number(258)), Box::new(AstNode::Literal(Decimal::new(15, 1))));
+    assert_eq!(vec![1, 26, 2, 1, 19, 3, 0, 0, 0, b'1', b'.', b'5'], node.to_bytes());
+    assert_eq!(Ok(node), AstNode::from_bytes(&[1, 26, 2, 1, 19, 3, 0, 0, 0, b'1', b'.', b'5']));
+    assert_eq!("unexpected end of data at position 2", AstNode::from_bytes(&[27, 26]).unwrap_err().to_string());
+    assert_eq!(
+      "unexpected trailing bytes at position 1",
+      AstNode::from_bytes(&[25, 25]).unwrap_err().to_string()
+    );
+    assert_eq!("invalid node tag 34 at position 0", AstNode::from_bytes(&[34]).unwrap_err().to_string());
+    assert_eq!("invalid boolean value 2 at position 1", AstNode::from_bytes(&[5, 2]).unwrap_err().to_string());
+    assert_eq!(
+      "maximum nesting depth exceeded at position 129",
+      AstNode::from_bytes(&[27; 200]).unwrap_err().to_string()
+    );
+    assert!(AstNode::from_bytes(&[19, 3, 0, 0, 0, b'a', b'b', b'c']).is_err());
+    assert!(AstNode::from_bytes(&[30, 1, 0, 0, 0, 0xff]).is_err());
+    assert!(AstNode::from_bytes(&[7, 255, 255, 255, 255]).is_err());
+  }
+
+  #[test]
+  fn test_bytes_round_trip() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for _ in 0..1000 {
+      let node = random.node(5);
+      let bytes = node.to_bytes();
+      assert_eq!(Ok(node.clone()), AstNode::from_bytes(&bytes), "{:?}", node);
+      for length in 0..bytes.len() {
+        assert!(AstNode::from_bytes(&bytes[..length]).is_err());
+      }
+    }
+  }
 }
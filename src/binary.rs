@@ -0,0 +1,255 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Compact binary encoding of the AST.
+//!
+//! Every node is encoded in preorder as a single tag byte followed by its payload.
+//! The tag is the position of the node kind in the list of variants
+//! (`Abs` = 0, `Add` = 1, ..., `Xor` = 33, in order of declaration of [AstNode], which is not alphabetical:
+//! `Or` = 24 precedes `Null` = 25, `Number` = 26, `Neg` = 27 and `Nq` = 28).
+//! All integers are little-endian; lengths and counts are `u32` values.
+//!
+//! | Node kind                                               | Payload                                    |
+//! |---------------------------------------------------------|--------------------------------------------|
+//! | `Avg`, `Count`, `Null`, `Sum`                           | none                                       |
+//! | `Bool`                                                  | one byte, `0` or `1`                       |
+//! | `Number`                                                | index as `u16`                             |
+//! | `Literal`, `StrLiteral`, `DateLiteral`                  | text length followed by UTF-8 text         |
+//! | `Abs`, `IsNotNull`, `IsNull`, `Neg`                     | operand                                    |
+//! | binary operators and `Round`                            | left operand followed by right operand     |
+//! | `Between`, `If`                                         | three operands                             |
+//! | `Coalesce`, `Max`, `Min`                                | operand count followed by operands         |
+//! | `In`                                                    | tested value, item count followed by items |
+//! | `Call`                                                  | name as text, argument count, arguments    |
+//!
+//! Decimal literals are stored as text to preserve their scale, date literals as `YYYY-MM-DD`.
+//! Decoding rejects nodes nested deeper than [DEFAULT_MAX_DEPTH](crate::DEFAULT_MAX_DEPTH).
+
+use crate::ast::{AstNode, VARIANTS};
+use crate::errors::{Result, SecelError};
+use crate::evaluator::IndexKey;
+use crate::parser::DEFAULT_MAX_DEPTH;
+#[cfg(feature = "chrono")]
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Encodes the node into bytes.
+pub(crate) fn encode(node: &AstNode) -> Vec<u8> {
+  let mut bytes = vec![];
+  write_node(&mut bytes, node);
+  bytes
+}
+
+/// Decodes the node from bytes, all bytes must be consumed.
+pub(crate) fn decode(bytes: &[u8]) -> Result<AstNode> {
+  let mut reader = Reader { bytes, position: 0 };
+  let node = reader.node(0)?;
+  if reader.position < bytes.len() {
+    return Err(SecelError::at("unexpected trailing bytes", reader.position));
+  }
+  Ok(node)
+}
+
+/// Writes the tag and the payload of the node.
+fn write_node(bytes: &mut Vec<u8>, node: &AstNode) {
+  let tag = node.variant_index() as u8;
+  bytes.push(tag);
+  match node {
+    AstNode::Avg | AstNode::Count | AstNode::Null | AstNode::Sum => {}
+    AstNode::Bool(value) => bytes.push(u8::from(*value)),
+    AstNode::Number(index) => bytes.extend_from_slice(&index.to_le_bytes()),
+    AstNode::Literal(value) => write_text(bytes, &value.to_string()),
+    AstNode::StrLiteral(value) => write_text(bytes, value),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(value) => write_text(bytes, &value.to_string()),
+    AstNode::Call(name, arguments) => {
+      write_text(bytes, name);
+      write_list(bytes, arguments);
+    }
+    AstNode::Coalesce(operands) | AstNode::Max(operands) | AstNode::Min(operands) => write_list(bytes, operands),
+    AstNode::In(lhs, items) => {
+      write_node(bytes, lhs);
+      write_list(bytes, items);
+    }
+    other => {
+      for child in other.children() {
+        write_node(bytes, child);
+      }
+    }
+  }
+}
+
+/// Writes the length-prefixed text.
+fn write_text(bytes: &mut Vec<u8>, text: &str) {
+  bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+  bytes.extend_from_slice(text.as_bytes());
+}
+
+/// Writes the count-prefixed list of nodes.
+fn write_list(bytes: &mut Vec<u8>, nodes: &[AstNode]) {
+  bytes.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+  for node in nodes {
+    write_node(bytes, node);
+  }
+}
+
+/// Reader of encoded nodes.
+struct Reader<'a> {
+  /// Encoded bytes.
+  bytes: &'a [u8],
+  /// Position of the next byte to be read.
+  position: usize,
+}
+
+impl<'a> Reader<'a> {
+  /// Reads the specified number of bytes.
+  fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+    match self.position.checked_add(count) {
+      Some(end) if end <= self.bytes.len() => {
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+      }
+      _ => Err(SecelError::at("unexpected end of data", self.bytes.len())),
+    }
+  }
+
+  /// Reads single byte.
+  fn byte(&mut self) -> Result<u8> {
+    Ok(self.take(1)?[0])
+  }
+
+  /// Reads length or count.
+  fn length(&mut self) -> Result<usize> {
+    let bytes = self.take(4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+  }
+
+  /// Reads length-prefixed text.
+  fn text(&mut self) -> Result<String> {
+    let position = self.position;
+    let length = self.length()?;
+    String::from_utf8(self.take(length)?.to_vec()).map_err(|_| SecelError::at("invalid UTF-8 text", position))
+  }
+
+  /// Reads single operand.
+  fn boxed(&mut self, depth: usize) -> Result<Box<AstNode>> {
+    Ok(Box::new(self.node(depth + 1)?))
+  }
+
+  /// Reads the operand of a node with single operand.
+  fn unary(&mut self, depth: usize, node: fn(Box<AstNode>) -> AstNode) -> Result<AstNode> {
+    Ok(node(self.boxed(depth)?))
+  }
+
+  /// Reads the operands of a node with two operands.
+  fn binary(&mut self, depth: usize, node: fn(Box<AstNode>, Box<AstNode>) -> AstNode) -> Result<AstNode> {
+    Ok(node(self.boxed(depth)?, self.boxed(depth)?))
+  }
+
+  /// Reads the operands of a node with three operands.
+  fn ternary(&mut self, depth: usize, node: fn(Box<AstNode>, Box<AstNode>, Box<AstNode>) -> AstNode) -> Result<AstNode> {
+    Ok(node(self.boxed(depth)?, self.boxed(depth)?, self.boxed(depth)?))
+  }
+
+  /// Reads count-prefixed list of nodes.
+  fn list(&mut self, depth: usize) -> Result<Vec<AstNode>> {
+    let count = self.length()?;
+    // every node takes at least one byte, this protects against allocating for bogus counts
+    let mut nodes = Vec::with_capacity(count.min(self.bytes.len() - self.position));
+    for _ in 0..count {
+      nodes.push(self.node(depth + 1)?);
+    }
+    Ok(nodes)
+  }
+
+  /// Reads the node at the specified nesting depth.
+  fn node(&mut self, depth: usize) -> Result<AstNode> {
+    let position = self.position;
+    if depth > DEFAULT_MAX_DEPTH {
+      return Err(SecelError::at("maximum nesting depth exceeded", position));
+    }
+    let tag = self.byte()?;
+    let kind = match VARIANTS.get(tag as usize) {
+      Some(kind) => *kind,
+      None => return Err(SecelError::at(&format!("invalid node tag {}", tag), position)),
+    };
+    match kind {
+      "Abs" => self.unary(depth, AstNode::Abs),
+      "Add" => self.binary(depth, AstNode::Add),
+      "And" => self.binary(depth, AstNode::And),
+      "Avg" => Ok(AstNode::Avg),
+      "Between" => self.ternary(depth, AstNode::Between),
+      "Bool" => match self.byte()? {
+        0 => Ok(AstNode::Bool(false)),
+        1 => Ok(AstNode::Bool(true)),
+        other => Err(SecelError::at(&format!("invalid boolean value {}", other), position + 1)),
+      },
+      "Call" => Ok(AstNode::Call(self.text()?, self.list(depth)?)),
+      "Coalesce" => Ok(AstNode::Coalesce(self.list(depth)?)),
+      "Count" => Ok(AstNode::Count),
+      #[cfg(feature = "chrono")]
+      "DateLiteral" => {
+        let text = self.text()?;
+        NaiveDate::from_str(&text)
+          .map(AstNode::DateLiteral)
+          .map_err(|_| SecelError::at(&format!("invalid date '{}'", text), position))
+      }
+      "Div" => self.binary(depth, AstNode::Div),
+      "Eq" => self.binary(depth, AstNode::Eq),
+      "Ge" => self.binary(depth, AstNode::Ge),
+      "Gt" => self.binary(depth, AstNode::Gt),
+      "If" => self.ternary(depth, AstNode::If),
+      "In" => Ok(AstNode::In(self.boxed(depth)?, self.list(depth)?)),
+      "IsNotNull" => self.unary(depth, AstNode::IsNotNull),
+      "IsNull" => self.unary(depth, AstNode::IsNull),
+      "Le" => self.binary(depth, AstNode::Le),
+      "Literal" => {
+        let text = self.text()?;
+        Decimal::from_str(&text)
+          .map(AstNode::Literal)
+          .map_err(|_| SecelError::at(&format!("invalid decimal number '{}'", text), position))
+      }
+      "Lt" => self.binary(depth, AstNode::Lt),
+      "Max" => Ok(AstNode::Max(self.list(depth)?)),
+      "Min" => Ok(AstNode::Min(self.list(depth)?)),
+      "Mul" => self.binary(depth, AstNode::Mul),
+      "Neg" => self.unary(depth, AstNode::Neg),
+      "Null" => Ok(AstNode::Null),
+      "Number" => {
+        let bytes = self.take(2)?;
+        Ok(AstNode::Number(IndexKey::from_le_bytes([bytes[0], bytes[1]])))
+      }
+      "Nq" => self.binary(depth, AstNode::Nq),
+      "Or" => self.binary(depth, AstNode::Or),
+      "Round" => self.binary(depth, AstNode::Round),
+      "StrLiteral" => Ok(AstNode::StrLiteral(self.text()?)),
+      "Sub" => self.binary(depth, AstNode::Sub),
+      "Sum" => Ok(AstNode::Sum),
+      "Xor" => self.binary(depth, AstNode::Xor),
+      other => Err(SecelError::at(&format!("unsupported node kind '{}'", other), position)),
+    }
+  }
+}
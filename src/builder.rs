@@ -0,0 +1,221 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Builder functions for constructing abstract syntax trees.
+//!
+//! Functions are named after the operators and functions of the language
+//! (suffixed with `_` when the name is a Rust keyword) and box the operands internally,
+//! so `if_(eq(idx(1), idx(2)), idx(1), idx(2))` builds the same tree as parsing `if(1=2;1;2)`.
+
+use crate::ast::AstNode;
+use crate::IndexKey;
+use rust_decimal::Decimal;
+
+/// Builds the node reading the value of specified index.
+pub fn idx(key: IndexKey) -> AstNode {
+  AstNode::Number(key)
+}
+
+/// Builds `null` node.
+pub fn null() -> AstNode {
+  AstNode::Null
+}
+
+/// Builds boolean literal node.
+pub fn bool_(value: bool) -> AstNode {
+  AstNode::Bool(value)
+}
+
+/// Builds decimal number literal node.
+pub fn num(value: Decimal) -> AstNode {
+  AstNode::Literal(value)
+}
+
+/// Builds string literal node.
+pub fn str_(value: &str) -> AstNode {
+  AstNode::StrLiteral(value.to_string())
+}
+
+/// Builds `if` expression node.
+pub fn if_(condition: AstNode, then: AstNode, otherwise: AstNode) -> AstNode {
+  AstNode::If(Box::new(condition), Box::new(then), Box::new(otherwise))
+}
+
+/// Builds `=` operator node.
+pub fn eq(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Eq(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `<>` operator node.
+pub fn nq(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Nq(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `>` operator node.
+pub fn gt(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Gt(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `>=` operator node.
+pub fn ge(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Ge(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `<` operator node.
+pub fn lt(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Lt(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `<=` operator node.
+pub fn le(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Le(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `between` operator node.
+pub fn between(value: AstNode, lower: AstNode, upper: AstNode) -> AstNode {
+  AstNode::Between(Box::new(value), Box::new(lower), Box::new(upper))
+}
+
+/// Builds `in` operator node.
+pub fn in_(value: AstNode, list: Vec<AstNode>) -> AstNode {
+  AstNode::In(Box::new(value), list)
+}
+
+/// Builds `is null` test node.
+pub fn is_null(value: AstNode) -> AstNode {
+  AstNode::IsNull(Box::new(value))
+}
+
+/// Builds `is not null` test node.
+pub fn is_not_null(value: AstNode) -> AstNode {
+  AstNode::IsNotNull(Box::new(value))
+}
+
+/// Builds `and` operator node.
+pub fn and(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::And(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `or` operator node.
+pub fn or(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Or(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `xor` operator node.
+pub fn xor(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Xor(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `+` operator node.
+pub fn add(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Add(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds binary `-` operator node.
+pub fn sub(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Sub(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `*` operator node.
+pub fn mul(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Mul(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds `/` operator node.
+pub fn div(lhs: AstNode, rhs: AstNode) -> AstNode {
+  AstNode::Div(Box::new(lhs), Box::new(rhs))
+}
+
+/// Builds unary `-` operator node.
+pub fn neg(value: AstNode) -> AstNode {
+  AstNode::Neg(Box::new(value))
+}
+
+/// Builds `abs` function node.
+pub fn abs(value: AstNode) -> AstNode {
+  AstNode::Abs(Box::new(value))
+}
+
+/// Builds `round` function node.
+pub fn round(value: AstNode, scale: AstNode) -> AstNode {
+  AstNode::Round(Box::new(value), Box::new(scale))
+}
+
+/// Builds `coalesce` function node.
+pub fn coalesce(values: Vec<AstNode>) -> AstNode {
+  AstNode::Coalesce(values)
+}
+
+/// Builds `max` function node.
+pub fn max(values: Vec<AstNode>) -> AstNode {
+  AstNode::Max(values)
+}
+
+/// Builds `min` function node.
+pub fn min(values: Vec<AstNode>) -> AstNode {
+  AstNode::Min(values)
+}
+
+/// Builds the node calling user-registered function.
+pub fn call(name: &str, arguments: Vec<AstNode>) -> AstNode {
+  AstNode::Call(name.to_string(), arguments)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+
+  fn parsed(input: &str) -> AstNode {
+    Parser::new(input).parse().unwrap()
+  }
+
+  #[test]
+  fn test_if() {
+    assert_eq!(parsed("if(1=2;1;2)"), if_(eq(idx(1), idx(2)), idx(1), idx(2)));
+    assert_eq!(parsed("if(1>2;1)"), if_(gt(idx(1), idx(2)), idx(1), null()));
+  }
+
+  #[test]
+  fn test_operators() {
+    assert_eq!(
+      parsed(r#"1 + 2 * 1.5 >= - 3 and (4 in (5; "a") or true xor 6 is not null)"#),
+      and(
+        ge(add(idx(1), mul(idx(2), num(Decimal::new(15, 1)))), neg(idx(3))),
+        xor(or(in_(idx(4), vec![idx(5), str_("a")]), bool_(true)), is_not_null(idx(6)))
+      )
+    );
+    assert_eq!(
+      parsed("round(abs(1); 2) - coalesce(max(1; 2); min(3); f()) / 4 between 5 and 6"),
+      between(
+        sub(
+          round(abs(idx(1)), num(Decimal::new(2, 0))),
+          div(coalesce(vec![max(vec![idx(1), idx(2)]), min(vec![idx(3)]), call("f", vec![])]), idx(4))
+        ),
+        idx(5),
+        idx(6)
+      )
+    );
+  }
+}
@@ -0,0 +1,274 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Enum-based compiled expression implementation.
+//!
+//! Compiled expression mirrors the [AstNode] with literals converted into values and
+//! function arguments validated, it is evaluated using direct match dispatch.
+
+use crate::ast::AstNode;
+use crate::errors::Result;
+use crate::evaluator::{check_depth, err_unsupported_call, round_scale, Context, EvaluatorConfig, IndexKey, IndexedValues, DEFAULT_MAX_EVALUATOR_DEPTH};
+use crate::semantics::{self, avg_numbers, count_numbers, sum_numbers, Equality};
+use crate::values::Value;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Compiled expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compiled {
+  /// Compiled `abs` function.
+  Abs(Box<Compiled>),
+  /// Compiled arithmetic operator `+`.
+  Add(Box<Compiled>, Box<Compiled>),
+  /// Compiled conjunction operator `and`.
+  And(Box<Compiled>, Box<Compiled>),
+  /// Compiled `avg()` function.
+  Avg,
+  /// Compiled range operator `between`, with the tested value, lower and upper bound.
+  Between(Box<Compiled>, Box<Compiled>, Box<Compiled>),
+  /// Compiled `coalesce` function.
+  Coalesce(Vec<Compiled>),
+  /// Compiled `count()` function.
+  Count,
+  /// Constant value, compiled from literals, `true`, `false` and `null`.
+  Const(Value),
+  /// Compiled arithmetic operator `/`.
+  Div(Box<Compiled>, Box<Compiled>),
+  /// Compiled comparison operator `=`.
+  Eq(Box<Compiled>, Box<Compiled>),
+  /// Compiled comparison operator `>=`.
+  Ge(Box<Compiled>, Box<Compiled>),
+  /// Compiled comparison operator `>`.
+  Gt(Box<Compiled>, Box<Compiled>),
+  /// Compiled `if` expression, with the condition, true-branch and false-branch.
+  If(Box<Compiled>, Box<Compiled>, Box<Compiled>),
+  /// Compiled list membership operator `in`.
+  In(Box<Compiled>, Vec<Compiled>),
+  /// Value stored under specified index.
+  Index(IndexKey),
+  /// Compiled `is not null` test.
+  IsNotNull(Box<Compiled>),
+  /// Compiled `is null` test.
+  IsNull(Box<Compiled>),
+  /// Compiled comparison operator `<=`.
+  Le(Box<Compiled>, Box<Compiled>),
+  /// Compiled comparison operator `<`.
+  Lt(Box<Compiled>, Box<Compiled>),
+  /// Compiled `max` function.
+  Max(Vec<Compiled>),
+  /// Compiled `min` function.
+  Min(Vec<Compiled>),
+  /// Compiled arithmetic operator `*`.
+  Mul(Box<Compiled>, Box<Compiled>),
+  /// Compiled unary arithmetic operator `-`.
+  Neg(Box<Compiled>),
+  /// Compiled comparison operator `<>`.
+  Nq(Box<Compiled>, Box<Compiled>),
+  /// Compiled disjunction operator `or`.
+  Or(Box<Compiled>, Box<Compiled>),
+  /// Compiled `round` function, with the validated scale.
+  Round(Box<Compiled>, u32),
+  /// Compiled arithmetic operator `-`.
+  Sub(Box<Compiled>, Box<Compiled>),
+  /// Compiled `sum()` function.
+  Sum,
+  /// Compiled exclusive disjunction operator `xor`.
+  Xor(Box<Compiled>, Box<Compiled>),
+}
+
+/// Compiles given [AstNode], calls of user-registered functions are not supported and reported as errors.
+/// Compiled expression is evaluated recursively, so trees deeper than [DEFAULT_MAX_EVALUATOR_DEPTH] are reported as errors.
+pub fn compile(node: &AstNode) -> Result<Compiled> {
+  check_depth(node, DEFAULT_MAX_EVALUATOR_DEPTH)?;
+  compile_node(node)
+}
+
+/// Compiles the node, dispatching on node type.
+fn compile_node(node: &AstNode) -> Result<Compiled> {
+  Ok(match node {
+    AstNode::Abs(mhs) => Compiled::Abs(compile_box(mhs)?),
+    AstNode::Add(lhs, rhs) => Compiled::Add(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::And(lhs, rhs) => Compiled::And(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Between(lhs, mhs, rhs) => Compiled::Between(compile_box(lhs)?, compile_box(mhs)?, compile_box(rhs)?),
+    AstNode::Avg => Compiled::Avg,
+    AstNode::Bool(mhs) => Compiled::Const(Value::Bool(*mhs)),
+    AstNode::Call(name, _) => return Err(err_unsupported_call(name)),
+    AstNode::Coalesce(mhs) => Compiled::Coalesce(compile_list(mhs)?),
+    AstNode::Count => Compiled::Count,
+    AstNode::Div(lhs, rhs) => Compiled::Div(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Eq(lhs, rhs) => Compiled::Eq(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Ge(lhs, rhs) => Compiled::Ge(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Gt(lhs, rhs) => Compiled::Gt(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::If(mhs, lhs, rhs) => Compiled::If(compile_box(mhs)?, compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::In(lhs, rhs) => Compiled::In(compile_box(lhs)?, compile_list(rhs)?),
+    AstNode::IsNotNull(mhs) => Compiled::IsNotNull(compile_box(mhs)?),
+    AstNode::IsNull(mhs) => Compiled::IsNull(compile_box(mhs)?),
+    AstNode::Le(lhs, rhs) => Compiled::Le(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Literal(mhs) => Compiled::Const(Value::Number(*mhs)),
+    AstNode::Lt(lhs, rhs) => Compiled::Lt(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Max(mhs) => Compiled::Max(compile_list(mhs)?),
+    AstNode::Min(mhs) => Compiled::Min(compile_list(mhs)?),
+    AstNode::Mul(lhs, rhs) => Compiled::Mul(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Neg(mhs) => Compiled::Neg(compile_box(mhs)?),
+    AstNode::Nq(lhs, rhs) => Compiled::Nq(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Null => Compiled::Const(Value::Null),
+    AstNode::Number(mhs) => Compiled::Index(*mhs),
+    AstNode::Or(lhs, rhs) => Compiled::Or(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Round(lhs, rhs) => Compiled::Round(compile_box(lhs)?, round_scale(rhs)?),
+    AstNode::StrLiteral(mhs) => Compiled::Const(Value::Str(mhs.clone())),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(mhs) => Compiled::Const(Value::Date(*mhs)),
+    AstNode::Sub(lhs, rhs) => Compiled::Sub(compile_box(lhs)?, compile_box(rhs)?),
+    AstNode::Sum => Compiled::Sum,
+    AstNode::Xor(lhs, rhs) => Compiled::Xor(compile_box(lhs)?, compile_box(rhs)?),
+  })
+}
+
+/// Compiles the node into boxed compiled expression.
+fn compile_box(node: &AstNode) -> Result<Box<Compiled>> {
+  Ok(Box::new(compile_node(node)?))
+}
+
+/// Compiles the list of nodes.
+fn compile_list(nodes: &[AstNode]) -> Result<Vec<Compiled>> {
+  nodes.iter().map(compile_node).collect()
+}
+
+impl Compiled {
+  /// Evaluates the compiled expression against specified indexed values using default configuration.
+  pub fn eval(&self, values: &IndexedValues) -> Value {
+    self.eval_with_config(values, &EvaluatorConfig::default())
+  }
+
+  /// Evaluates the compiled expression against specified indexed values using specified configuration.
+  pub fn eval_with_config(&self, values: &IndexedValues, config: &EvaluatorConfig) -> Value {
+    match self {
+      Compiled::Abs(mhs) => semantics::abs(mhs.eval_with_config(values, config)),
+      Compiled::Add(lhs, rhs) => arithmetic(lhs, rhs, values, config, Value::add),
+      Compiled::And(lhs, rhs) => match lhs.eval_with_config(values, config) {
+        Value::Bool(false) => Value::Bool(false),
+        Value::Bool(true) => semantics::to_bool(rhs.eval_with_config(values, config)),
+        _ if config.three_valued_logic => semantics::and(Value::Null, rhs.eval_with_config(values, config)),
+        _ => Value::Null,
+      },
+      Compiled::Avg => avg_numbers(Context::values(values)),
+      Compiled::Between(lhs, mhs, rhs) => semantics::between(
+        lhs.eval_with_config(values, config),
+        mhs.eval_with_config(values, config),
+        rhs.eval_with_config(values, config),
+      ),
+      Compiled::Coalesce(mhs) => mhs
+        .iter()
+        .map(|mhs| mhs.eval_with_config(values, config))
+        .find(|value| *value != Value::Null)
+        .unwrap_or(Value::Null),
+      Compiled::Const(value) => value.clone(),
+      Compiled::Count => count_numbers(Context::values(values)),
+      Compiled::Div(lhs, rhs) => arithmetic(lhs, rhs, values, config, Value::div),
+      Compiled::Eq(lhs, rhs) => Equality::from(config).eq(&lhs.eval_with_config(values, config), &rhs.eval_with_config(values, config)),
+      Compiled::Ge(lhs, rhs) => ordering(lhs, rhs, values, config, Ordering::is_ge),
+      Compiled::Gt(lhs, rhs) => ordering(lhs, rhs, values, config, Ordering::is_gt),
+      Compiled::If(mhs, lhs, rhs) => match mhs.eval_with_config(values, config) {
+        Value::Bool(true) => lhs.eval_with_config(values, config),
+        Value::Bool(false) => rhs.eval_with_config(values, config),
+        _ => Value::Null,
+      },
+      Compiled::In(lhs, rhs) => Equality::from(config).contains(
+        &lhs.eval_with_config(values, config),
+        rhs.iter().map(|rhs| rhs.eval_with_config(values, config)),
+      ),
+      Compiled::Index(key) => values.get(key).unwrap_or(&config.missing).clone(),
+      Compiled::IsNotNull(mhs) => Value::Bool(mhs.eval_with_config(values, config) != Value::Null),
+      Compiled::IsNull(mhs) => Value::Bool(mhs.eval_with_config(values, config) == Value::Null),
+      Compiled::Le(lhs, rhs) => ordering(lhs, rhs, values, config, Ordering::is_le),
+      Compiled::Lt(lhs, rhs) => ordering(lhs, rhs, values, config, Ordering::is_lt),
+      Compiled::Max(mhs) => semantics::extremum(
+        mhs.iter().map(|mhs| mhs.eval_with_config(values, config)),
+        Decimal::max,
+        config.skip_nulls_in_min_max,
+      ),
+      Compiled::Min(mhs) => semantics::extremum(
+        mhs.iter().map(|mhs| mhs.eval_with_config(values, config)),
+        Decimal::min,
+        config.skip_nulls_in_min_max,
+      ),
+      Compiled::Mul(lhs, rhs) => arithmetic(lhs, rhs, values, config, Value::mul),
+      Compiled::Neg(mhs) => semantics::neg(mhs.eval_with_config(values, config)),
+      Compiled::Nq(lhs, rhs) => Equality::from(config).nq(&lhs.eval_with_config(values, config), &rhs.eval_with_config(values, config)),
+      Compiled::Or(lhs, rhs) => match lhs.eval_with_config(values, config) {
+        Value::Bool(true) => Value::Bool(true),
+        Value::Bool(false) => semantics::to_bool(rhs.eval_with_config(values, config)),
+        _ if config.three_valued_logic => semantics::or(Value::Null, rhs.eval_with_config(values, config)),
+        _ => Value::Null,
+      },
+      Compiled::Round(lhs, scale) => semantics::round(lhs.eval_with_config(values, config), *scale),
+      Compiled::Sub(lhs, rhs) => arithmetic(lhs, rhs, values, config, Value::sub),
+      Compiled::Sum => sum_numbers(Context::values(values)),
+      Compiled::Xor(lhs, rhs) => semantics::xor(lhs.eval_with_config(values, config), rhs.eval_with_config(values, config)),
+    }
+  }
+}
+
+/// Evaluates arithmetic operator, see the arithmetic operators of [Value].
+fn arithmetic(lhs: &Compiled, rhs: &Compiled, values: &IndexedValues, config: &EvaluatorConfig, operation: fn(Value, Value) -> Value) -> Value {
+  operation(lhs.eval_with_config(values, config), rhs.eval_with_config(values, config))
+}
+
+/// Evaluates ordering operator, see [semantics::ordering].
+fn ordering(lhs: &Compiled, rhs: &Compiled, values: &IndexedValues, config: &EvaluatorConfig, compare: fn(Ordering) -> bool) -> Value {
+  semantics::ordering(&lhs.eval_with_config(values, config), &rhs.eval_with_config(values, config), compare)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+
+  #[test]
+  fn test_compile() {
+    let compiled = compile(&Parser::new("if(1 > 2.5; round(1; 2); null)").parse().unwrap()).unwrap();
+    assert_eq!(
+      Compiled::If(
+        Box::new(Compiled::Gt(
+          Box::new(Compiled::Index(1)),
+          Box::new(Compiled::Const(Value::Number(Decimal::new(25, 1))))
+        )),
+        Box::new(Compiled::Round(Box::new(Compiled::Index(1)), 2)),
+        Box::new(Compiled::Const(Value::Null))
+      ),
+      compiled
+    );
+    let mut values = IndexedValues::new();
+    values.insert(1, Value::Number(Decimal::new(31415, 4)));
+    assert_eq!(Value::Number(Decimal::new(314, 2)), compiled.eval(&values));
+  }
+
+  #[test]
+  fn test_compile_call() {
+    let node = Parser::new("double(1) > 2").parse().unwrap();
+    assert_eq!("unsupported function call 'double'", compile(&node).unwrap_err().to_string());
+  }
+}
@@ -0,0 +1,241 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Structural differences between two ASTs.
+
+use crate::ast::AstNode;
+use difference::{Changeset, Difference};
+use std::fmt;
+
+/// Single difference between two trees, nodes are located by path of operand positions from the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffOp {
+  /// Operator (or called function) of the node changed, operands are compared separately.
+  Operator {
+    /// Path of the node.
+    path: Vec<usize>,
+    /// Operator in the old tree.
+    old: String,
+    /// Operator in the new tree.
+    new: String,
+  },
+  /// Value of the leaf node changed.
+  Leaf {
+    /// Path of the node.
+    path: Vec<usize>,
+    /// Source text of the leaf in the old tree.
+    old: String,
+    /// Source text of the leaf in the new tree.
+    new: String,
+    /// Character level difference of the texts, removed text in `[-` `-]`, added text in `{+` `+}`.
+    diff: String,
+  },
+  /// Whole node replaced by a node of different shape.
+  Replace {
+    /// Path of the node.
+    path: Vec<usize>,
+    /// Source text of the node in the old tree.
+    old: String,
+    /// Source text of the node in the new tree.
+    new: String,
+  },
+  /// Node inserted into a list of operands.
+  Insert {
+    /// Path of the inserted node.
+    path: Vec<usize>,
+    /// Source text of the inserted node.
+    node: String,
+  },
+  /// Node deleted from a list of operands.
+  Delete {
+    /// Path of the deleted node.
+    path: Vec<usize>,
+    /// Source text of the deleted node.
+    node: String,
+  },
+}
+
+impl fmt::Display for DiffOp {
+  /// Implementation of [Display](std::fmt::Display) trait for [DiffOp], renders single line description.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DiffOp::Operator { path, old, new } => write!(f, "{}: operator {} replaced with {}", path_to_string(path), old, new),
+      DiffOp::Leaf { path, old, new, diff } => write!(f, "{}: {} changed to {} ({})", path_to_string(path), old, new, diff),
+      DiffOp::Replace { path, old, new } => write!(f, "{}: {} replaced with {}", path_to_string(path), old, new),
+      DiffOp::Insert { path, node } => write!(f, "{}: {} inserted", path_to_string(path), node),
+      DiffOp::Delete { path, node } => write!(f, "{}: {} deleted", path_to_string(path), node),
+    }
+  }
+}
+
+/// Returns differences between two trees, empty when the trees are equal.
+///
+/// Nodes are compared structurally: when the operators differ but the number of operands is the same,
+/// the operator change is reported and operands are compared pairwise, lists of operands of the same
+/// operator are compared position by position with trailing insertions or deletions,
+/// leaves of the same kind are compared as text.
+pub fn ast_diff(a: &AstNode, b: &AstNode) -> Vec<DiffOp> {
+  let mut ops = vec![];
+  diff_nodes(a, b, &mut vec![], &mut ops);
+  ops
+}
+
+/// Collects differences between nodes at the specified path.
+fn diff_nodes(a: &AstNode, b: &AstNode, path: &mut Vec<usize>, ops: &mut Vec<DiffOp>) {
+  if a == b {
+    return;
+  }
+  let (a_children, b_children) = (a.children(), b.children());
+  if a_children.is_empty() && b_children.is_empty() {
+    let (old, new) = (a.to_source(), b.to_source());
+    if a.kind() == b.kind() {
+      let diff = text_diff(&old, &new);
+      ops.push(DiffOp::Leaf {
+        path: path.clone(),
+        old,
+        new,
+        diff,
+      });
+    } else {
+      ops.push(DiffOp::Replace { path: path.clone(), old, new });
+    }
+    return;
+  }
+  let (old, new) = (operator(a), operator(b));
+  if old == new {
+    let common = a_children.len().min(b_children.len());
+    for (position, (a_child, b_child)) in a_children.iter().zip(b_children.iter()).enumerate() {
+      path.push(position);
+      diff_nodes(a_child, b_child, path, ops);
+      path.pop();
+    }
+    for (position, node) in a_children.iter().enumerate().skip(common) {
+      ops.push(DiffOp::Delete {
+        path: child_path(path, position),
+        node: node.to_source(),
+      });
+    }
+    for (position, node) in b_children.iter().enumerate().skip(common) {
+      ops.push(DiffOp::Insert {
+        path: child_path(path, position),
+        node: node.to_source(),
+      });
+    }
+  } else if a_children.len() == b_children.len() {
+    ops.push(DiffOp::Operator { path: path.clone(), old, new });
+    for (position, (a_child, b_child)) in a_children.iter().zip(b_children.iter()).enumerate() {
+      path.push(position);
+      diff_nodes(a_child, b_child, path, ops);
+      path.pop();
+    }
+  } else {
+    ops.push(DiffOp::Replace {
+      path: path.clone(),
+      old: a.to_source(),
+      new: b.to_source(),
+    });
+  }
+}
+
+/// Returns the operator of the node, called function is included for function calls.
+fn operator(node: &AstNode) -> String {
+  match node {
+    AstNode::Call(name, _) => format!("{} {}", node.kind(), name),
+    _ => node.kind().to_string(),
+  }
+}
+
+/// Returns the path of the child node at the specified position.
+fn child_path(path: &[usize], position: usize) -> Vec<usize> {
+  let mut path = path.to_vec();
+  path.push(position);
+  path
+}
+
+/// Returns the path rendered as `/` separated positions, root is `/`.
+fn path_to_string(path: &[usize]) -> String {
+  if path.is_empty() {
+    "/".to_string()
+  } else {
+    path.iter().map(|position| format!("/{}", position)).collect()
+  }
+}
+
+/// Returns character level difference of two texts.
+fn text_diff(old: &str, new: &str) -> String {
+  Changeset::new(old, new, "")
+    .diffs
+    .iter()
+    .map(|difference| match difference {
+      Difference::Same(text) => text.to_string(),
+      Difference::Rem(text) => format!("[-{}-]", text),
+      Difference::Add(text) => format!("{{+{}+}}", text),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse_expression;
+
+  /// Returns rendered differences between parsed expressions.
+  fn diff(a: &str, b: &str) -> Vec<String> {
+    ast_diff(&parse_expression(a), &parse_expression(b)).iter().map(|op| op.to_string()).collect()
+  }
+
+  #[test]
+  fn test_ast_diff() {
+    assert_eq!(
+      vec![
+        DiffOp::Operator {
+          path: vec![0],
+          old: "Eq".to_string(),
+          new: "Lt".to_string()
+        },
+        DiffOp::Leaf {
+          path: vec![2],
+          old: "2".to_string(),
+          new: "3".to_string(),
+          diff: "[-2-]{+3+}".to_string()
+        },
+      ],
+      ast_diff(&parse_expression("if(1=2;1;2)"), &parse_expression("if(1<2;1;3)"))
+    );
+    assert_eq!(
+      vec!["/0: operator Eq replaced with Lt", "/2: 2 changed to 3 ([-2-]{+3+})"],
+      diff("if(1=2;1;2)", "if(1<2;1;3)")
+    );
+    assert!(diff("if(1=2;1;2)", "if(1=2;1;2)").is_empty());
+  }
+
+  #[test]
+  fn test_ast_diff_lists() {
+    assert_eq!(vec!["/1: 2 replaced with null", "/2: 3 inserted"], diff("max(1;2)", "max(1;null;3)"));
+    assert_eq!(vec!["/1: 2.0 deleted"], diff("min(1;2.0)", "min(1)"));
+    assert_eq!(vec!["/: operator Call f replaced with Call g"], diff("f(1)", "g(1)"));
+    assert_eq!(vec!["/1: \"abc\" changed to \"abd\" (\"ab[-c-]{+d+}\")"], diff(r#"1="abc""#, r#"1="abd""#));
+    assert_eq!(vec!["/: 1+2 replaced with abs(1)"], diff("1+2", "abs(1)"));
+  }
+}
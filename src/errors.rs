@@ -24,25 +24,172 @@
 
 //! Errors implementation.
 
+use crate::lexer::{Token, TokenKind};
 use std::fmt;
+use std::num::ParseIntError;
 
 /// Common result type.
 pub type Result<T, E = SecelError> = std::result::Result<T, E>;
 
-/// Common error definition.
+/// Common error definition, variants distinguish the stage at which the error was reported.
 #[derive(Debug, PartialEq, Eq)]
-pub struct SecelError(String);
+pub enum SecelError {
+  /// Error reported by the lexer, like unexpected character or malformed literal.
+  Lex {
+    /// Error message text.
+    message: String,
+    /// Position (character offset) in input the error refers to, if known.
+    position: Option<usize>,
+  },
+  /// Error reported by the parser when none of the expected tokens was found.
+  Parse {
+    /// Kinds of tokens expected at the position.
+    expected: Vec<TokenKind>,
+    /// Token found instead.
+    found: Token,
+    /// Position (character offset) in input of the found token.
+    position: usize,
+  },
+  /// Other error reported by the parser, like unknown identifier or exceeded nesting depth.
+  Syntax {
+    /// Error message text.
+    message: String,
+    /// Position (character offset) in input the error refers to, if known.
+    position: Option<usize>,
+  },
+  /// Error reported while building, checking or evaluating expressions, and any other error.
+  Eval {
+    /// Error message text.
+    message: String,
+    /// Position the error refers to, if known.
+    position: Option<usize>,
+  },
+}
 
 impl fmt::Display for SecelError {
   /// Implementation of [Display](std::fmt::Display) trait for [SecelError].
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "{}", self.0)
+    match self.position() {
+      Some(position) => write!(f, "{} at position {}", self.message(), position),
+      None => write!(f, "{}", self.message()),
+    }
+  }
+}
+
+impl From<ParseIntError> for SecelError {
+  /// Converts integer parsing failure into [SecelError::Lex], preserving the reason of the failure.
+  fn from(reason: ParseIntError) -> Self {
+    Self::lex(&format!("invalid integer: {}", reason), None)
   }
 }
 
 impl SecelError {
-  /// Creates a new [SecelError] with specified message text.
+  /// Creates a new [SecelError::Eval] with specified message text.
   pub fn new(message: &str) -> Self {
-    Self(message.to_string())
+    Self::Eval {
+      message: message.to_string(),
+      position: None,
+    }
+  }
+  /// Creates a new [SecelError::Eval] with specified message text and position.
+  pub fn at(message: &str, position: usize) -> Self {
+    Self::Eval {
+      message: message.to_string(),
+      position: Some(position),
+    }
+  }
+  /// Creates a new [SecelError::Lex] with specified message text and optional position in input.
+  pub fn lex(message: &str, position: Option<usize>) -> Self {
+    Self::Lex {
+      message: message.to_string(),
+      position,
+    }
+  }
+  /// Creates a new [SecelError::Parse] reporting kinds of tokens expected and the token found at specified position in input.
+  pub fn parse(expected: Vec<TokenKind>, found: Token, position: usize) -> Self {
+    Self::Parse { expected, found, position }
+  }
+  /// Adds specified kinds of tokens to tokens expected by [SecelError::Parse] reported at specified position,
+  /// used when an alternative construct starting at that position could be parsed instead.
+  /// Other errors are returned unchanged.
+  pub(crate) fn expecting(mut self, kinds: &[TokenKind], at: usize) -> Self {
+    if let Self::Parse { expected, position, .. } = &mut self {
+      if *position == at {
+        for kind in kinds {
+          if !expected.contains(kind) {
+            expected.push(*kind);
+          }
+        }
+      }
+    }
+    self
+  }
+  /// Creates a new [SecelError::Syntax] with specified message text and optional position in input.
+  pub fn syntax(message: &str, position: Option<usize>) -> Self {
+    Self::Syntax {
+      message: message.to_string(),
+      position,
+    }
+  }
+  /// Returns the error message text without position.
+  pub fn message(&self) -> String {
+    match self {
+      Self::Lex { message, .. } | Self::Syntax { message, .. } | Self::Eval { message, .. } => message.clone(),
+      Self::Parse { expected, found, .. } => match expected.as_slice() {
+        [kind] => format!("expected {} but encountered {:?}", kind, found),
+        kinds => format!(
+          "expected one of {} but encountered {:?}",
+          kinds.iter().map(|kind| format!("`{}`", kind)).collect::<Vec<String>>().join(", "),
+          found
+        ),
+      },
+    }
+  }
+  /// Returns the position (character offset) in input the error refers to, if known.
+  pub fn position(&self) -> Option<usize> {
+    match self {
+      Self::Lex { position, .. } | Self::Syntax { position, .. } | Self::Eval { position, .. } => *position,
+      Self::Parse { position, .. } => Some(*position),
+    }
+  }
+  /// Returns the line and column (both starting from 1) in the specified source the error refers to, if the position is known.
+  /// Position past the end of the source refers to the column after the last character.
+  pub fn line_column(&self, source: &str) -> Option<(usize, usize)> {
+    let position = self.position()?;
+    let (mut line, mut column) = (1, 1);
+    for ch in source.chars().take(position) {
+      if ch == '\n' {
+        line += 1;
+        column = 1;
+      } else {
+        column += 1;
+      }
+    }
+    Some((line, column))
+  }
+  /// Renders the error with the offending line of the specified source and the `^` under the error column, like:
+  ///
+  /// ```text
+  /// error: expected RightParen but encountered Number(3)
+  ///  --> line 3, column 5
+  ///   |
+  /// 3 |   2 3)
+  ///   |     ^
+  /// ```
+  ///
+  /// Errors without position are rendered as the message only.
+  pub fn render(&self, source: &str) -> String {
+    let message = format!("error: {}", self.message());
+    let (line, column) = match self.line_column(source) {
+      Some(line_column) => line_column,
+      None => return message,
+    };
+    let text = source.lines().nth(line - 1).unwrap_or_default();
+    let padding: String = text.chars().take(column - 1).map(|ch| if ch == '\t' { '\t' } else { ' ' }).collect();
+    let gutter = " ".repeat(line.to_string().len());
+    format!(
+      "{}\n{}--> line {}, column {}\n{} |\n{} | {}\n{} | {}^",
+      message, gutter, line, column, gutter, line, text, gutter, padding
+    )
   }
 }
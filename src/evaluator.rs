@@ -25,103 +25,369 @@
 //! Evaluator implementation.
 
 use crate::ast::AstNode;
-use crate::errors::Result;
+use crate::errors::{Result, SecelError};
+use crate::optimizer::fold;
+use crate::semantics::{self, avg_numbers, count_numbers, sum_numbers, Equality};
 use crate::values::Value;
+#[cfg(feature = "chrono")]
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::Arc;
 
 /// Type alias for the key that indexes values.
-pub type IndexKey = u8;
+pub type IndexKey = u16;
 
 /// Type alias for the index of values.
 pub type IndexedValues = HashMap<IndexKey, Value>;
 
 /// Type alias for the evaluator of the expression.
-pub type Evaluator = Box<dyn Fn(&IndexedValues) -> Value>;
+pub type Evaluator = ContextEvaluator<IndexedValues>;
+
+/// Type alias for the evaluator of the expression evaluated against values provided by a [Context].
+/// Evaluators are `Send + Sync`, so a single evaluator may be shared between threads.
+pub type ContextEvaluator<C> = Box<dyn Fn(&C) -> Value + Send + Sync>;
+
+/// Source of the values referenced by indexes in expressions.
+pub trait Context {
+  /// Returns the value stored under specified index, `None` when there is no such value.
+  fn get(&self, key: IndexKey) -> Option<Value>;
+  /// Returns an iterator over all values stored in the context, aggregated by `sum`, `avg` and `count` functions.
+  /// Contexts not overriding this method provide no values, so `sum()` evaluates to `0` and `avg()` to `Null`.
+  fn values(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+    Box::new(std::iter::empty())
+  }
+}
+
+impl Context for IndexedValues {
+  fn get(&self, key: IndexKey) -> Option<Value> {
+    HashMap::get(self, &key).cloned()
+  }
+  fn values(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+    Box::new(HashMap::values(self))
+  }
+}
+
+/// Positional values, the index is the position of the value in the slice (starting from 0).
+impl Context for [Value] {
+  fn get(&self, key: IndexKey) -> Option<Value> {
+    <[Value]>::get(self, key as usize).cloned()
+  }
+  fn values(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+    Box::new(self.iter())
+  }
+}
+
+/// Positional values, the index is the position of the value in the vector (starting from 0).
+impl Context for Vec<Value> {
+  fn get(&self, key: IndexKey) -> Option<Value> {
+    self.as_slice().get(key as usize).cloned()
+  }
+  fn values(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+    Box::new(self.iter())
+  }
+}
+
+/// Type alias for the user-registered function, invoked with the values of the arguments.
+pub type Function = Arc<dyn Fn(&[Value]) -> Value + Send + Sync>;
+
+/// Registry of user-registered functions, called with `name(arguments)` syntax.
+#[derive(Clone, Default)]
+pub struct Functions(HashMap<String, Function>);
+
+impl Functions {
+  /// Returns the function registered under specified name.
+  pub fn get(&self, name: &str) -> Option<&Function> {
+    self.0.get(name)
+  }
+}
+
+impl From<HashMap<String, Function>> for Functions {
+  fn from(functions: HashMap<String, Function>) -> Self {
+    Self(functions)
+  }
+}
+
+/// Lists the names of registered functions.
+impl fmt::Debug for Functions {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut names = self.0.keys().collect::<Vec<&String>>();
+    names.sort();
+    f.debug_set().entries(names).finish()
+  }
+}
+
+/// Registries are equal when the same functions are registered under the same names.
+impl PartialEq for Functions {
+  fn eq(&self, other: &Self) -> bool {
+    self.0.len() == other.0.len()
+      && self
+        .0
+        .iter()
+        .all(|(name, function)| other.0.get(name).is_some_and(|other| Arc::ptr_eq(function, other)))
+  }
+}
+
+impl Eq for Functions {}
+
+/// Default maximum depth of the expression tree the evaluator is built for,
+/// also applied by the strict evaluator and the compiled expression.
+/// The stack used per level of the tree depends on the evaluator and the build profile,
+/// unoptimized builds and threads with small stacks may need a lower limit.
+pub const DEFAULT_MAX_EVALUATOR_DEPTH: usize = 1024;
+
+/// Configuration of the evaluator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvaluatorConfig {
+  /// Enables SQL three-valued (Kleene) logic in `and` and `or` operators,
+  /// where `false and null` is `false` and `true or null` is `true`.
+  pub three_valued_logic: bool,
+  /// Value returned for indexes missing in the context, `Null` by default.
+  pub missing: Value,
+  /// Makes `min` and `max` functions skip `Null` operands instead of evaluating to `Null`
+  /// when any operand is `Null`, the result is `Null` only when all operands are `Null`.
+  pub skip_nulls_in_min_max: bool,
+  /// Makes `null = null` evaluate to `true` and `null <> null` to `false` (default),
+  /// when disabled both evaluate to `Null`, like in SQL.
+  pub null_equals_null: bool,
+  /// Tolerance of comparing numbers with `=`, `<>` and `in` operators, numbers differing
+  /// by no more than the tolerance are equal, `None` (default) for exact comparison.
+  pub eq_tolerance: Option<Decimal>,
+  /// Maximum depth of the expression tree the evaluator is built for, deeper trees are
+  /// reported as errors instead of overflowing the stack, see [DEFAULT_MAX_EVALUATOR_DEPTH].
+  pub max_depth: usize,
+  /// Enables folding of constant subtrees before building the evaluator, see [crate::optimize].
+  pub optimize: bool,
+  /// User-registered functions, calls of unknown functions evaluate to `Null`.
+  pub functions: Functions,
+}
+
+impl EvaluatorConfig {
+  /// Returns the configuration with specified user-registered functions.
+  pub fn with_functions(mut self, functions: HashMap<String, Function>) -> Self {
+    self.functions = Functions::from(functions);
+    self
+  }
+}
+
+impl Default for EvaluatorConfig {
+  fn default() -> Self {
+    Self {
+      three_valued_logic: false,
+      missing: Value::Null,
+      skip_nulls_in_min_max: false,
+      null_equals_null: true,
+      eq_tolerance: None,
+      max_depth: DEFAULT_MAX_EVALUATOR_DEPTH,
+      optimize: false,
+      functions: Functions::default(),
+    }
+  }
+}
 
 /// Builds an expression evaluator for given [AstNode].
 pub fn build_evaluator(node: &AstNode) -> Result<Evaluator> {
+  build_context_evaluator(node, &EvaluatorConfig::default())
+}
+
+/// Builds an expression evaluator for given [AstNode] using specified configuration.
+pub fn build_evaluator_with_config(node: &AstNode, config: &EvaluatorConfig) -> Result<Evaluator> {
+  build_context_evaluator(node, config)
+}
+
+/// Evaluates the evaluator against each row and writes the results into the caller-provided buffer,
+/// the result for `rows[i]` is written into `out[i]`.
+///
+/// # Panics
+///
+/// Panics when the number of rows differs from the length of the buffer.
+pub fn evaluate_into(evaluator: &Evaluator, rows: &[IndexedValues], out: &mut [Value]) {
+  assert_eq!(rows.len(), out.len(), "number of rows must match the length of the output buffer");
+  for (row, result) in rows.iter().zip(out.iter_mut()) {
+    *result = evaluator(row);
+  }
+}
+
+/// Builds an expression evaluator for given [AstNode], evaluated against values provided by specified [Context] type.
+pub fn build_context_evaluator<C: Context + ?Sized + 'static>(node: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  check_depth(node, config.max_depth)?;
+  if config.optimize {
+    build_node(&fold(node.clone(), config), config)
+  } else {
+    build_node(node, config)
+  }
+}
+
+/// Checks, without recursion, that the depth of the tree rooted at given [AstNode] does not exceed specified maximum,
+/// so that building (and evaluating) a deeper tree is reported as an error instead of overflowing the stack.
+pub(crate) fn check_depth(node: &AstNode, max_depth: usize) -> Result<()> {
+  let depth = node.depth();
+  if depth > max_depth {
+    return Err(SecelError::new(&format!("expression depth {} exceeds the maximum depth {}", depth, max_depth)));
+  }
+  Ok(())
+}
+
+/// Builds an evaluator for given [AstNode], dispatching on node type.
+pub(crate) fn build_node<C: Context + ?Sized + 'static>(node: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
   match node {
-    AstNode::And(lhs, rhs) => build_and(lhs, rhs),
-    AstNode::Eq(lhs, rhs) => build_eq(lhs, rhs),
-    AstNode::Ge(lhs, rhs) => build_ge(lhs, rhs),
-    AstNode::Gt(lhs, rhs) => build_gt(lhs, rhs),
-    AstNode::If(mhs, lhs, rhs) => build_if(mhs, lhs, rhs),
-    AstNode::Le(lhs, rhs) => build_le(lhs, rhs),
-    AstNode::Lt(lhs, rhs) => build_lt(lhs, rhs),
-    AstNode::Nq(lhs, rhs) => build_nq(lhs, rhs),
+    AstNode::Abs(mhs) => build_abs(mhs, config),
+    AstNode::Add(lhs, rhs) => build_add(lhs, rhs, config),
+    AstNode::And(lhs, rhs) => build_and(lhs, rhs, config),
+    AstNode::Avg => build_aggregate(avg_numbers),
+    AstNode::Between(lhs, mhs, rhs) => build_between(lhs, mhs, rhs, config),
+    AstNode::Bool(mhs) => build_bool(*mhs),
+    AstNode::Call(name, mhs) => build_call(name, mhs, config),
+    AstNode::Coalesce(mhs) => build_coalesce(mhs, config),
+    AstNode::Count => build_aggregate(count_numbers),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(mhs) => build_date_literal(*mhs),
+    AstNode::Div(lhs, rhs) => build_div(lhs, rhs, config),
+    AstNode::Eq(lhs, rhs) => build_eq(lhs, rhs, config),
+    AstNode::Ge(lhs, rhs) => build_ge(lhs, rhs, config),
+    AstNode::Gt(lhs, rhs) => build_gt(lhs, rhs, config),
+    AstNode::If(mhs, lhs, rhs) => build_if(mhs, lhs, rhs, config),
+    AstNode::In(lhs, rhs) => build_in(lhs, rhs, config),
+    AstNode::IsNotNull(mhs) => build_is_not_null(mhs, config),
+    AstNode::IsNull(mhs) => build_is_null(mhs, config),
+    AstNode::Le(lhs, rhs) => build_le(lhs, rhs, config),
+    AstNode::Literal(mhs) => build_literal(*mhs),
+    AstNode::Lt(lhs, rhs) => build_lt(lhs, rhs, config),
+    AstNode::Max(mhs) => build_extremum(mhs, Decimal::max, config),
+    AstNode::Min(mhs) => build_extremum(mhs, Decimal::min, config),
+    AstNode::Mul(lhs, rhs) => build_mul(lhs, rhs, config),
+    AstNode::Neg(mhs) => build_neg(mhs, config),
+    AstNode::Nq(lhs, rhs) => build_nq(lhs, rhs, config),
     AstNode::Null => build_null(),
-    AstNode::Number(mhs) => build_number(*mhs),
-    AstNode::Or(lhs, rhs) => build_or(lhs, rhs),
+    AstNode::Number(mhs) => build_number(*mhs, config),
+    AstNode::Or(lhs, rhs) => build_or(lhs, rhs, config),
+    AstNode::Round(lhs, rhs) => build_round(lhs, rhs, config),
+    AstNode::StrLiteral(mhs) => build_str_literal(mhs),
+    AstNode::Sub(lhs, rhs) => build_sub(lhs, rhs, config),
+    AstNode::Sum => build_aggregate(sum_numbers),
+    AstNode::Xor(lhs, rhs) => build_xor(lhs, rhs, config),
   }
 }
 
+/// Builds an evaluator for `abs` function.
+fn build_abs<C: Context + ?Sized + 'static>(mhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let mhe = build_node(mhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::abs(mhe(iv))))
+}
+
+/// Builds an evaluator for `+` operator.
+fn build_add<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  build_arithmetic(lhs, rhs, Value::add, config)
+}
+
+/// Builds an evaluator for `sum`, `avg` and `count` functions, aggregating all values in the context.
+fn build_aggregate<C: Context + ?Sized + 'static>(aggregate: fn(Box<dyn Iterator<Item = &Value> + '_>) -> Value) -> Result<ContextEvaluator<C>> {
+  Ok(Box::new(move |iv: &C| aggregate(iv.values())))
+}
+
 /// Builds an evaluator for `and` operator.
-fn build_and(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
-    if let Value::Bool(lhv) = lhe(iv) {
-      if let Value::Bool(rhv) = rhe(iv) {
-        return Value::Bool(lhv && rhv);
-      }
-    }
-    Value::Null
-  }))
+fn build_and<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(if config.three_valued_logic {
+    combine_and_kleene(lhe, rhe)
+  } else {
+    combine_and(lhe, rhe)
+  })
 }
 
-/// Builds an evaluator for `=` operator.
-fn build_eq(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| match lhe(iv) {
-    Value::Number(lhv) => match rhe(iv) {
-      Value::Number(rhv) => Value::Bool(lhv == rhv),
-      Value::Null => Value::Bool(false),
-      _ => Value::Null,
-    },
-    Value::Null => match rhe(iv) {
-      Value::Number(_) => Value::Bool(false),
-      Value::Null => Value::Bool(true),
+/// Combines evaluators of `and` operands, the right-side operand
+/// is not evaluated when the left-side operand evaluates to `false`.
+fn combine_and<C: Context + ?Sized + 'static>(lhe: ContextEvaluator<C>, rhe: ContextEvaluator<C>) -> ContextEvaluator<C> {
+  Box::new(move |iv: &C| match lhe(iv) {
+    Value::Bool(false) => Value::Bool(false),
+    Value::Bool(true) => semantics::to_bool(rhe(iv)),
+    _ => Value::Null,
+  })
+}
+
+/// Builds an evaluator for `between` operator, both bounds are inclusive.
+fn build_between<C: Context + ?Sized + 'static>(lhs: &AstNode, mhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let mhe = build_node(mhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::between(lhe(iv), mhe(iv), rhe(iv))))
+}
+
+/// Combines evaluators of `and` operands using three-valued (Kleene) logic,
+/// non-boolean operands are treated as unknown (`Null`).
+fn combine_and_kleene<C: Context + ?Sized + 'static>(lhe: ContextEvaluator<C>, rhe: ContextEvaluator<C>) -> ContextEvaluator<C> {
+  Box::new(move |iv: &C| match lhe(iv) {
+    Value::Bool(false) => Value::Bool(false),
+    Value::Bool(true) => semantics::to_bool(rhe(iv)),
+    _ => match rhe(iv) {
+      Value::Bool(false) => Value::Bool(false),
       _ => Value::Null,
     },
-    _ => Value::Null,
+  })
+}
+
+/// Builds an evaluator for `Bool` node.
+fn build_bool<C: Context + ?Sized + 'static>(value: bool) -> Result<ContextEvaluator<C>> {
+  Ok(Box::new(move |_: &C| Value::Bool(value)))
+}
+
+/// Builds an evaluator for the call of user-registered function, calls of unknown functions evaluate to `Null`.
+fn build_call<C: Context + ?Sized + 'static>(name: &str, mhs: &[AstNode], config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let function = match config.functions.get(name) {
+    Some(function) => function.clone(),
+    None => return build_null(),
+  };
+  let mhes = mhs.iter().map(|node| build_node(node, config)).collect::<Result<Vec<ContextEvaluator<C>>>>()?;
+  Ok(Box::new(move |iv: &C| function(&mhes.iter().map(|mhe| mhe(iv)).collect::<Vec<Value>>())))
+}
+
+/// Builds an evaluator for `coalesce` function, operands are evaluated from left to right
+/// until the first operand that does not evaluate to `Null`.
+fn build_coalesce<C: Context + ?Sized + 'static>(mhs: &[AstNode], config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let mhes = mhs.iter().map(|node| build_node(node, config)).collect::<Result<Vec<ContextEvaluator<C>>>>()?;
+  Ok(Box::new(move |iv: &C| {
+    mhes.iter().map(|mhe| mhe(iv)).find(|value| *value != Value::Null).unwrap_or(Value::Null)
   }))
 }
 
+/// Builds an evaluator for `/` operator, division by zero evaluates to `Null`.
+fn build_div<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  build_arithmetic(lhs, rhs, Value::div, config)
+}
+
+/// Builds an evaluator for `=` operator.
+fn build_eq<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  let equality = Equality::from(config);
+  Ok(Box::new(move |iv: &C| equality.eq(&lhe(iv), &rhe(iv))))
+}
+
 /// Builds an evaluator for `>` operator.
-fn build_ge(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
-    if let Value::Number(lhv) = lhe(iv) {
-      if let Value::Number(rhv) = rhe(iv) {
-        return Value::Bool(lhv >= rhv);
-      }
-    }
-    Value::Null
-  }))
+fn build_ge<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::ordering(&lhe(iv), &rhe(iv), Ordering::is_ge)))
 }
 
 /// Builds an evaluator for `>=` operator.
-fn build_gt(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
-    if let Value::Number(lhv) = lhe(iv) {
-      if let Value::Number(rhv) = rhe(iv) {
-        return Value::Bool(lhv > rhv);
-      }
-    }
-    Value::Null
-  }))
+fn build_gt<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::ordering(&lhe(iv), &rhe(iv), Ordering::is_gt)))
 }
 
 /// Builds an evaluator for `if` expression.
-fn build_if(mhs: &AstNode, lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let mhe = build_evaluator(mhs)?;
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
+fn build_if<C: Context + ?Sized + 'static>(mhs: &AstNode, lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let mhe = build_node(mhs, config)?;
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| {
     if let Value::Bool(mhv) = mhe(iv) {
       return if mhv { lhe(iv) } else { rhe(iv) };
     }
@@ -129,91 +395,201 @@ fn build_if(mhs: &AstNode, lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
   }))
 }
 
+/// Builds an evaluator for `in` operator, evaluates to `true` when any list element
+/// is equal to the tested value, to `false` when none is equal and no `Null` was involved
+/// in comparisons, otherwise to `Null`.
+fn build_in<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &[AstNode], config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhes = rhs.iter().map(|node| build_node(node, config)).collect::<Result<Vec<ContextEvaluator<C>>>>()?;
+  let equality = Equality::from(config);
+  Ok(Box::new(move |iv: &C| equality.contains(&lhe(iv), rhes.iter().map(|rhe| rhe(iv)))))
+}
+
+/// Builds an evaluator for `is not null` test, always evaluates to `Bool`.
+fn build_is_not_null<C: Context + ?Sized + 'static>(mhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let mhe = build_node(mhs, config)?;
+  Ok(Box::new(move |iv: &C| Value::Bool(mhe(iv) != Value::Null)))
+}
+
+/// Builds an evaluator for `is null` test, always evaluates to `Bool`.
+fn build_is_null<C: Context + ?Sized + 'static>(mhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let mhe = build_node(mhs, config)?;
+  Ok(Box::new(move |iv: &C| Value::Bool(mhe(iv) == Value::Null)))
+}
+
 /// Builds an evaluator for `<` operator.
-fn build_le(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
-    if let Value::Number(lhv) = lhe(iv) {
-      if let Value::Number(rhv) = rhe(iv) {
-        return Value::Bool(lhv <= rhv);
-      }
-    }
-    Value::Null
-  }))
+fn build_le<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::ordering(&lhe(iv), &rhe(iv), Ordering::is_le)))
 }
 
 /// Builds an evaluator for `<=` operator.
-fn build_lt(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
-    if let Value::Number(lhv) = lhe(iv) {
-      if let Value::Number(rhv) = rhe(iv) {
-        return Value::Bool(lhv < rhv);
-      }
+fn build_lt<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::ordering(&lhe(iv), &rhe(iv), Ordering::is_lt)))
+}
+
+/// Builds an evaluator for `*` operator.
+fn build_mul<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  build_arithmetic(lhs, rhs, Value::mul, config)
+}
+
+/// Builds an evaluator for unary `-` operator.
+fn build_neg<C: Context + ?Sized + 'static>(mhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let mhe = build_node(mhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::neg(mhe(iv))))
+}
+
+/// Builds an evaluator for `<>` operator, evaluates to the negated result of `=` operator.
+fn build_nq<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  let equality = Equality::from(config);
+  Ok(Box::new(move |iv: &C| equality.nq(&lhe(iv), &rhe(iv))))
+}
+
+/// Builds an evaluator for `or` operator.
+fn build_or<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(if config.three_valued_logic {
+    combine_or_kleene(lhe, rhe)
+  } else {
+    combine_or(lhe, rhe)
+  })
+}
+
+/// Combines evaluators of `or` operands, the right-side operand
+/// is not evaluated when the left-side operand evaluates to `true`.
+fn combine_or<C: Context + ?Sized + 'static>(lhe: ContextEvaluator<C>, rhe: ContextEvaluator<C>) -> ContextEvaluator<C> {
+  Box::new(move |iv: &C| match lhe(iv) {
+    Value::Bool(true) => Value::Bool(true),
+    Value::Bool(false) => semantics::to_bool(rhe(iv)),
+    _ => Value::Null,
+  })
+}
+
+/// Builds an evaluator for `round` function, midpoint values are rounded
+/// to the nearest even number (banker's rounding), e.g. `1.115` rounded to 2 places is `1.12`
+/// and `1.125` is `1.12` too.
+fn build_round<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let scale = round_scale(rhs)?;
+  Ok(Box::new(move |iv: &C| semantics::round(lhe(iv), scale)))
+}
+
+/// Returns the scale of `round` function, the scale must be a non-negative integer literal.
+pub(crate) fn round_scale(node: &AstNode) -> Result<u32> {
+  match node {
+    AstNode::Literal(scale) if scale.fract().is_zero() && *scale >= Decimal::ZERO => {
+      scale.to_u32().ok_or_else(|| SecelError::new("scale of 'round' is out of range"))
     }
-    Value::Null
-  }))
+    _ => Err(SecelError::new("scale of 'round' must be a non-negative integer literal")),
+  }
 }
 
-/// Builds an evaluator for `<>` operator.
-fn build_nq(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| match lhe(iv) {
-    Value::Number(lhv) => match rhe(iv) {
-      Value::Number(rhv) => Value::Bool(lhv != rhv),
-      Value::Null => Value::Bool(true),
-      _ => Value::Null,
-    },
-    Value::Null => match rhe(iv) {
-      Value::Number(_) => Value::Bool(true),
-      Value::Null => Value::Bool(false),
+/// Creates an error reporting the call of user-registered function in evaluators not supporting function calls.
+pub(crate) fn err_unsupported_call(name: &str) -> SecelError {
+  SecelError::new(&format!("unsupported function call '{}'", name))
+}
+
+/// Builds an evaluator for `-` operator.
+fn build_sub<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  build_arithmetic(lhs, rhs, Value::sub, config)
+}
+
+/// Builds an evaluator for `xor` operator.
+fn build_xor<C: Context + ?Sized + 'static>(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| semantics::xor(lhe(iv), rhe(iv))))
+}
+
+/// Combines evaluators of `or` operands using three-valued (Kleene) logic,
+/// non-boolean operands are treated as unknown (`Null`).
+fn combine_or_kleene<C: Context + ?Sized + 'static>(lhe: ContextEvaluator<C>, rhe: ContextEvaluator<C>) -> ContextEvaluator<C> {
+  Box::new(move |iv: &C| match lhe(iv) {
+    Value::Bool(true) => Value::Bool(true),
+    Value::Bool(false) => semantics::to_bool(rhe(iv)),
+    _ => match rhe(iv) {
+      Value::Bool(true) => Value::Bool(true),
       _ => Value::Null,
     },
-    _ => Value::Null,
-  }))
+  })
 }
 
-/// Builds an evaluator for `or` operator.
-fn build_or(lhs: &AstNode, rhs: &AstNode) -> Result<Evaluator> {
-  let lhe = build_evaluator(lhs)?;
-  let rhe = build_evaluator(rhs)?;
-  Ok(Box::new(move |iv: &IndexedValues| {
-    if let Value::Bool(lhv) = lhe(iv) {
-      if let Value::Bool(rhv) = rhe(iv) {
-        return Value::Bool(lhv || rhv);
-      }
-    }
-    Value::Null
-  }))
+/// Builds an evaluator for arithmetic operator, evaluates to `Null` when any operand
+/// is not a number or the operation fails (overflow, division by zero).
+fn build_arithmetic<C: Context + ?Sized + 'static>(
+  lhs: &AstNode,
+  rhs: &AstNode,
+  operation: fn(Value, Value) -> Value,
+  config: &EvaluatorConfig,
+) -> Result<ContextEvaluator<C>> {
+  let lhe = build_node(lhs, config)?;
+  let rhe = build_node(rhs, config)?;
+  Ok(Box::new(move |iv: &C| operation(lhe(iv), rhe(iv))))
+}
+
+/// Builds an evaluator for `min` and `max` functions, evaluates to `Null` when any operand
+/// is not a number, `Null` operands are skipped when configured.
+fn build_extremum<C: Context + ?Sized + 'static>(
+  mhs: &[AstNode],
+  select: fn(Decimal, Decimal) -> Decimal,
+  config: &EvaluatorConfig,
+) -> Result<ContextEvaluator<C>> {
+  let mhes = mhs.iter().map(|node| build_node(node, config)).collect::<Result<Vec<ContextEvaluator<C>>>>()?;
+  let skip_nulls = config.skip_nulls_in_min_max;
+  Ok(Box::new(move |iv: &C| semantics::extremum(mhes.iter().map(|mhe| mhe(iv)), select, skip_nulls)))
 }
 
 /// Builds an evaluator for `Null` node.
-fn build_null() -> Result<Evaluator> {
-  Ok(Box::new(move |_: &IndexedValues| Value::Null))
+fn build_null<C: Context + ?Sized + 'static>() -> Result<ContextEvaluator<C>> {
+  Ok(Box::new(move |_: &C| Value::Null))
+}
+
+/// Builds an evaluator for `Literal` node.
+fn build_literal<C: Context + ?Sized + 'static>(value: Decimal) -> Result<ContextEvaluator<C>> {
+  Ok(Box::new(move |_: &C| Value::Number(value)))
+}
+
+/// Builds an evaluator for `StrLiteral` node.
+fn build_str_literal<C: Context + ?Sized + 'static>(value: &str) -> Result<ContextEvaluator<C>> {
+  let value = value.to_string();
+  Ok(Box::new(move |_: &C| Value::Str(value.clone())))
+}
+
+/// Builds an evaluator for `DateLiteral` node.
+#[cfg(feature = "chrono")]
+fn build_date_literal<C: Context + ?Sized + 'static>(value: NaiveDate) -> Result<ContextEvaluator<C>> {
+  Ok(Box::new(move |_: &C| Value::Date(value)))
 }
 
 /// Builds an evaluator for `Number` node.
-fn build_number(key: IndexKey) -> Result<Evaluator> {
-  Ok(Box::new(
-    move |iv: &IndexedValues| if let Some(value) = iv.get(&key) { *value } else { Value::Null },
-  ))
+fn build_number<C: Context + ?Sized + 'static>(key: IndexKey, config: &EvaluatorConfig) -> Result<ContextEvaluator<C>> {
+  let missing = config.missing.clone();
+  Ok(Box::new(move |iv: &C| if let Some(value) = iv.get(key) { value } else { missing.clone() }))
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
   use crate::IndexedValues;
-  use rust_decimal::Decimal;
+
+  type BinaryBuilder = fn(&AstNode, &AstNode, &EvaluatorConfig) -> Result<Evaluator>;
+
+  fn config() -> EvaluatorConfig {
+    EvaluatorConfig::default()
+  }
 
   #[test]
   fn test_build_if() {
     let mut m = IndexedValues::new();
     m.insert(1, Value::Bool(true));
     m.insert(2, Value::Bool(true));
-    let b = build_if(&AstNode::Number(255), &AstNode::Number(1), &AstNode::Number(2)).unwrap();
+    let b = build_if(&AstNode::Number(255), &AstNode::Number(1), &AstNode::Number(2), &config()).unwrap();
     assert_eq!(Value::Null, b(&m));
   }
 
@@ -222,7 +598,7 @@ mod tests {
     let mut r = IndexedValues::new();
     r.insert(1, Value::Bool(true));
     r.insert(2, Value::Bool(false));
-    let b = build_ge(&AstNode::Number(1), &AstNode::Number(1)).unwrap();
+    let b = build_ge(&AstNode::Number(1), &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
   }
 
@@ -231,7 +607,7 @@ mod tests {
     let mut r = IndexedValues::new();
     r.insert(1, Value::Bool(true));
     r.insert(2, Value::Bool(false));
-    let b = build_gt(&AstNode::Number(1), &AstNode::Number(1)).unwrap();
+    let b = build_gt(&AstNode::Number(1), &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
   }
 
@@ -240,7 +616,7 @@ mod tests {
     let mut r = IndexedValues::new();
     r.insert(1, Value::Bool(true));
     r.insert(2, Value::Bool(false));
-    let b = build_le(&AstNode::Number(1), &AstNode::Number(1)).unwrap();
+    let b = build_le(&AstNode::Number(1), &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
   }
 
@@ -249,7 +625,23 @@ mod tests {
     let mut r = IndexedValues::new();
     r.insert(1, Value::Bool(true));
     r.insert(2, Value::Bool(false));
-    let b = build_lt(&AstNode::Number(1), &AstNode::Number(1)).unwrap();
+    let b = build_lt(&AstNode::Number(1), &AstNode::Number(1), &config()).unwrap();
+    assert_eq!(Value::Null, b(&r));
+  }
+
+  #[test]
+  fn test_build_ordering_str() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Str("apple".to_string()));
+    r.insert(2, Value::Str("banana".to_string()));
+    r.insert(3, Value::Number(Decimal::new(1, 0)));
+    let b = build_lt(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap();
+    assert_eq!(Value::Bool(true), b(&r));
+    let b = build_ge(&AstNode::Number(1), &AstNode::Number(1), &config()).unwrap();
+    assert_eq!(Value::Bool(true), b(&r));
+    let b = build_gt(&AstNode::Number(1), &AstNode::Number(3), &config()).unwrap();
+    assert_eq!(Value::Null, b(&r));
+    let b = build_le(&AstNode::Number(3), &AstNode::Number(2), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
   }
 
@@ -258,12 +650,16 @@ mod tests {
     let mut r = IndexedValues::new();
     r.insert(1, Value::Bool(true));
     r.insert(2, Value::Number(Decimal::new(123, 2)));
-    let b = build_eq(&AstNode::Null, &AstNode::Number(1)).unwrap();
+    let b = build_eq(&AstNode::Null, &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
-    let b = build_eq(&AstNode::Number(2), &AstNode::Number(1)).unwrap();
+    let b = build_eq(&AstNode::Number(2), &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
-    let b = build_eq(&AstNode::Number(1), &AstNode::Number(2)).unwrap();
+    let b = build_eq(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
+    let b = build_eq(&AstNode::Number(1), &AstNode::Bool(true), &config()).unwrap();
+    assert_eq!(Value::Bool(true), b(&r));
+    let b = build_eq(&AstNode::Bool(false), &AstNode::Number(1), &config()).unwrap();
+    assert_eq!(Value::Bool(false), b(&r));
   }
 
   #[test]
@@ -271,25 +667,29 @@ mod tests {
     let mut r = IndexedValues::new();
     r.insert(1, Value::Bool(true));
     r.insert(2, Value::Number(Decimal::new(123, 2)));
-    let b = build_nq(&AstNode::Null, &AstNode::Number(1)).unwrap();
+    let b = build_nq(&AstNode::Null, &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
-    let b = build_nq(&AstNode::Number(2), &AstNode::Number(1)).unwrap();
+    let b = build_nq(&AstNode::Number(2), &AstNode::Number(1), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
-    let b = build_nq(&AstNode::Number(1), &AstNode::Number(2)).unwrap();
+    let b = build_nq(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
+    let b = build_nq(&AstNode::Number(1), &AstNode::Bool(true), &config()).unwrap();
+    assert_eq!(Value::Bool(false), b(&r));
+    let b = build_nq(&AstNode::Bool(false), &AstNode::Number(1), &config()).unwrap();
+    assert_eq!(Value::Bool(true), b(&r));
   }
 
   #[test]
   fn test_build_and() {
     let r = IndexedValues::new();
-    let b = build_and(&AstNode::Null, &AstNode::Null).unwrap();
+    let b = build_and(&AstNode::Null, &AstNode::Null, &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
   }
 
   #[test]
   fn test_build_or() {
     let r = IndexedValues::new();
-    let b = build_or(&AstNode::Null, &AstNode::Null).unwrap();
+    let b = build_or(&AstNode::Null, &AstNode::Null, &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
   }
 
@@ -303,9 +703,222 @@ mod tests {
   #[test]
   fn test_build_number() {
     let mut r = IndexedValues::new();
-    let b = build_number(1).unwrap();
+    let b = build_number(1, &config()).unwrap();
     assert_eq!(Value::Null, b(&r));
     r.insert(1, Value::Number(Decimal::new(123, 2)));
     assert_eq!(Value::Number(Decimal::new(123, 2)), b(&r));
   }
+
+  #[test]
+  fn test_build_number_missing() {
+    let mut r = IndexedValues::new();
+    let config = EvaluatorConfig {
+      missing: Value::Number(Decimal::ZERO),
+      ..EvaluatorConfig::default()
+    };
+    let b = build_number(1, &config).unwrap();
+    assert_eq!(Value::Number(Decimal::ZERO), b(&r));
+    r.insert(1, Value::Bool(true));
+    assert_eq!(Value::Bool(true), b(&r));
+  }
+
+  #[test]
+  fn test_build_literal() {
+    let r = IndexedValues::new();
+    let b = build_literal(Decimal::new(15, 1)).unwrap();
+    assert_eq!(Value::Number(Decimal::new(15, 1)), b(&r));
+  }
+
+  #[test]
+  fn test_build_bool() {
+    let r = IndexedValues::new();
+    assert_eq!(Value::Bool(true), build_bool(true).unwrap()(&r));
+    assert_eq!(Value::Bool(false), build_bool(false).unwrap()(&r));
+  }
+
+  #[test]
+  fn test_build_str_literal() {
+    let r = IndexedValues::new();
+    let b = build_str_literal("abc").unwrap();
+    assert_eq!(Value::Str("abc".to_string()), b(&r));
+  }
+
+  #[test]
+  fn test_build_arithmetic() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Number(Decimal::new(6, 0)));
+    r.insert(2, Value::Number(Decimal::new(4, 0)));
+    r.insert(3, Value::Number(Decimal::ZERO));
+    r.insert(4, Value::Bool(true));
+    let n = |value: i64, scale: u32| Value::Number(Decimal::new(value, scale));
+    assert_eq!(n(10, 0), build_add(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(n(2, 0), build_sub(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(n(24, 0), build_mul(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(n(15, 1), build_div(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_div(&AstNode::Number(1), &AstNode::Number(3), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_add(&AstNode::Number(1), &AstNode::Null, &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_sub(&AstNode::Number(4), &AstNode::Number(1), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_mul(&AstNode::Number(1), &AstNode::Number(5), &config()).unwrap()(&r));
+  }
+
+  #[test]
+  fn test_build_neg() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Number(Decimal::new(5, 0)));
+    r.insert(2, Value::Str("abc".to_string()));
+    assert_eq!(Value::Number(Decimal::new(-5, 0)), build_neg(&AstNode::Number(1), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_neg(&AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_neg(&AstNode::Null, &config()).unwrap()(&r));
+  }
+
+  #[test]
+  fn test_build_between() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Number(Decimal::new(5, 0)));
+    r.insert(2, Value::Number(Decimal::new(1, 0)));
+    r.insert(3, Value::Number(Decimal::new(10, 0)));
+    let between =
+      |v: IndexKey, lo: IndexKey, hi: IndexKey| build_between(&AstNode::Number(v), &AstNode::Number(lo), &AstNode::Number(hi), &config()).unwrap()(&r);
+    assert_eq!(Value::Bool(true), between(1, 2, 3));
+    assert_eq!(Value::Bool(true), between(1, 1, 3));
+    assert_eq!(Value::Bool(true), between(1, 2, 1));
+    assert_eq!(Value::Bool(false), between(3, 2, 1));
+    assert_eq!(Value::Null, between(1, 4, 3));
+    assert_eq!(Value::Null, between(4, 2, 3));
+  }
+
+  #[test]
+  fn test_build_in() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Number(Decimal::new(5, 0)));
+    r.insert(2, Value::Number(Decimal::new(1, 0)));
+    r.insert(3, Value::Number(Decimal::new(5, 0)));
+    let list = |keys: &[IndexKey]| keys.iter().map(|key| AstNode::Number(*key)).collect::<Vec<AstNode>>();
+    assert_eq!(Value::Bool(true), build_in(&AstNode::Number(1), &list(&[2, 3]), &config()).unwrap()(&r));
+    assert_eq!(Value::Bool(false), build_in(&AstNode::Number(1), &list(&[2]), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_in(&AstNode::Number(1), &list(&[2, 4]), &config()).unwrap()(&r));
+    assert_eq!(Value::Bool(true), build_in(&AstNode::Number(1), &list(&[4, 3]), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_in(&AstNode::Number(4), &list(&[1, 2]), &config()).unwrap()(&r));
+  }
+
+  #[test]
+  fn test_build_is_null() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Number(Decimal::new(5, 0)));
+    r.insert(2, Value::Null);
+    assert_eq!(Value::Bool(false), build_is_null(&AstNode::Number(1), &config()).unwrap()(&r));
+    assert_eq!(Value::Bool(true), build_is_null(&AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(Value::Bool(true), build_is_null(&AstNode::Number(3), &config()).unwrap()(&r));
+    assert_eq!(Value::Bool(true), build_is_not_null(&AstNode::Number(1), &config()).unwrap()(&r));
+    assert_eq!(Value::Bool(false), build_is_not_null(&AstNode::Number(2), &config()).unwrap()(&r));
+  }
+
+  #[test]
+  fn test_build_xor() {
+    let r = IndexedValues::new();
+    let xor = |lhs: AstNode, rhs: AstNode| build_xor(&lhs, &rhs, &config()).unwrap()(&r);
+    assert_eq!(Value::Bool(false), xor(AstNode::Bool(true), AstNode::Bool(true)));
+    assert_eq!(Value::Bool(true), xor(AstNode::Bool(true), AstNode::Bool(false)));
+    assert_eq!(Value::Bool(true), xor(AstNode::Bool(false), AstNode::Bool(true)));
+    assert_eq!(Value::Bool(false), xor(AstNode::Bool(false), AstNode::Bool(false)));
+    assert_eq!(Value::Null, xor(AstNode::Bool(true), AstNode::Null));
+  }
+
+  #[test]
+  fn test_build_arithmetic_null() {
+    let mut r = IndexedValues::new();
+    r.insert(1, Value::Number(Decimal::new(6, 0)));
+    r.insert(2, Value::Number(Decimal::ZERO));
+    r.insert(3, Value::Number(Decimal::new(0, 2)));
+    r.insert(4, Value::Null);
+    r.insert(5, Value::Bool(true));
+    r.insert(6, Value::Str("6".to_string()));
+    assert_eq!(Value::Null, build_div(&AstNode::Number(1), &AstNode::Number(2), &config()).unwrap()(&r));
+    assert_eq!(Value::Null, build_div(&AstNode::Number(1), &AstNode::Number(3), &config()).unwrap()(&r));
+    assert_eq!(
+      Value::Number(Decimal::ZERO),
+      build_div(&AstNode::Number(2), &AstNode::Number(1), &config()).unwrap()(&r)
+    );
+    let operators: [BinaryBuilder; 4] = [build_add, build_sub, build_mul, build_div];
+    for build in operators {
+      for key in [4, 5, 6, 7] {
+        assert_eq!(Value::Null, build(&AstNode::Number(1), &AstNode::Number(key), &config()).unwrap()(&r));
+        assert_eq!(Value::Null, build(&AstNode::Number(key), &AstNode::Number(1), &config()).unwrap()(&r));
+      }
+    }
+  }
+
+  #[test]
+  fn test_short_circuit() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    let r = IndexedValues::new();
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counting = |value: Value| -> Evaluator {
+      let counter = counter.clone();
+      Box::new(move |_: &IndexedValues| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        value.clone()
+      })
+    };
+    let constant = |value: Value| -> Evaluator { Box::new(move |_: &IndexedValues| value.clone()) };
+    assert_eq!(Value::Bool(false), combine_and(constant(Value::Bool(false)), counting(Value::Bool(true)))(&r));
+    assert_eq!(Value::Bool(false), combine_and(constant(Value::Bool(false)), counting(Value::Null))(&r));
+    assert_eq!(Value::Bool(true), combine_or(constant(Value::Bool(true)), counting(Value::Bool(false)))(&r));
+    assert_eq!(Value::Bool(true), combine_or(constant(Value::Bool(true)), counting(Value::Null))(&r));
+    assert_eq!(Value::Null, combine_and(constant(Value::Null), counting(Value::Bool(true)))(&r));
+    assert_eq!(Value::Null, combine_or(constant(Value::Null), counting(Value::Bool(true)))(&r));
+    assert_eq!(0, counter.load(Ordering::Relaxed));
+    assert_eq!(Value::Bool(true), combine_and(constant(Value::Bool(true)), counting(Value::Bool(true)))(&r));
+    assert_eq!(Value::Null, combine_and(constant(Value::Bool(true)), counting(Value::Null))(&r));
+    assert_eq!(Value::Bool(false), combine_or(constant(Value::Bool(false)), counting(Value::Bool(false)))(&r));
+    assert_eq!(Value::Null, combine_or(constant(Value::Bool(false)), counting(Value::Null))(&r));
+    assert_eq!(4, counter.load(Ordering::Relaxed));
+  }
+
+  #[test]
+  fn test_context_without_values() {
+    struct Single(Value);
+    impl Context for Single {
+      fn get(&self, key: IndexKey) -> Option<Value> {
+        (key == 1).then(|| self.0.clone())
+      }
+    }
+    let context = Single(Value::Number(Decimal::new(5, 0)));
+    let evaluate = |node: &AstNode| build_context_evaluator::<Single>(node, &config()).unwrap()(&context);
+    assert_eq!(Value::Number(Decimal::ZERO), evaluate(&AstNode::Sum));
+    assert_eq!(Value::Null, evaluate(&AstNode::Avg));
+    assert_eq!(Value::Number(Decimal::ZERO), evaluate(&AstNode::Count));
+    assert_eq!(Value::Number(Decimal::new(5, 0)), evaluate(&AstNode::Number(1)));
+  }
+
+  #[test]
+  fn test_three_valued_logic() {
+    let r = IndexedValues::new();
+    let (t, f, n) = (AstNode::Bool(true), AstNode::Bool(false), AstNode::Null);
+    let (vt, vf, vn) = (Value::Bool(true), Value::Bool(false), Value::Null);
+    let kleene = EvaluatorConfig {
+      three_valued_logic: true,
+      ..EvaluatorConfig::default()
+    };
+    let and = |lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig| build_and(lhs, rhs, config).unwrap()(&r);
+    let or = |lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig| build_or(lhs, rhs, config).unwrap()(&r);
+    let table = [
+      (&t, &t, &vt, &vt, &vt, &vt),
+      (&t, &f, &vf, &vt, &vf, &vt),
+      (&t, &n, &vn, &vt, &vn, &vt),
+      (&f, &t, &vf, &vt, &vf, &vt),
+      (&f, &f, &vf, &vf, &vf, &vf),
+      (&f, &n, &vf, &vn, &vf, &vn),
+      (&n, &t, &vn, &vn, &vn, &vt),
+      (&n, &f, &vn, &vn, &vf, &vn),
+      (&n, &n, &vn, &vn, &vn, &vn),
+    ];
+    for (lhs, rhs, default_and, default_or, kleene_and, kleene_or) in table {
+      assert_eq!(*default_and, and(lhs, rhs, &config()));
+      assert_eq!(*default_or, or(lhs, rhs, &config()));
+      assert_eq!(*kleene_and, and(lhs, rhs, &kleene));
+      assert_eq!(*kleene_or, or(lhs, rhs, &kleene));
+    }
+  }
 }
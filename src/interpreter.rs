@@ -0,0 +1,224 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Tree-walking interpreter.
+//!
+//! Interpreter evaluates the expression directly from the [AstNode], without building any closures.
+//! Nodes the evaluator fails to build from (like `round` with invalid scale) evaluate to `Null`.
+//! Interpreter recurses on every node and does not apply `max_depth` of the configuration,
+//! the depth of untrusted expressions should be checked before evaluating, see [AstNode::depth].
+
+use crate::ast::AstNode;
+use crate::evaluator::{round_scale, Context, EvaluatorConfig, IndexedValues};
+use crate::semantics::{self, avg_numbers, count_numbers, sum_numbers, Equality};
+use crate::values::Value;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Single step of the evaluation trace, the value computed for a node of specified kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+  /// Kind of the evaluated node, see [AstNode::kind].
+  pub kind: &'static str,
+  /// Value computed for the node.
+  pub value: Value,
+}
+
+/// Evaluates given [AstNode] against specified indexed values using default configuration.
+/// No functions are registered, so calls of user-registered functions evaluate to `Null`,
+/// like calls of unknown functions in the evaluator.
+pub fn eval(node: &AstNode, values: &IndexedValues) -> Value {
+  eval_with_config(node, values, &EvaluatorConfig::default())
+}
+
+/// Evaluates given [AstNode] against specified indexed values using specified configuration.
+pub fn eval_with_config(node: &AstNode, values: &IndexedValues, config: &EvaluatorConfig) -> Value {
+  Interpreter { values, config, steps: None }.eval(node)
+}
+
+/// Evaluates given [AstNode] against specified indexed values and returns the result
+/// together with the values computed for all evaluated nodes, in order of evaluation.
+/// Operands are recorded before the operator, nodes skipped by short-circuit
+/// evaluation and branches of `if` not taken are not recorded.
+pub fn explain(node: &AstNode, values: &IndexedValues) -> (Value, Vec<TraceStep>) {
+  let config = EvaluatorConfig::default();
+  let mut interpreter = Interpreter {
+    values,
+    config: &config,
+    steps: Some(vec![]),
+  };
+  let value = interpreter.eval(node);
+  (value, interpreter.steps.unwrap_or_default())
+}
+
+/// Interpreter state.
+struct Interpreter<'a> {
+  /// Values referenced by indexes.
+  values: &'a IndexedValues,
+  /// Configuration of the evaluation.
+  config: &'a EvaluatorConfig,
+  /// Evaluation trace, `None` when not recorded.
+  steps: Option<Vec<TraceStep>>,
+}
+
+impl<'a> Interpreter<'a> {
+  /// Evaluates the node and records the computed value in the trace.
+  fn eval(&mut self, node: &AstNode) -> Value {
+    let value = self.eval_node(node);
+    if let Some(steps) = &mut self.steps {
+      steps.push(TraceStep {
+        kind: node.kind(),
+        value: value.clone(),
+      });
+    }
+    value
+  }
+
+  /// Evaluates the node, dispatching on node type.
+  fn eval_node(&mut self, node: &AstNode) -> Value {
+    match node {
+      AstNode::Abs(mhs) => semantics::abs(self.eval(mhs)),
+      AstNode::Add(lhs, rhs) => self.eval_arithmetic(lhs, rhs, Value::add),
+      AstNode::And(lhs, rhs) => match self.eval(lhs) {
+        Value::Bool(false) => Value::Bool(false),
+        Value::Bool(true) => semantics::to_bool(self.eval(rhs)),
+        _ if self.config.three_valued_logic => semantics::and(Value::Null, self.eval(rhs)),
+        _ => Value::Null,
+      },
+      AstNode::Avg => avg_numbers(Context::values(self.values)),
+      AstNode::Between(lhs, mhs, rhs) => semantics::between(self.eval(lhs), self.eval(mhs), self.eval(rhs)),
+      AstNode::Bool(mhs) => Value::Bool(*mhs),
+      AstNode::Call(name, mhs) => match self.config.functions.get(name) {
+        Some(function) => function(&mhs.iter().map(|node| self.eval(node)).collect::<Vec<Value>>()),
+        None => Value::Null,
+      },
+      AstNode::Coalesce(mhs) => {
+        for node in mhs {
+          let value = self.eval(node);
+          if value != Value::Null {
+            return value;
+          }
+        }
+        Value::Null
+      }
+      AstNode::Count => count_numbers(Context::values(self.values)),
+      AstNode::Div(lhs, rhs) => self.eval_arithmetic(lhs, rhs, Value::div),
+      AstNode::Eq(lhs, rhs) => Equality::from(self.config).eq(&self.eval(lhs), &self.eval(rhs)),
+      AstNode::Ge(lhs, rhs) => self.eval_ordering(lhs, rhs, Ordering::is_ge),
+      AstNode::Gt(lhs, rhs) => self.eval_ordering(lhs, rhs, Ordering::is_gt),
+      AstNode::If(mhs, lhs, rhs) => match self.eval(mhs) {
+        Value::Bool(true) => self.eval(lhs),
+        Value::Bool(false) => self.eval(rhs),
+        _ => Value::Null,
+      },
+      AstNode::In(lhs, rhs) => {
+        let lhv = self.eval(lhs);
+        Equality::from(self.config).contains(&lhv, rhs.iter().map(|node| self.eval(node)))
+      }
+      AstNode::IsNotNull(mhs) => Value::Bool(self.eval(mhs) != Value::Null),
+      AstNode::IsNull(mhs) => Value::Bool(self.eval(mhs) == Value::Null),
+      AstNode::Le(lhs, rhs) => self.eval_ordering(lhs, rhs, Ordering::is_le),
+      AstNode::Literal(mhs) => Value::Number(*mhs),
+      AstNode::Lt(lhs, rhs) => self.eval_ordering(lhs, rhs, Ordering::is_lt),
+      AstNode::Max(mhs) => self.eval_extremum(mhs, Decimal::max),
+      AstNode::Min(mhs) => self.eval_extremum(mhs, Decimal::min),
+      AstNode::Mul(lhs, rhs) => self.eval_arithmetic(lhs, rhs, Value::mul),
+      AstNode::Neg(mhs) => semantics::neg(self.eval(mhs)),
+      AstNode::Nq(lhs, rhs) => Equality::from(self.config).nq(&self.eval(lhs), &self.eval(rhs)),
+      AstNode::Null => Value::Null,
+      AstNode::Number(mhs) => self.values.get(mhs).unwrap_or(&self.config.missing).clone(),
+      AstNode::Or(lhs, rhs) => match self.eval(lhs) {
+        Value::Bool(true) => Value::Bool(true),
+        Value::Bool(false) => semantics::to_bool(self.eval(rhs)),
+        _ if self.config.three_valued_logic => semantics::or(Value::Null, self.eval(rhs)),
+        _ => Value::Null,
+      },
+      AstNode::Round(lhs, rhs) => match round_scale(rhs) {
+        Ok(scale) => semantics::round(self.eval(lhs), scale),
+        Err(_) => Value::Null,
+      },
+      AstNode::StrLiteral(mhs) => Value::Str(mhs.clone()),
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(mhs) => Value::Date(*mhs),
+      AstNode::Sub(lhs, rhs) => self.eval_arithmetic(lhs, rhs, Value::sub),
+      AstNode::Sum => sum_numbers(Context::values(self.values)),
+      AstNode::Xor(lhs, rhs) => semantics::xor(self.eval(lhs), self.eval(rhs)),
+    }
+  }
+
+  /// Evaluates arithmetic operator, see the arithmetic operators of [Value].
+  fn eval_arithmetic(&mut self, lhs: &AstNode, rhs: &AstNode, operation: fn(Value, Value) -> Value) -> Value {
+    operation(self.eval(lhs), self.eval(rhs))
+  }
+
+  /// Evaluates `min` and `max` functions, see [semantics::extremum].
+  fn eval_extremum(&mut self, mhs: &[AstNode], select: fn(Decimal, Decimal) -> Decimal) -> Value {
+    let skip_nulls = self.config.skip_nulls_in_min_max;
+    semantics::extremum(mhs.iter().map(|node| self.eval(node)), select, skip_nulls)
+  }
+
+  /// Evaluates ordering operator, see [semantics::ordering].
+  fn eval_ordering(&mut self, lhs: &AstNode, rhs: &AstNode, compare: fn(Ordering) -> bool) -> Value {
+    semantics::ordering(&self.eval(lhs), &self.eval(rhs), compare)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+
+  fn step(kind: &'static str, value: Value) -> TraceStep {
+    TraceStep { kind, value }
+  }
+
+  #[test]
+  fn test_explain_if() {
+    let node = Parser::new("if(1 > 2; 1; 2)").parse().unwrap();
+    let mut values = IndexedValues::new();
+    values.insert(1, Value::Number(Decimal::new(1, 0)));
+    values.insert(2, Value::Number(Decimal::new(2, 0)));
+    let (value, steps) = explain(&node, &values);
+    assert_eq!(Value::Number(Decimal::new(2, 0)), value);
+    assert_eq!(
+      vec![
+        step("Number", Value::Number(Decimal::new(1, 0))),
+        step("Number", Value::Number(Decimal::new(2, 0))),
+        step("Gt", Value::Bool(false)),
+        step("Number", Value::Number(Decimal::new(2, 0))),
+        step("If", Value::Number(Decimal::new(2, 0))),
+      ],
+      steps
+    );
+  }
+
+  #[test]
+  fn test_explain_short_circuit() {
+    let node = Parser::new("false and 1 = 2").parse().unwrap();
+    let (value, steps) = explain(&node, &IndexedValues::new());
+    assert_eq!(Value::Bool(false), value);
+    assert_eq!(vec![step("Bool", Value::Bool(false)), step("And", Value::Bool(false))], steps);
+  }
+}
@@ -24,41 +24,262 @@
 
 //! Lexer implementation.
 
+use crate::errors::{Result, SecelError};
+use crate::tokens::TokenSource;
 use crate::IndexKey;
+#[cfg(feature = "chrono")]
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::fmt;
+use std::io::Write;
 
 /// Token definition.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
+  Abs,
   And,
+  Avg,
+  Asterisk,
+  Between,
+  Coalesce,
+  Count,
+  /// Date literal, available with `chrono` feature.
+  #[cfg(feature = "chrono")]
+  Date(NaiveDate),
+  Decimal(Decimal),
+  Else,
+  Elseif,
   Eof,
   Eq,
+  False,
   Ge,
   Gt,
+  Identifier(String),
   If,
+  In,
+  Is,
   Le,
   LeftParen,
   Lt,
+  Max,
+  Min,
+  Minus,
+  Not,
   Number(IndexKey),
   Null,
   Nq,
   Or,
+  Plus,
   RightParen,
+  Round,
   Semicolon,
+  Slash,
+  Str(String),
+  Sum,
+  True,
+  /// Unrecognized character and its position in input.
+  Undef(char, usize),
+  Xor,
+}
+
+impl Token {
+  /// Returns the kind of the token.
+  pub fn kind(&self) -> TokenKind {
+    match self {
+      Token::Abs => TokenKind::Abs,
+      Token::And => TokenKind::And,
+      Token::Avg => TokenKind::Avg,
+      Token::Asterisk => TokenKind::Asterisk,
+      Token::Between => TokenKind::Between,
+      Token::Coalesce => TokenKind::Coalesce,
+      Token::Count => TokenKind::Count,
+      #[cfg(feature = "chrono")]
+      Token::Date(_) => TokenKind::Date,
+      Token::Decimal(_) => TokenKind::Decimal,
+      Token::Else => TokenKind::Else,
+      Token::Elseif => TokenKind::Elseif,
+      Token::Eof => TokenKind::Eof,
+      Token::Eq => TokenKind::Eq,
+      Token::False => TokenKind::False,
+      Token::Ge => TokenKind::Ge,
+      Token::Gt => TokenKind::Gt,
+      Token::Identifier(_) => TokenKind::Identifier,
+      Token::If => TokenKind::If,
+      Token::In => TokenKind::In,
+      Token::Is => TokenKind::Is,
+      Token::Le => TokenKind::Le,
+      Token::LeftParen => TokenKind::LeftParen,
+      Token::Lt => TokenKind::Lt,
+      Token::Max => TokenKind::Max,
+      Token::Min => TokenKind::Min,
+      Token::Minus => TokenKind::Minus,
+      Token::Not => TokenKind::Not,
+      Token::Number(_) => TokenKind::Number,
+      Token::Null => TokenKind::Null,
+      Token::Nq => TokenKind::Nq,
+      Token::Or => TokenKind::Or,
+      Token::Plus => TokenKind::Plus,
+      Token::RightParen => TokenKind::RightParen,
+      Token::Round => TokenKind::Round,
+      Token::Semicolon => TokenKind::Semicolon,
+      Token::Slash => TokenKind::Slash,
+      Token::Str(_) => TokenKind::Str,
+      Token::Sum => TokenKind::Sum,
+      Token::True => TokenKind::True,
+      Token::Undef(..) => TokenKind::Undef,
+      Token::Xor => TokenKind::Xor,
+    }
+  }
+}
+
+/// Kind of the token, without the value carried by literals and identifiers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+  Abs,
+  And,
+  Avg,
+  Asterisk,
+  Between,
+  Coalesce,
+  Count,
+  #[cfg(feature = "chrono")]
+  /// Date literal, available with `chrono` feature.
+  Date,
+  Decimal,
+  Else,
+  Elseif,
+  Eof,
+  Eq,
+  False,
+  Ge,
+  Gt,
+  Identifier,
+  If,
+  In,
+  Is,
+  Le,
+  LeftParen,
+  Lt,
+  Max,
+  Min,
+  Minus,
+  Not,
+  Number,
+  Null,
+  Nq,
+  Or,
+  Plus,
+  RightParen,
+  Round,
+  Semicolon,
+  Slash,
+  Str,
+  Sum,
+  True,
   Undef,
+  Xor,
+}
+
+impl fmt::Display for TokenKind {
+  /// Implementation of [Display](std::fmt::Display) trait for [TokenKind].
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      TokenKind::Abs => "Abs",
+      TokenKind::And => "And",
+      TokenKind::Avg => "Avg",
+      TokenKind::Asterisk => "Asterisk",
+      TokenKind::Between => "Between",
+      TokenKind::Coalesce => "Coalesce",
+      TokenKind::Count => "Count",
+      #[cfg(feature = "chrono")]
+      TokenKind::Date => "Date",
+      TokenKind::Decimal => "Decimal",
+      TokenKind::Else => "Else",
+      TokenKind::Elseif => "Elseif",
+      TokenKind::Eof => "Eof",
+      TokenKind::Eq => "Eq",
+      TokenKind::False => "False",
+      TokenKind::Ge => "Ge",
+      TokenKind::Gt => "Gt",
+      TokenKind::Identifier => "Identifier",
+      TokenKind::If => "If",
+      TokenKind::In => "In",
+      TokenKind::Is => "Is",
+      TokenKind::Le => "Le",
+      TokenKind::LeftParen => "LeftParen",
+      TokenKind::Lt => "Lt",
+      TokenKind::Max => "Max",
+      TokenKind::Min => "Min",
+      TokenKind::Minus => "Minus",
+      TokenKind::Not => "Not",
+      TokenKind::Number => "Number",
+      TokenKind::Null => "Null",
+      TokenKind::Nq => "Nq",
+      TokenKind::Or => "Or",
+      TokenKind::Plus => "Plus",
+      TokenKind::RightParen => "RightParen",
+      TokenKind::Round => "Round",
+      TokenKind::Semicolon => "Semicolon",
+      TokenKind::Slash => "Slash",
+      TokenKind::Str => "Str",
+      TokenKind::Sum => "Sum",
+      TokenKind::True => "True",
+      TokenKind::Undef => "Undef",
+      TokenKind::Xor => "Xor",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// Span of the characters in input that produced a token.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Span {
+  /// Offset of the first character of the token.
+  pub start: usize,
+  /// Offset of the character following the token.
+  pub end: usize,
 }
 
 /// Single space character.
 const WS: char = ' ';
 
-/// Buffer size for lexer input.
-const BUF_SIZE: usize = 4;
+/// Keywords recognized by the lexer.
+const KEYWORDS: [(&str, Token); 21] = [
+  ("abs", Token::Abs),
+  ("and", Token::And),
+  ("avg", Token::Avg),
+  ("between", Token::Between),
+  ("coalesce", Token::Coalesce),
+  ("count", Token::Count),
+  ("else", Token::Else),
+  ("elseif", Token::Elseif),
+  ("false", Token::False),
+  ("if", Token::If),
+  ("in", Token::In),
+  ("is", Token::Is),
+  ("max", Token::Max),
+  ("min", Token::Min),
+  ("not", Token::Not),
+  ("null", Token::Null),
+  ("or", Token::Or),
+  ("round", Token::Round),
+  ("sum", Token::Sum),
+  ("true", Token::True),
+  ("xor", Token::Xor),
+];
 
 /// The lexer.
 pub struct Lexer {
   /// Input characters.
   input: Vec<char>,
+  /// Byte offsets of input characters, followed by the length of input in bytes.
+  offsets: Vec<usize>,
+  /// Offsets of the first characters of input lines.
+  line_starts: Vec<usize>,
   /// Current position in input.
   position: usize,
+  /// Flag indicating that the iterator already returned the last token.
+  exhausted: bool,
 }
 
 impl Lexer {
@@ -66,16 +287,61 @@ impl Lexer {
   pub fn new(input: &str) -> Self {
     Self {
       input: input.chars().collect(),
+      offsets: input.char_indices().map(|(offset, _)| offset).chain(Some(input.len())).collect(),
+      line_starts: Some(0)
+        .into_iter()
+        .chain(input.chars().enumerate().filter(|(_, ch)| *ch == '\n').map(|(offset, _)| offset + 1))
+        .collect(),
       position: 0,
+      exhausted: false,
+    }
+  }
+  /// Splits the whole input into tokens, the last token is always [Token::Eof].
+  pub fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = vec![];
+    loop {
+      match lexer.next_token()? {
+        Token::Eof => {
+          tokens.push(Token::Eof);
+          return Ok(tokens);
+        }
+        Token::Undef(ch, position) => return Err(err_unexpected_character(ch, position)),
+        token => tokens.push(token),
+      }
     }
   }
   ///
   pub fn get_position(&self) -> usize {
     self.position
   }
+  /// Returns the current position in input as a byte offset.
+  pub fn byte_position(&self) -> usize {
+    self.offsets[self.position]
+  }
+  /// Returns the line and column (both starting from 1) of the current position in input.
+  pub fn position_line_col(&self) -> (usize, usize) {
+    self.line_col(self.position)
+  }
+  /// Returns the line and column (both starting from 1) of the specified position in input.
+  pub fn line_col(&self, position: usize) -> (usize, usize) {
+    let line = self.line_starts.partition_point(|start| *start <= position);
+    (line, position - self.line_starts[line - 1] + 1)
+  }
+  /// Converts a span of character offsets into a span of byte offsets.
+  pub fn byte_span(&self, span: Span) -> Span {
+    Span {
+      start: self.offsets[span.start],
+      end: self.offsets[span.end],
+    }
+  }
   ///
   pub fn trace(&self) {
-    print!("{:>4} | {}", self.position, self.input[self.position..].to_vec().iter().collect::<String>());
+    let _ = self.trace_to(&mut std::io::stdout());
+  }
+  /// Writes the current position and the remaining input to specified output.
+  pub fn trace_to(&self, output: &mut dyn Write) -> std::io::Result<()> {
+    write!(output, "{:>4} | {}", self.position, self.input[self.position..].iter().collect::<String>())
   }
   ///
   pub fn set_position(&mut self, position: usize) {
@@ -84,94 +350,174 @@ impl Lexer {
     }
   }
   /// Returns the next token starting from current position.
-  pub fn next_token(&mut self) -> Token {
-    let chars = self.read_input();
-    match chars {
-      ['n', 'u', 'l', 'l'] => {
-        self.position += 4;
-        Token::Null
-      }
-      ['a', 'n', 'd', _] => {
-        self.position += 3;
-        Token::And
-      }
-      ['i', 'f', _, _] => {
-        self.position += 2;
-        Token::If
-      }
-      ['o', 'r', _, _] => {
-        self.position += 2;
-        Token::Or
-      }
-      ['<', '=', _, _] => {
+  pub fn next_token(&mut self) -> Result<Token> {
+    self.next_token_spanned().map(|(token, _)| token)
+  }
+  /// Returns the next token starting from current position without advancing the position.
+  pub fn peek_token(&mut self) -> Result<Token> {
+    let position = self.position;
+    let token = self.next_token();
+    self.position = position;
+    token
+  }
+  /// Returns the next token starting from current position,
+  /// together with the span of characters (offsets) it was read from.
+  pub fn next_token_spanned(&mut self) -> Result<(Token, Span)> {
+    self.consume_whitespace();
+    let start = self.position;
+    let token = self.read_token()?;
+    Ok((token, Span { start, end: self.position }))
+  }
+  /// Reads a single token starting from current position.
+  fn read_token(&mut self) -> Result<Token> {
+    self.consume_whitespace();
+    let ch = match self.char_at(0) {
+      Some(ch) => ch,
+      None => return Ok(Token::Eof),
+    };
+    if is_identifier_start(ch) {
+      return Ok(self.consume_identifier());
+    }
+    Ok(match (ch, self.char_at(1).unwrap_or(WS)) {
+      ('<', '=') => {
         self.position += 2;
         Token::Le
       }
-      ['>', '=', _, _] => {
+      ('>', '=') => {
         self.position += 2;
         Token::Ge
       }
-      ['<', '>', _, _] => {
+      ('<', '>') => {
         self.position += 2;
         Token::Nq
       }
-      ['=', _, _, _] => {
+      ('=', _) => {
         self.position += 1;
         Token::Eq
       }
-      ['<', _, _, _] => {
+      ('<', _) => {
         self.position += 1;
         Token::Lt
       }
-      ['>', _, _, _] => {
+      ('>', _) => {
         self.position += 1;
         Token::Gt
       }
-      [';', _, _, _] => {
+      (';', _) => {
         self.position += 1;
         Token::Semicolon
       }
-      ['(', _, _, _] => {
+      ('(', _) => {
         self.position += 1;
         Token::LeftParen
       }
-      [')', _, _, _] => {
+      (')', _) => {
         self.position += 1;
         Token::RightParen
       }
-      [ch, _, _, _] if is_non_zero_digit(ch) => {
-        let digits = self.consume_digits();
-        if let Ok(number) = digits.parse::<IndexKey>() {
-          Token::Number(number)
-        } else {
-          Token::Undef
+      ('"', _) => self.consume_string()?,
+      #[cfg(feature = "chrono")]
+      ('@', ch) if is_digit(ch) => self.consume_date()?,
+      ('+', _) => {
+        self.position += 1;
+        Token::Plus
+      }
+      ('-', _) => {
+        self.position += 1;
+        Token::Minus
+      }
+      ('*', _) => {
+        self.position += 1;
+        Token::Asterisk
+      }
+      ('/', _) => {
+        self.position += 1;
+        Token::Slash
+      }
+      (ch, _) if is_digit(ch) => self.consume_number()?,
+      ('.', ch) if is_digit(ch) => return Err(SecelError::lex("missing digits before decimal point", Some(self.position))),
+      (ch, _) => Token::Undef(ch, self.position),
+    })
+  }
+  /// Consumes a string literal enclosed in double quotes, `\"` and `\\` are escaped characters.
+  fn consume_string(&mut self) -> Result<Token> {
+    let position = self.position;
+    self.position += 1;
+    let mut value = "".to_string();
+    loop {
+      match (self.char_at(0), self.char_at(1)) {
+        (Some('"'), _) => {
+          self.position += 1;
+          return Ok(Token::Str(value));
         }
+        (Some('\\'), Some(ch @ ('"' | '\\'))) => {
+          value.push(ch);
+          self.position += 2;
+        }
+        (Some(ch), _) => {
+          value.push(ch);
+          self.position += 1;
+        }
+        (None, _) => return Err(SecelError::lex("unterminated string literal", Some(position))),
       }
-      [WS, WS, WS, WS] => Token::Eof,
-      _ => Token::Undef,
     }
   }
-  /// Reads characters from input.
-  fn read_input(&mut self) -> [char; BUF_SIZE] {
-    self.consume_whitespace();
-    let mut buffer: [char; BUF_SIZE] = [WS; BUF_SIZE];
-    for (offset, value) in buffer.iter_mut().enumerate() {
-      if let Some(ch) = self.char_at(offset) {
-        *value = ch
+  /// Consumes a date literal in `@YYYY-MM-DD` format.
+  #[cfg(feature = "chrono")]
+  fn consume_date(&mut self) -> Result<Token> {
+    let position = self.position;
+    self.position += 1;
+    let mut text = "".to_string();
+    while let Some(ch) = self.char_at(0) {
+      if is_digit(ch) || ch == '-' {
+        text.push(ch);
+        self.position += 1;
+      } else {
+        break;
+      }
+    }
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+      .map(Token::Date)
+      .map_err(|_| SecelError::lex(&format!("invalid date literal '@{}'", text), Some(position)))
+  }
+  /// Consumes the longest run of identifier characters and matches it against keywords.
+  fn consume_identifier(&mut self) -> Token {
+    let mut word = "".to_string();
+    while let Some(ch) = self.char_at(0) {
+      if is_identifier_char(ch) {
+        word.push(ch);
+        self.position += 1;
+      } else {
+        break;
       }
     }
-    buffer
+    if let Some((_, token)) = KEYWORDS.iter().find(|(keyword, _)| *keyword == word) {
+      token.clone()
+    } else {
+      Token::Identifier(word)
+    }
   }
-  /// Consumes whitespace characters.
+  /// Consumes whitespace characters and comments.
   fn consume_whitespace(&mut self) {
     while let Some(ch) = self.char_at(0) {
       if is_whitespace(ch) {
         self.position += 1;
+      } else if is_comment_start(ch) {
+        self.consume_comment();
       } else {
         break;
       }
     }
   }
+  /// Consumes a comment up to the end of line, the newline character is left in input.
+  fn consume_comment(&mut self) {
+    while let Some(ch) = self.char_at(0) {
+      if ch == '\n' {
+        break;
+      }
+      self.position += 1;
+    }
+  }
   /// Consumes all digits.
   fn consume_digits(&mut self) -> String {
     let mut digits = "".to_string();
@@ -185,6 +531,35 @@ impl Lexer {
     }
     digits
   }
+  /// Consumes an index number or a decimal literal.
+  fn consume_number(&mut self) -> Result<Token> {
+    let start = self.position;
+    let digits = self.consume_digits();
+    if digits.len() > 1 && digits.starts_with('0') {
+      return Err(SecelError::lex(&format!("leading zero in number literal '{}'", digits), Some(start)));
+    }
+    if self.char_at(0) != Some('.') {
+      // digits are non-empty ASCII digits, so parsing fails only on overflow, reported with the limit
+      // instead of the generic message of the `From<ParseIntError>` conversion
+      return digits
+        .parse::<IndexKey>()
+        .map(Token::Number)
+        .map_err(|_| SecelError::lex(&format!("index {} exceeds maximum {}", digits, IndexKey::MAX), Some(start)));
+    }
+    self.position += 1;
+    let fraction = self.consume_digits();
+    if fraction.is_empty() {
+      return Err(SecelError::lex(&format!("missing digits after decimal point in '{}.'", digits), Some(start)));
+    }
+    let literal = format!("{}.{}", digits, fraction);
+    if self.char_at(0) == Some('.') {
+      return Err(SecelError::lex(&format!("unexpected '.' after decimal literal '{}'", literal), Some(start)));
+    }
+    literal
+      .parse::<Decimal>()
+      .map(Token::Decimal)
+      .map_err(|reason| SecelError::lex(&format!("invalid decimal literal '{}': {}", literal, reason), Some(start)))
+  }
   /// Returns the character at the current cursor position advanced with specified offset.
   fn char_at(&self, offset: usize) -> Option<char> {
     if self.position + offset < self.input.len() {
@@ -195,21 +570,67 @@ impl Lexer {
   }
 }
 
+impl TokenSource for Lexer {
+  fn get_position(&self) -> usize {
+    Lexer::get_position(self)
+  }
+  fn set_position(&mut self, position: usize) {
+    Lexer::set_position(self, position)
+  }
+  fn next_token_spanned(&mut self) -> Result<(Token, Span)> {
+    Lexer::next_token_spanned(self)
+  }
+  fn trace_to(&self, output: &mut dyn Write) -> std::io::Result<()> {
+    Lexer::trace_to(self, output)
+  }
+}
+
+impl Iterator for Lexer {
+  type Item = Token;
+  /// Returns the next token, iteration stops after [Token::Eof] or [Token::Undef] is returned.
+  /// Lexer errors are reported as [Token::Undef] pointing at the beginning of the erroneous token.
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.exhausted {
+      return None;
+    }
+    self.consume_whitespace();
+    let position = self.position;
+    let token = self.next_token().unwrap_or_else(|_| Token::Undef(self.input[position], position));
+    self.exhausted = matches!(token, Token::Eof | Token::Undef(..));
+    Some(token)
+  }
+}
+
+/// Creates an error reporting unrecognized character at specified position.
+pub fn err_unexpected_character(ch: char, position: usize) -> SecelError {
+  SecelError::lex(&format!("unexpected character '{}'", ch), Some(position))
+}
+
 /// Returns `true` when the specified character is a whitespace character.
 fn is_whitespace(ch: char) -> bool {
   matches!(ch, '\u{0009}'..='\u{000D}' | '\u{0020}')
 }
 
+/// Returns `true` when the specified character may start an identifier or a keyword.
+fn is_identifier_start(ch: char) -> bool {
+  ch.is_ascii_alphabetic() || ch == '_'
+}
+
+/// Returns `true` when the specified character may continue an identifier or a keyword.
+fn is_identifier_char(ch: char) -> bool {
+  ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+/// Returns `true` when the specified character starts a line comment.
+fn is_comment_start(ch: char) -> bool {
+  ch == '#'
+}
+
 /// Returns `true` when the specified character is an ASCII digit.
 fn is_digit(ch: char) -> bool {
   ch.is_ascii_digit()
 }
 
-/// Returns `true` when the specified character is a non-zero ASCII digit.
-fn is_non_zero_digit(ch: char) -> bool {
-  ch.is_ascii_digit() && ch != '0'
-}
-
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -218,10 +639,9 @@ mod tests {
     let mut lexer = Lexer::new(input);
     let mut tokens = vec![];
     loop {
-      let token = lexer.next_token();
-
+      let token = lexer.next_token().unwrap();
       match token {
-        t @ Token::Eof | t @ Token::Undef => {
+        t @ Token::Eof | t @ Token::Undef(..) => {
           tokens.push(t);
           break;
         }
@@ -231,6 +651,17 @@ mod tests {
     tokens
   }
 
+  fn error(input: &str) -> String {
+    let mut lexer = Lexer::new(input);
+    loop {
+      match lexer.next_token() {
+        Ok(Token::Eof) | Ok(Token::Undef(..)) => panic!("expected lexer error"),
+        Ok(_) => {}
+        Err(reason) => return reason.to_string(),
+      }
+    }
+  }
+
   #[test]
   fn test_0001() {
     assert!((Token::If == Token::If));
@@ -299,12 +730,12 @@ mod tests {
 
   #[test]
   fn test_0005() {
-    assert_eq!(&[Token::If, Token::LeftParen, Token::Undef], tokenize("if(256=null;1;2)").as_slice());
+    assert_eq!("index 65536 exceeds maximum 65535 at position 3", error("if(65536=null;1;2)"));
   }
 
   #[test]
   fn test_0006() {
-    assert_eq!(&[Token::Undef], tokenize(":").as_slice());
+    assert_eq!(&[Token::Undef(':', 0)], tokenize(":").as_slice());
   }
 
   #[test]
@@ -334,4 +765,403 @@ mod tests {
       tokenize("if(1<2 or 1>3 or 1>4;1;null)").as_slice()
     );
   }
+
+  #[test]
+  fn test_0008() {
+    assert_eq!(&[Token::Number(0), Token::Eof], tokenize("0").as_slice());
+    assert_eq!(&[Token::Number(10), Token::Eof], tokenize("10").as_slice());
+    assert_eq!(
+      &[
+        Token::If,
+        Token::LeftParen,
+        Token::Number(0),
+        Token::Eq,
+        Token::Number(1),
+        Token::Semicolon,
+        Token::Number(1),
+        Token::Semicolon,
+        Token::Number(2),
+        Token::RightParen,
+        Token::Eof
+      ],
+      tokenize("if(0=1;1;2)").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0009() {
+    assert_eq!("leading zero in number literal '00' at position 0", error("00"));
+    assert_eq!("leading zero in number literal '01' at position 0", error("01"));
+    assert_eq!("leading zero in number literal '007' at position 3", error("if(007=1;1;2)"));
+  }
+
+  #[test]
+  fn test_0010() {
+    assert_eq!(&[Token::Decimal(Decimal::new(12345, 2)), Token::Eof], tokenize("123.45").as_slice());
+    assert_eq!(&[Token::Decimal(Decimal::new(5, 1)), Token::Eof], tokenize("0.5").as_slice());
+    assert_eq!(
+      &[Token::Number(1), Token::Gt, Token::Decimal(Decimal::new(15, 1)), Token::Eof],
+      tokenize("1>1.5").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0011() {
+    assert_eq!("missing digits after decimal point in '12.' at position 0", error("12."));
+    assert_eq!("unexpected '.' after decimal literal '1.2' at position 0", error("1.2.3"));
+    assert_eq!("missing digits before decimal point at position 0", error(".5"));
+    assert_eq!("leading zero in number literal '00' at position 0", error("00.5"));
+  }
+
+  #[test]
+  fn test_0012() {
+    assert_eq!(&[Token::Minus, Token::Number(1), Token::Eof], tokenize("-1").as_slice());
+    assert_eq!(&[Token::Minus, Token::Decimal(Decimal::new(25, 1)), Token::Eof], tokenize("-2.5").as_slice());
+    assert_eq!(&[Token::Minus, Token::Number(1), Token::Eof], tokenize("- 1").as_slice());
+    assert_eq!(&[Token::Minus, Token::Minus, Token::Number(1), Token::Eof], tokenize("--1").as_slice());
+    assert_eq!("leading zero in number literal '01' at position 1", error("-01"));
+  }
+
+  #[test]
+  fn test_0013() {
+    let mut lexer = Lexer::new("if ( 12 >= -1.5 )");
+    let mut spans = vec![];
+    loop {
+      let (token, span) = lexer.next_token_spanned().unwrap();
+      spans.push((token, span.start, span.end));
+      if spans.last().unwrap().0 == Token::Eof {
+        break;
+      }
+    }
+    assert_eq!(
+      vec![
+        (Token::If, 0, 2),
+        (Token::LeftParen, 3, 4),
+        (Token::Number(12), 5, 7),
+        (Token::Ge, 8, 10),
+        (Token::Minus, 11, 12),
+        (Token::Decimal(Decimal::new(15, 1)), 12, 15),
+        (Token::RightParen, 16, 17),
+        (Token::Eof, 17, 17)
+      ],
+      spans
+    );
+  }
+
+  #[test]
+  fn test_0014() {
+    let input = "żółw 12";
+    let mut lexer = Lexer::new(input);
+    lexer.set_position(4);
+    assert_eq!(4, lexer.get_position());
+    assert_eq!(7, lexer.byte_position());
+    let (token, span) = lexer.next_token_spanned().unwrap();
+    assert_eq!(Token::Number(12), token);
+    assert_eq!(Span { start: 5, end: 7 }, span);
+    let span = lexer.byte_span(span);
+    assert_eq!(Span { start: 8, end: 10 }, span);
+    assert_eq!("12", &input[span.start..span.end]);
+    assert_eq!(10, lexer.byte_position());
+  }
+
+  #[test]
+  fn test_0015() {
+    let mut lexer = Lexer::new("if(1 = 2;\n  10;\r\n\n  null)");
+    assert_eq!((1, 1), lexer.position_line_col());
+    let mut positions = vec![];
+    loop {
+      let token = lexer.next_token().unwrap();
+      positions.push(lexer.position_line_col());
+      if token == Token::Eof {
+        break;
+      }
+    }
+    assert_eq!(
+      vec![(1, 3), (1, 4), (1, 5), (1, 7), (1, 9), (1, 10), (2, 5), (2, 6), (4, 7), (4, 8), (4, 8)],
+      positions
+    );
+    lexer.set_position(12);
+    assert_eq!((2, 3), lexer.position_line_col());
+  }
+
+  #[test]
+  fn test_0016() {
+    let mut lexer = Lexer::new("  1 >= 2");
+    assert_eq!(Token::Number(1), lexer.peek_token().unwrap());
+    assert_eq!(Token::Number(1), lexer.peek_token().unwrap());
+    assert_eq!(0, lexer.get_position());
+    assert_eq!(Token::Number(1), lexer.next_token().unwrap());
+    assert_eq!(Token::Ge, lexer.peek_token().unwrap());
+    assert_eq!(Token::Ge, lexer.peek_token().unwrap());
+    assert_eq!(3, lexer.get_position());
+    assert!(Lexer::new("01").peek_token().is_err());
+  }
+
+  #[test]
+  fn test_0017() {
+    assert_eq!(tokenize("if(1<2 or 1>3;1;null)"), Lexer::tokenize("if(1<2 or 1>3;1;null)").unwrap());
+    assert_eq!(vec![Token::Eof], Lexer::tokenize("  ").unwrap());
+    assert_eq!(
+      "unexpected character ':' at position 4",
+      Lexer::tokenize("if(1:2;1;2)").unwrap_err().to_string()
+    );
+    assert_eq!(
+      "leading zero in number literal '01' at position 3",
+      Lexer::tokenize("if(01=2;1;2)").unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn test_0018() {
+    assert_eq!(
+      &[
+        Token::If,
+        Token::LeftParen,
+        Token::True,
+        Token::Or,
+        Token::False,
+        Token::Semicolon,
+        Token::Number(1),
+        Token::Semicolon,
+        Token::Number(2),
+        Token::RightParen,
+        Token::Eof
+      ],
+      tokenize("if(true or false;1;2)").as_slice()
+    );
+    assert_eq!(&[Token::Identifier("fals".to_string()), Token::Eof], tokenize("fals").as_slice());
+  }
+
+  #[test]
+  fn test_0019() {
+    let expected = &[
+      Token::If,
+      Token::LeftParen,
+      Token::Number(1),
+      Token::Eq,
+      Token::Number(2),
+      Token::Semicolon,
+      Token::Number(1),
+      Token::Semicolon,
+      Token::Number(2),
+      Token::RightParen,
+      Token::Eof,
+    ];
+    assert_eq!(expected, tokenize("if(1=2;1;2) # pick first\n").as_slice());
+    assert_eq!(expected, tokenize("if(1=2;\n# first branch\n1;\n  # second branch\n2)").as_slice());
+    assert_eq!(expected, tokenize("if(1=2;1;2)#").as_slice());
+    assert_eq!(expected, tokenize("if(1=2;1;2) # no newline; 3").as_slice());
+  }
+
+  #[test]
+  fn test_0020() {
+    assert_eq!(&[Token::And, Token::Eof], tokenize("and").as_slice());
+    assert_eq!(&[Token::Null, Token::Eof], tokenize("null").as_slice());
+    assert_eq!(&[Token::Or, Token::Or, Token::Eof], tokenize("or\tor").as_slice());
+    assert_eq!(&[Token::Identifier("nulls".to_string()), Token::Eof], tokenize("nulls").as_slice());
+    assert_eq!(
+      &[Token::Identifier("iff".to_string()), Token::LeftParen, Token::Eof],
+      tokenize("iff(").as_slice()
+    );
+    assert_eq!(
+      &[Token::Number(1), Token::Identifier("andor".to_string()), Token::Number(2), Token::Eof],
+      tokenize("1 andor 2").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0021() {
+    assert_eq!(&[Token::Number(256), Token::Eof], tokenize("256").as_slice());
+    assert_eq!(&[Token::Number(1000), Token::Eof], tokenize("1000").as_slice());
+    assert_eq!(&[Token::Number(65535), Token::Eof], tokenize("65535").as_slice());
+  }
+
+  #[test]
+  fn test_0022() {
+    assert_eq!("index 70000 exceeds maximum 65535 at position 0", error("70000"));
+    assert_eq!("index 99999999999999999999 exceeds maximum 65535 at position 0", error("99999999999999999999"));
+  }
+
+  #[test]
+  fn test_0023() {
+    assert_eq!(
+      &[
+        Token::If,
+        Token::LeftParen,
+        Token::Identifier("price".to_string()),
+        Token::Gt,
+        Token::Identifier("unit_cost2".to_string()),
+        Token::Semicolon,
+        Token::Identifier("_price".to_string()),
+        Token::Semicolon,
+        Token::Identifier("cost".to_string()),
+        Token::RightParen,
+        Token::Eof
+      ],
+      tokenize("if(price > unit_cost2; _price; cost)").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0024() {
+    assert_eq!(
+      &[Token::Number(1), Token::Eq, Token::Str("A1".to_string()), Token::Eof],
+      tokenize(r#"1 = "A1""#).as_slice()
+    );
+    assert_eq!(&[Token::Str("".to_string()), Token::Eof], tokenize(r#""""#).as_slice());
+    assert_eq!(&[Token::Str(r#"say "hi""#.to_string()), Token::Eof], tokenize(r#""say \"hi\"""#).as_slice());
+    assert_eq!(&[Token::Str(r"a\b\".to_string()), Token::Eof], tokenize(r#""a\b\\""#).as_slice());
+    assert_eq!(&[Token::Str("a # b".to_string()), Token::Eof], tokenize(r#""a # b" # comment"#).as_slice());
+    assert_eq!("unterminated string literal at position 4", error(r#"1 = "abc"#));
+  }
+
+  #[test]
+  fn test_0025() {
+    for input in ["if(1<2 or 1>3 or 1>4;1;null)", "if(1:2;1;2)", ""] {
+      assert_eq!(tokenize(input), Lexer::new(input).collect::<Vec<Token>>());
+    }
+    assert_eq!(
+      vec![Token::Number(1), Token::Eq, Token::Undef('0', 4)],
+      Lexer::new("1 = 01").collect::<Vec<Token>>()
+    );
+    let mut tokens = vec![];
+    for token in Lexer::new("true or false") {
+      tokens.push(token);
+    }
+    assert_eq!(vec![Token::True, Token::Or, Token::False, Token::Eof], tokens);
+  }
+
+  #[test]
+  fn test_0026() {
+    assert_eq!(
+      &[
+        Token::Number(1),
+        Token::Plus,
+        Token::Number(2),
+        Token::Asterisk,
+        Token::Number(3),
+        Token::Minus,
+        Token::Decimal(Decimal::new(45, 1)),
+        Token::Slash,
+        Token::Number(5),
+        Token::Gt,
+        Token::Number(6),
+        Token::Eof
+      ],
+      tokenize("1+2 * 3-4.5/5>6").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0027() {
+    assert_eq!(
+      &[Token::Number(1), Token::Between, Token::Number(2), Token::And, Token::Number(3), Token::Eof],
+      tokenize("1 between 2 and 3").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0028() {
+    assert_eq!(
+      &[
+        Token::Number(1),
+        Token::In,
+        Token::LeftParen,
+        Token::Number(2),
+        Token::Semicolon,
+        Token::Number(3),
+        Token::RightParen,
+        Token::Eof
+      ],
+      tokenize("1 in (2;3)").as_slice()
+    );
+    assert_eq!(&[Token::Identifier("index".to_string()), Token::Eof], tokenize("index").as_slice());
+  }
+
+  #[test]
+  fn test_0029() {
+    assert_eq!(&[Token::Number(1), Token::Is, Token::Null, Token::Eof], tokenize("1 is null").as_slice());
+    assert_eq!(
+      &[Token::Number(1), Token::Is, Token::Not, Token::Null, Token::Eof],
+      tokenize("1 is not null").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0030() {
+    assert_eq!(&[Token::True, Token::Xor, Token::False, Token::Eof], tokenize("true xor false").as_slice());
+  }
+
+  #[test]
+  fn test_0031() {
+    assert_eq!(
+      &[Token::Elseif, Token::Else, Token::Identifier("elsewhere".to_string()), Token::Eof],
+      tokenize("elseif else elsewhere").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0032() {
+    assert_eq!(
+      &[
+        Token::Coalesce,
+        Token::LeftParen,
+        Token::Number(1),
+        Token::Semicolon,
+        Token::Number(2),
+        Token::RightParen,
+        Token::Identifier("coalesced".to_string()),
+        Token::Eof
+      ],
+      tokenize("coalesce(1;2) coalesced").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0033() {
+    assert_eq!(
+      &[Token::Min, Token::Max, Token::Identifier("minimum".to_string()), Token::Eof],
+      tokenize("min max minimum").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0034() {
+    assert_eq!(
+      &[Token::Abs, Token::Round, Token::Identifier("rounding".to_string()), Token::Eof],
+      tokenize("abs round rounding").as_slice()
+    );
+  }
+
+  #[test]
+  fn test_0035() {
+    assert_eq!(
+      &[Token::Sum, Token::Avg, Token::Count, Token::Identifier("summary".to_string()), Token::Eof],
+      tokenize("sum avg count summary").as_slice()
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "chrono")]
+  fn test_0036() {
+    let date = |year, month, day| Token::Date(NaiveDate::from_ymd_opt(year, month, day).unwrap());
+    assert_eq!(
+      &[date(2024, 1, 31), Token::Lt, date(2024, 2, 1), Token::Eof],
+      tokenize("@2024-01-31 < @2024-02-01").as_slice()
+    );
+    assert_eq!(
+      "invalid date literal '@2024-13-01' at position 4",
+      Lexer::tokenize("1 > @2024-13-01").unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn test_0037() {
+    let error = SecelError::from("70000".parse::<IndexKey>().unwrap_err());
+    assert!(matches!(error, SecelError::Lex { position: None, .. }));
+    assert_eq!("invalid integer: number too large to fit in target type", error.to_string());
+    let parse = |digits: &str| -> Result<IndexKey> { Ok(digits.parse::<IndexKey>()?) };
+    assert_eq!(Ok(12), parse("12"));
+    assert_eq!("invalid integer: invalid digit found in string", parse("1a").unwrap_err().to_string());
+  }
 }
@@ -23,22 +23,53 @@
  */
 
 extern crate ascii_tree;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 extern crate difference;
 extern crate rust_decimal;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 mod ast;
+mod binary;
+pub mod builder;
+mod compiled;
+mod diff;
 mod errors;
 mod evaluator;
+mod interpreter;
 mod lexer;
+mod optimizer;
 mod parser;
+mod semantics;
+mod slots;
+mod strict;
+mod tokens;
+mod types;
 mod values;
+mod vm;
 
 #[cfg(test)]
 mod tests;
 
-pub use ast::AstNode;
-pub use evaluator::{Evaluator, IndexKey, IndexedValues};
+pub use ast::{ast_to_dot, ast_to_tree, ast_to_tree_with, walk, AstInterner, AstIter, AstNode, TreeStyle, Visitor};
+pub use compiled::{compile, Compiled};
+pub use diff::{ast_diff, DiffOp};
+pub use errors::{Result, SecelError};
+pub use evaluator::{
+  build_context_evaluator, evaluate_into, Context, ContextEvaluator, Evaluator, EvaluatorConfig, Function, Functions, IndexKey, IndexedValues,
+  DEFAULT_MAX_EVALUATOR_DEPTH,
+};
+pub use interpreter::{eval, eval_with_config, explain, TraceStep};
+pub use lexer::{Lexer, Span, Token, TokenKind};
+pub use optimizer::optimize;
+pub use parser::{Parser, ParserConfig, DEFAULT_MAX_DEPTH};
+pub use slots::{build_slot_evaluator, SlotEvaluator};
+pub use strict::{build_evaluator_strict, build_evaluator_strict_with_functions, evaluate_opt, StrictEvaluator};
+pub use tokens::{TokenCursor, TokenSource};
+pub use types::ExprType;
 pub use values::Value;
+pub use vm::{Op, Program};
 
 /// Parses expression, panics on failure.
 pub fn parse_expression(input: &str) -> AstNode {
@@ -50,3 +81,9 @@ pub fn build_evaluator(input: &str) -> Evaluator {
   let node = parser::Parser::new(input).parse().unwrap();
   evaluator::build_evaluator(&node).unwrap()
 }
+
+/// Builds evaluator using specified configuration, panics on failure.
+pub fn build_evaluator_with_config(input: &str, config: &EvaluatorConfig) -> Evaluator {
+  let node = parser::Parser::new(input).parse().unwrap();
+  evaluator::build_evaluator_with_config(&node, config).unwrap()
+}
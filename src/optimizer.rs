@@ -0,0 +1,358 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Constant folding implementation.
+//!
+//! Optimizer replaces subtrees whose operands are all literals (or `null`) with the value
+//! they evaluate to, folds comparisons of an expression with itself (`1 = 1`),
+//! and removes branches of `if` expressions with constant conditions.
+//! Simplification removes boolean literals from logical operators, where it does not change the result.
+
+use crate::ast::AstNode;
+use crate::evaluator::{build_node, EvaluatorConfig, IndexedValues};
+use crate::values::Value;
+
+/// Folds constant subtrees of given [AstNode], following the semantics of the evaluator built with default configuration.
+/// Folding recurses on every node, the depth of the tree is not limited, see [AstNode::depth].
+pub fn optimize(node: AstNode) -> AstNode {
+  fold(node, &EvaluatorConfig::default())
+}
+
+/// Folds constant subtrees of given [AstNode], following the semantics of the evaluator built with specified configuration.
+pub(crate) fn fold(node: AstNode, config: &EvaluatorConfig) -> AstNode {
+  let node = match node {
+    AstNode::Abs(mhs) => AstNode::Abs(fold_box(mhs, config)),
+    AstNode::Add(lhs, rhs) => AstNode::Add(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::And(lhs, rhs) => match fold(*lhs, config) {
+      AstNode::Bool(false) => return AstNode::Bool(false),
+      lhs => AstNode::And(Box::new(lhs), fold_box(rhs, config)),
+    },
+    AstNode::Between(lhs, mhs, rhs) => AstNode::Between(fold_box(lhs, config), fold_box(mhs, config), fold_box(rhs, config)),
+    AstNode::Call(name, mhs) => AstNode::Call(name, mhs.into_iter().map(|node| fold(node, config)).collect()),
+    AstNode::Coalesce(mhs) => AstNode::Coalesce(mhs.into_iter().map(|node| fold(node, config)).collect()),
+    AstNode::Div(lhs, rhs) => AstNode::Div(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Eq(lhs, rhs) => match (fold_box(lhs, config), fold_box(rhs, config)) {
+      (lhs, rhs) if is_reflexive(&lhs, &rhs, config) => return AstNode::Bool(true),
+      (lhs, rhs) => AstNode::Eq(lhs, rhs),
+    },
+    AstNode::Ge(lhs, rhs) => AstNode::Ge(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Gt(lhs, rhs) => AstNode::Gt(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::If(mhs, lhs, rhs) => match fold(*mhs, config) {
+      AstNode::Bool(true) => return fold(*lhs, config),
+      AstNode::Bool(false) => return fold(*rhs, config),
+      mhs if is_constant(&mhs) => return AstNode::Null,
+      mhs => AstNode::If(Box::new(mhs), fold_box(lhs, config), fold_box(rhs, config)),
+    },
+    AstNode::In(lhs, rhs) => AstNode::In(fold_box(lhs, config), rhs.into_iter().map(|node| fold(node, config)).collect()),
+    AstNode::IsNotNull(mhs) => AstNode::IsNotNull(fold_box(mhs, config)),
+    AstNode::IsNull(mhs) => AstNode::IsNull(fold_box(mhs, config)),
+    AstNode::Le(lhs, rhs) => AstNode::Le(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Lt(lhs, rhs) => AstNode::Lt(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Max(mhs) => AstNode::Max(mhs.into_iter().map(|node| fold(node, config)).collect()),
+    AstNode::Min(mhs) => AstNode::Min(mhs.into_iter().map(|node| fold(node, config)).collect()),
+    AstNode::Mul(lhs, rhs) => AstNode::Mul(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Neg(mhs) => AstNode::Neg(fold_box(mhs, config)),
+    AstNode::Nq(lhs, rhs) => match (fold_box(lhs, config), fold_box(rhs, config)) {
+      (lhs, rhs) if is_reflexive(&lhs, &rhs, config) => return AstNode::Bool(false),
+      (lhs, rhs) => AstNode::Nq(lhs, rhs),
+    },
+    AstNode::Or(lhs, rhs) => match fold(*lhs, config) {
+      AstNode::Bool(true) => return AstNode::Bool(true),
+      lhs => AstNode::Or(Box::new(lhs), fold_box(rhs, config)),
+    },
+    AstNode::Round(lhs, rhs) => AstNode::Round(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Sub(lhs, rhs) => AstNode::Sub(fold_box(lhs, config), fold_box(rhs, config)),
+    AstNode::Xor(lhs, rhs) => AstNode::Xor(fold_box(lhs, config), fold_box(rhs, config)),
+    leaf => return leaf,
+  };
+  if has_constant_operands(&node) {
+    if let Ok(evaluator) = build_node::<IndexedValues>(&node, config) {
+      return value_to_node(evaluator(&IndexedValues::new()));
+    }
+  }
+  node
+}
+
+/// Simplifies logical operators with boolean literal operands, following the semantics of the evaluator
+/// built with default configuration: `x and true`, `x or false` and `x xor false` are replaced with `x`
+/// (when `x` evaluates to boolean or `null`), `false and x` with `false` and `true or x` with `true`.
+pub(crate) fn simplify(mut node: AstNode) -> AstNode {
+  for child in node.children_mut() {
+    *child = simplify(std::mem::replace(child, AstNode::Null));
+  }
+  match node {
+    AstNode::And(lhs, rhs) => match (*lhs, *rhs) {
+      (AstNode::Bool(false), _) => AstNode::Bool(false),
+      (AstNode::Bool(true), mhs) | (mhs, AstNode::Bool(true)) if is_boolean(&mhs) => mhs,
+      (lhs, rhs) => AstNode::And(Box::new(lhs), Box::new(rhs)),
+    },
+    AstNode::Or(lhs, rhs) => match (*lhs, *rhs) {
+      (AstNode::Bool(true), _) => AstNode::Bool(true),
+      (AstNode::Bool(false), mhs) | (mhs, AstNode::Bool(false)) if is_boolean(&mhs) => mhs,
+      (lhs, rhs) => AstNode::Or(Box::new(lhs), Box::new(rhs)),
+    },
+    AstNode::Xor(lhs, rhs) => match (*lhs, *rhs) {
+      (AstNode::Bool(false), mhs) | (mhs, AstNode::Bool(false)) if is_boolean(&mhs) => mhs,
+      (lhs, rhs) => AstNode::Xor(Box::new(lhs), Box::new(rhs)),
+    },
+    other => other,
+  }
+}
+
+/// Returns `true` when the node always evaluates to boolean or `null`.
+fn is_boolean(node: &AstNode) -> bool {
+  matches!(
+    node,
+    AstNode::And(..)
+      | AstNode::Between(..)
+      | AstNode::Bool(_)
+      | AstNode::Eq(..)
+      | AstNode::Ge(..)
+      | AstNode::Gt(..)
+      | AstNode::In(..)
+      | AstNode::IsNotNull(_)
+      | AstNode::IsNull(_)
+      | AstNode::Le(..)
+      | AstNode::Lt(..)
+      | AstNode::Nq(..)
+      | AstNode::Or(..)
+      | AstNode::Xor(..)
+  )
+}
+
+/// Folds boxed node, reusing the allocation.
+fn fold_box(mut node: Box<AstNode>, config: &EvaluatorConfig) -> Box<AstNode> {
+  *node = fold(std::mem::replace(&mut *node, AstNode::Null), config);
+  node
+}
+
+/// Returns `true` when the node is a literal or `null`.
+pub(crate) fn is_constant(node: &AstNode) -> bool {
+  match node {
+    AstNode::Bool(_) | AstNode::Literal(_) | AstNode::Null | AstNode::StrLiteral(_) => true,
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(_) => true,
+    _ => false,
+  }
+}
+
+/// Returns `true` when the operands of `=` operator always evaluate to equal values, that is when operands
+/// are the same expression without calls of user-registered functions and `null` equals `null`.
+fn is_reflexive(lhs: &AstNode, rhs: &AstNode, config: &EvaluatorConfig) -> bool {
+  config.null_equals_null && lhs == rhs && !lhs.iter().any(|node| matches!(node, AstNode::Call(..)))
+}
+
+/// Returns `true` when all operands of the operator node are constant.
+fn has_constant_operands(node: &AstNode) -> bool {
+  match node {
+    AstNode::Add(lhs, rhs)
+    | AstNode::And(lhs, rhs)
+    | AstNode::Div(lhs, rhs)
+    | AstNode::Eq(lhs, rhs)
+    | AstNode::Ge(lhs, rhs)
+    | AstNode::Gt(lhs, rhs)
+    | AstNode::Le(lhs, rhs)
+    | AstNode::Lt(lhs, rhs)
+    | AstNode::Mul(lhs, rhs)
+    | AstNode::Nq(lhs, rhs)
+    | AstNode::Or(lhs, rhs)
+    | AstNode::Round(lhs, rhs)
+    | AstNode::Sub(lhs, rhs)
+    | AstNode::Xor(lhs, rhs) => is_constant(lhs) && is_constant(rhs),
+    AstNode::Between(lhs, mhs, rhs) | AstNode::If(mhs, lhs, rhs) => is_constant(lhs) && is_constant(mhs) && is_constant(rhs),
+    AstNode::Coalesce(mhs) | AstNode::Max(mhs) | AstNode::Min(mhs) => mhs.iter().all(is_constant),
+    AstNode::In(lhs, rhs) => is_constant(lhs) && rhs.iter().all(is_constant),
+    AstNode::Abs(mhs) | AstNode::IsNotNull(mhs) | AstNode::IsNull(mhs) | AstNode::Neg(mhs) => is_constant(mhs),
+    _ => false,
+  }
+}
+
+/// Converts the value into the node representing the same constant.
+pub(crate) fn value_to_node(value: Value) -> AstNode {
+  match value {
+    Value::Bool(value) => AstNode::Bool(value),
+    Value::Null => AstNode::Null,
+    Value::Number(value) => AstNode::Literal(value),
+    Value::Int(value) => AstNode::Literal(value.into()),
+    Value::Str(value) => AstNode::StrLiteral(value),
+    #[cfg(feature = "chrono")]
+    Value::Date(value) => AstNode::DateLiteral(value),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+  use rust_decimal::Decimal;
+
+  fn opt(input: &str) -> AstNode {
+    optimize(Parser::new(input).parse().unwrap())
+  }
+
+  fn boxed(node: AstNode) -> Box<AstNode> {
+    Box::new(node)
+  }
+
+  #[test]
+  fn test_fold_comparisons() {
+    assert_eq!(AstNode::Bool(true), opt("1.0 = 1.0"));
+    assert_eq!(AstNode::Bool(true), opt("null = null"));
+    assert_eq!(AstNode::Bool(false), opt("null <> null"));
+    assert_eq!(AstNode::Bool(false), opt("null = 1.0"));
+    assert_eq!(AstNode::Null, opt("null > 1.0"));
+    assert_eq!(AstNode::Bool(true), opt("2.0 >= 1.0 + 1.0"));
+    assert_eq!(AstNode::Bool(true), opt("\"a\" in (\"b\"; \"a\")"));
+    assert_eq!(AstNode::Bool(false), opt("1.5 between 2.0 and 3.0"));
+    assert_eq!(AstNode::Bool(true), opt("null is null"));
+    assert_eq!(AstNode::Bool(false), opt("null is not null"));
+  }
+
+  #[test]
+  fn test_fold_boolean_operators() {
+    assert_eq!(AstNode::Bool(false), opt("true and false"));
+    assert_eq!(AstNode::Bool(true), opt("false or true"));
+    assert_eq!(AstNode::Bool(true), opt("true xor false"));
+    assert_eq!(AstNode::Bool(false), opt("false and 1 = 2"));
+    assert_eq!(AstNode::Bool(true), opt("true or 1 = 2"));
+    assert_eq!(AstNode::Null, opt("null = 1.0 or null > 1.0"));
+    assert_eq!(
+      AstNode::And(
+        boxed(AstNode::Bool(true)),
+        boxed(AstNode::Eq(boxed(AstNode::Number(1)), boxed(AstNode::Number(2))))
+      ),
+      opt("1.0 = 1.0 and 1 = 2")
+    );
+  }
+
+  #[test]
+  fn test_fold_arithmetic() {
+    assert_eq!(AstNode::Literal(Decimal::new(7, 0)), opt("1.0 + 2.0 * 3.0"));
+    assert_eq!(AstNode::Null, opt("1.0 / 0.0"));
+    assert_eq!(AstNode::Literal(Decimal::new(-3, 0)), opt("-1.0 - 2.0"));
+  }
+
+  #[test]
+  fn test_fold_if() {
+    assert_eq!(AstNode::Number(1), opt("if(true; 1; 2)"));
+    assert_eq!(AstNode::Number(2), opt("if(1.0 > 2.0; 1; 2)"));
+    assert_eq!(AstNode::Null, opt("if(null > 1.0; 1; 2)"));
+    assert_eq!(
+      AstNode::If(
+        boxed(AstNode::Gt(boxed(AstNode::Number(1)), boxed(AstNode::Number(2)))),
+        boxed(AstNode::Number(3)),
+        boxed(AstNode::Number(2))
+      ),
+      opt("if(1 > 2; if(true; 3; 4); 2)")
+    );
+  }
+
+  #[test]
+  fn test_fold_reflexive_comparisons() {
+    assert_eq!(AstNode::Bool(true), opt("1 = 1"));
+    assert_eq!(AstNode::Bool(false), opt("1 + 2 <> 1 + 2"));
+    assert_eq!(AstNode::Number(2), opt("if(abs(1) = abs(1); 2; 3)"));
+    assert_eq!(
+      AstNode::Eq(boxed(AstNode::Call("f".to_string(), vec![])), boxed(AstNode::Call("f".to_string(), vec![]))),
+      opt("f() = f()")
+    );
+    let config = EvaluatorConfig {
+      null_equals_null: false,
+      ..Default::default()
+    };
+    assert_eq!(
+      AstNode::Eq(boxed(AstNode::Number(1)), boxed(AstNode::Number(1))),
+      fold(Parser::new("1 = 1").parse().unwrap(), &config)
+    );
+  }
+
+  #[test]
+  fn test_keep_non_constant() {
+    assert_eq!(
+      AstNode::Gt(boxed(AstNode::Number(1)), boxed(AstNode::Literal(Decimal::new(3, 0)))),
+      opt("1 > 1.0 + 2.0")
+    );
+    assert_eq!(
+      AstNode::And(
+        boxed(AstNode::Gt(boxed(AstNode::Number(1)), boxed(AstNode::Number(2)))),
+        boxed(AstNode::Bool(false))
+      ),
+      opt("1 > 2 and false")
+    );
+  }
+
+  fn simp(input: &str) -> AstNode {
+    Parser::new(input).parse().unwrap().simplify()
+  }
+
+  fn parsed(input: &str) -> AstNode {
+    Parser::new(input).parse().unwrap()
+  }
+
+  #[test]
+  fn test_simplify_and() {
+    assert_eq!(parsed("1 > 2"), simp("1 > 2 and true"));
+    assert_eq!(parsed("1 > 2"), simp("true and 1 > 2"));
+    assert_eq!(AstNode::Bool(false), simp("false and 1 > 2"));
+    assert_eq!(parsed("1 > 2 and false"), simp("1 > 2 and false"));
+  }
+
+  #[test]
+  fn test_simplify_or() {
+    assert_eq!(parsed("1 is null"), simp("1 is null or false"));
+    assert_eq!(parsed("1 is null"), simp("false or 1 is null"));
+    assert_eq!(AstNode::Bool(true), simp("true or 1 is null"));
+    assert_eq!(parsed("1 is null or true"), simp("1 is null or true"));
+  }
+
+  #[test]
+  fn test_simplify_xor() {
+    assert_eq!(parsed("1 in (2; 3)"), simp("1 in (2; 3) xor false"));
+    assert_eq!(parsed("1 in (2; 3)"), simp("false xor 1 in (2; 3)"));
+    assert_eq!(parsed("1 in (2; 3) xor true"), simp("1 in (2; 3) xor true"));
+  }
+
+  #[test]
+  fn test_simplify_keeps_non_boolean_operands() {
+    let node = AstNode::And(boxed(AstNode::Number(1)), boxed(AstNode::Bool(true)));
+    assert_eq!(node.clone(), node.simplify());
+  }
+
+  #[test]
+  fn test_simplify_combined() {
+    let input = "if((1 = 2 and true) or (false and 3 = 4) or (false xor 5 < 6); 1; 2)";
+    let node = simp(input);
+    assert_eq!(parsed("if(1 = 2 or 5 < 6; 1; 2)"), node);
+    let original = build_node::<IndexedValues>(&parsed(input), &EvaluatorConfig::default()).unwrap();
+    let simplified = build_node::<IndexedValues>(&node, &EvaluatorConfig::default()).unwrap();
+    for (first, second) in [(Value::Null, Value::Null), (Value::Int(1), Value::Int(1)), (Value::Int(1), Value::Int(2))] {
+      let mut values = IndexedValues::new();
+      values.insert(1, first.clone());
+      values.insert(2, second.clone());
+      values.insert(5, second);
+      values.insert(6, first);
+      assert_eq!(original(&values), simplified(&values));
+    }
+  }
+}
@@ -27,181 +27,688 @@
 //! Grammar:
 //!
 //! ```text
-//!       statement = if_expression
+//!       statement = (condition | sum) EOF
 //!                 ;
 //!
-//!   if_expression = `if` `(` condition `;` expression `;` expression `)`
+//!   if_expression = `if` `(` if_branches `)`
 //!                 ;
 //!
-//!       condition = disjunction { `or` disjunction }
+//!     if_branches = condition `;` expression [`;` `elseif` if_branches]
+//!                 | condition `;` expression `;` [`else`] expression
 //!                 ;
 //!
+//!                 (omitted false-branch defaults to `null`,
+//!                  `if(a;1;elseif b;2;else 3)` is the same as `if(a;1;if(b;2;3))`)
+//!
+//!       condition = disjunction { (`or` | `xor`) disjunction }
+//!                 ;
+//!
+//!                 (`or` and `xor` have the same precedence and are left-associative,
+//!                  precedences of `and`, `or` and `xor` are configurable with [ParserConfig])
+//!
 //!     disjunction = conjunction { `and` conjunction }
 //!                 ;
 //!    
 //!     conjunction = `(` condition `)`
 //!                 | comparison
+//!                 | `true`
+//!                 | `false`
+//!                 ;
+//!
+//!      comparison = sum (`=` | `<>` | `>` | `<` | `>=` | `<=`) sum
+//!                 | sum `between` sum `and` sum
+//!                 | sum `in` arguments
+//!                 | sum `is` [`not`] `null`
+//!                 ;
+//!
+//!             sum = product { (`+` | `-`) product }
 //!                 ;
 //!
-//!      comparison = value (`=` | `<>` | `>` | `<` | `>=` | `<=`) value
+//!         product = operand { (`*` | `/`) operand }
 //!                 ;
 //!
-//!      expression = value
+//!         operand = `(` sum `)`
+//!                 | if_expression
+//!                 | value
+//!                 ;
+//!
+//!      expression = `(` expression `)`
+//!                 | value
 //!                 | if_expression
 //!                 ;
 //!
 //!           value = NUMBER
+//!                 | DECIMAL
+//!                 | STRING
+//!                 | DATE
 //!                 | NULL
+//!                 | IDENTIFIER
+//!                 | negation
+//!                 | function
+//!                 | call
+//!                 ;
+//!
+//!        negation = `-` DECIMAL
+//!                 | `-` (NUMBER | IDENTIFIER | negation)
+//!                 ;
+//!
+//!                 (`-` immediately followed by NUMBER is a negative integer literal, `-2` is the number -2,
+//!                  separated `-` negates the operand, `- 2` is the negated value of index 2)
+//!
+//!        function = (`coalesce` | `min` | `max`) arguments
+//!                 | `abs` `(` sum `)`
+//!                 | `round` `(` sum `;` sum `)`
+//!                 | (`sum` | `avg` | `count`) `(` `)`
+//!                 ;
+//!
+//!                 (the scale of `round` must be a non-negative integer literal,
+//!                  NUMBER is read as the scale itself, not as an index, `round(1;2)`
+//!                  rounds the value of index 1 to 2 decimal places,
+//!                  `sum`, `avg` and `count` aggregate all numbers in the evaluated context)
+//!
+//!       arguments = `(` sum { `;` sum } `)`
 //!                 ;
+//!
+//!                 (at least one argument is required, `min()` is a syntax error)
+//!
+//!            call = IDENTIFIER `(` `)`
+//!                 | IDENTIFIER arguments
+//!                 ;
+//!
+//!                 (calls the user-registered function, see [crate::EvaluatorConfig::with_functions])
+//!
+//!                 (DATE is a date literal in `@YYYY-MM-DD` format, available with `chrono` feature)
 //! ```
 
 use crate::ast::AstNode;
 use crate::errors::{Result, SecelError};
-use crate::lexer::{Lexer, Token};
+use crate::lexer::{err_unexpected_character, Lexer, Token, TokenKind};
+use crate::tokens::{TokenCursor, TokenSource};
+use crate::IndexKey;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Constructor of a node with two operands.
+type BinaryNode = fn(Box<AstNode>, Box<AstNode>) -> AstNode;
+
+/// Configuration of the parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserConfig {
+  /// Precedence of `and` operator, higher precedence binds tighter.
+  pub and_precedence: u8,
+  /// Precedence of `or` operator, higher precedence binds tighter.
+  pub or_precedence: u8,
+  /// Precedence of `xor` operator, higher precedence binds tighter.
+  pub xor_precedence: u8,
+}
+
+impl Default for ParserConfig {
+  /// Creates configuration where `and` binds tighter than `or` and `xor`, like in SQL.
+  fn default() -> Self {
+    Self {
+      and_precedence: 2,
+      or_precedence: 1,
+      xor_precedence: 1,
+    }
+  }
+}
+
+impl ParserConfig {
+  /// Creates configuration where `and`, `or` and `xor` have equal precedence (strict left-to-right).
+  pub fn left_to_right() -> Self {
+    Self {
+      and_precedence: 1,
+      or_precedence: 1,
+      xor_precedence: 1,
+    }
+  }
+  /// Returns the precedence and node constructor of specified logical operator token.
+  fn logical_operator(&self, token: &Token) -> Option<(u8, BinaryNode)> {
+    match token {
+      Token::And => Some((self.and_precedence, AstNode::And)),
+      Token::Or => Some((self.or_precedence, AstNode::Or)),
+      Token::Xor => Some((self.xor_precedence, AstNode::Xor)),
+      _ => None,
+    }
+  }
+}
+
+/// Default maximum nesting depth of parsed constructs.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
 
 pub struct Parser {
-  lexer: Lexer,
+  tokens: Box<dyn TokenSource>,
+  symbols: HashMap<String, IndexKey>,
+  config: ParserConfig,
   trace: bool,
+  /// Current nesting depth of recursively parsed constructs.
+  depth: usize,
+  /// Maximum allowed nesting depth.
+  max_depth: usize,
+  /// Output the trace is written to, standard output when not specified.
+  trace_output: Option<Box<dyn Write>>,
+  /// Errors collected during recovering parse, `None` when parsing fails on the first error.
+  errors: Option<Vec<SecelError>>,
 }
 
 impl Parser {
   ///
   pub fn new(input: &str) -> Self {
+    Self::with_symbols(input, HashMap::new())
+  }
+  /// Creates a parser resolving identifiers to indexes using specified symbol table.
+  pub fn with_symbols(input: &str, symbols: HashMap<String, IndexKey>) -> Self {
+    Self::with_token_source(Box::new(Lexer::new(input)), symbols)
+  }
+  /// Creates a parser reading already tokenized input, bypassing the lexer.
+  pub fn from_tokens(tokens: Vec<Token>) -> Self {
+    Self::with_token_source(Box::new(TokenCursor::new(tokens)), HashMap::new())
+  }
+  /// Creates a parser reading tokens from specified source.
+  fn with_token_source(tokens: Box<dyn TokenSource>, symbols: HashMap<String, IndexKey>) -> Self {
     Self {
-      lexer: Lexer::new(input),
+      tokens,
+      symbols,
+      config: ParserConfig::default(),
       trace: false,
+      depth: 0,
+      max_depth: DEFAULT_MAX_DEPTH,
+      trace_output: None,
+      errors: None,
     }
   }
+  /// Sets the parser configuration.
+  pub fn with_config(mut self, config: ParserConfig) -> Self {
+    self.config = config;
+    self
+  }
+  /// Enables or disables tracing of the parsing process.
+  pub fn with_trace(mut self, trace: bool) -> Self {
+    self.trace = trace;
+    self
+  }
+  /// Sets the maximum allowed nesting depth of parsed constructs (parentheses, `if` expressions, negations).
+  pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+    self.max_depth = max_depth;
+    self
+  }
+  /// Sets the output the trace is written to, when tracing is enabled.
+  pub fn with_trace_output(mut self, output: impl Write + 'static) -> Self {
+    self.trace_output = Some(Box::new(output));
+    self
+  }
   ///
   pub fn parse(&mut self) -> Result<AstNode> {
     self.parse_statement()
   }
+  /// Parses the input recovering from errors at synchronization points (`;` and `)`)
+  /// and returns the parsed tree (when there were no errors) together with all collected errors.
+  pub fn parse_all_errors(&mut self) -> (Option<AstNode>, Vec<SecelError>) {
+    self.errors = Some(vec![]);
+    let result = self.parse_statement();
+    let mut errors = self.errors.take().unwrap_or_default();
+    match result {
+      Ok(node) if errors.is_empty() => (Some(node), errors),
+      Ok(_) => (None, errors),
+      Err(reason) => {
+        errors.push(reason);
+        (None, errors)
+      }
+    }
+  }
   ///
   fn parse_statement(&mut self) -> Result<AstNode> {
     self.trace("statement");
-    self.parse_if_expression()
-    // TODO make sure the input is empty (EOF)
+    let checkpoint = self.checkpoint();
+    let node = match self.parse_condition() {
+      Ok(node) => node,
+      Err(reason) => {
+        self.backtrack(checkpoint);
+        match self.parse_sum() {
+          Ok(node) if matches!(node, AstNode::If(..)) || matches!(self.tokens.peek_token(), Ok(Token::Eof)) => node,
+          _ => return Err(reason),
+        }
+      }
+    };
+    match self.tokens.next_token_spanned()? {
+      (Token::Eof, _) => Ok(node),
+      (_, span) => Err(SecelError::syntax("unexpected trailing input", Some(span.start))),
+    }
   }
   ///
   fn parse_if_expression(&mut self) -> Result<AstNode> {
     self.trace("if-expression");
     self.consume_token(Token::If)?;
     self.consume_token(Token::LeftParen)?;
-    let comparison = self.parse_condition()?;
-    self.consume_token(Token::Semicolon)?;
-    let left_op = self.parse_expression()?;
-    self.consume_token(Token::Semicolon)?;
-    let right_op = self.parse_expression()?;
-    self.consume_token(Token::RightParen)?;
-    Ok(AstNode::If(Box::new(comparison), Box::new(left_op), Box::new(right_op)))
+    let node = self.parse_if_branches()?;
+    self.recover_token(Token::RightParen)?;
+    Ok(node)
+  }
+  /// Parses branches of `if` expression, `elseif` branches are desugared into nested `if` expressions.
+  fn parse_if_branches(&mut self) -> Result<AstNode> {
+    self.trace("if-branches");
+    self.nested(|parser| {
+      let condition = parser.parse_condition();
+      let comparison = parser.recover_node(condition)?;
+      parser.recover_token(Token::Semicolon)?;
+      let expression = parser.parse_expression();
+      let left_op = parser.recover_node(expression)?;
+      let right_op = if parser.tokens.peek_token()? == Token::RightParen {
+        AstNode::Null
+      } else {
+        parser.recover_token(Token::Semicolon)?;
+        if parser.consume_token(Token::Elseif).is_ok() {
+          parser.parse_if_branches()?
+        } else {
+          let _ = parser.consume_token(Token::Else);
+          let expression = parser.parse_expression();
+          parser.recover_node(expression)?
+        }
+      };
+      Ok(AstNode::If(Box::new(comparison), Box::new(left_op), Box::new(right_op)))
+    })
   }
   ///
   fn parse_condition(&mut self) -> Result<AstNode> {
     self.trace("condition");
-    let mut left_node = self.parse_disjunction()?;
-    let position = self.lexer.get_position();
-    let mut consumed_or = false;
-    while self.consume_token(Token::Or).is_ok() {
-      consumed_or = true;
-      let right_node = self.parse_disjunction()?;
-      left_node = AstNode::Or(Box::new(left_node), Box::new(right_node));
-    }
-    if consumed_or {
-      return Ok(left_node);
-    }
-    self.lexer.set_position(position);
-    Ok(left_node)
+    self.nested(|parser| parser.parse_disjunction(0))
   }
-  ///
-  fn parse_disjunction(&mut self) -> Result<AstNode> {
+  /// Parses logical operators with precedence not lower than specified,
+  /// operators of equal precedence are left-associative.
+  fn parse_disjunction(&mut self, min_precedence: u8) -> Result<AstNode> {
     self.trace("disjunction");
     let mut left_node = self.parse_conjunction()?;
-    let position = self.lexer.get_position();
-    let mut consumed_and = false;
-    while self.consume_token(Token::And).is_ok() {
-      consumed_and = true;
-      let right_node = self.parse_conjunction()?;
-      left_node = AstNode::And(Box::new(left_node), Box::new(right_node));
-    }
-    if consumed_and {
-      return Ok(left_node);
+    loop {
+      let (precedence, node) = match self.tokens.peek_token().map(|token| self.config.logical_operator(&token)) {
+        Ok(Some((precedence, node))) if precedence >= min_precedence => (precedence, node),
+        _ => return Ok(left_node),
+      };
+      let (_, span) = self.tokens.next_token_spanned()?;
+      let right_node = match precedence.checked_add(1) {
+        Some(precedence) => self.parse_disjunction(precedence)?,
+        None => return Err(SecelError::syntax("precedence of logical operator is out of range", Some(span.start))),
+      };
+      left_node = node(Box::new(left_node), Box::new(right_node));
     }
-    self.lexer.set_position(position);
-    Ok(left_node)
   }
   ///
   fn parse_conjunction(&mut self) -> Result<AstNode> {
     self.trace("conjunction");
-    let position = self.lexer.get_position();
-    if let result @ Ok(_) = self.parse_comparison() {
-      return result;
+    let checkpoint = self.checkpoint();
+    let position = self.tokens.get_position();
+    let comparison = self.parse_comparison();
+    if comparison.is_ok() {
+      return comparison;
+    }
+    self.backtrack(checkpoint);
+    match self.tokens.next_token_spanned()? {
+      (Token::True, _) => Ok(AstNode::Bool(true)),
+      (Token::False, _) => Ok(AstNode::Bool(false)),
+      (Token::LeftParen, _) => {
+        let node = self.parse_condition()?;
+        self.consume_token(Token::RightParen)?;
+        Ok(node)
+      }
+      (_, span) => {
+        self.tokens.set_position(position);
+        comparison.map_err(|reason| reason.expecting(&[TokenKind::True, TokenKind::False], span.start))
+      }
     }
-    self.lexer.set_position(position);
-    self.consume_token(Token::LeftParen)?;
-    let node = self.parse_condition()?;
-    self.consume_token(Token::RightParen)?;
-    Ok(node)
   }
   ///
   fn parse_comparison(&mut self) -> Result<AstNode> {
     self.trace("comparison");
-    let left_op = self.parse_value()?;
-    let comparison_token = self.lexer.next_token();
-    let right_op = self.parse_value()?;
-    match comparison_token {
-      Token::Eq => Ok(AstNode::Eq(Box::new(left_op), Box::new(right_op))),
-      Token::Nq => Ok(AstNode::Nq(Box::new(left_op), Box::new(right_op))),
-      Token::Ge => Ok(AstNode::Ge(Box::new(left_op), Box::new(right_op))),
-      Token::Gt => Ok(AstNode::Gt(Box::new(left_op), Box::new(right_op))),
-      Token::Le => Ok(AstNode::Le(Box::new(left_op), Box::new(right_op))),
-      Token::Lt => Ok(AstNode::Lt(Box::new(left_op), Box::new(right_op))),
-      other => Err(SecelError::new(&format!("expected comparison token, but encountered {:?}", other))),
+    let node = self.read_comparison()?;
+    let position = self.tokens.get_position();
+    match self.tokens.next_token_spanned() {
+      Ok((Token::Eq | Token::Nq | Token::Ge | Token::Gt | Token::Le | Token::Lt | Token::Between | Token::In | Token::Is, span)) => {
+        Err(SecelError::syntax("chained comparisons are not allowed; use 'and'", Some(span.start)))
+      }
+      _ => {
+        self.tokens.set_position(position);
+        Ok(node)
+      }
     }
   }
+  /// Reads a single comparison, without checking what follows it.
+  fn read_comparison(&mut self) -> Result<AstNode> {
+    let left_op = self.parse_sum()?;
+    let (token, span) = self.tokens.next_token_spanned()?;
+    let node: BinaryNode = match token {
+      Token::Between => {
+        let lower = self.parse_sum()?;
+        self.consume_token(Token::And)?;
+        let upper = self.parse_sum()?;
+        return Ok(AstNode::Between(Box::new(left_op), Box::new(lower), Box::new(upper)));
+      }
+      Token::Is => {
+        let negated = self.consume_token(Token::Not).is_ok();
+        self.consume_token(Token::Null)?;
+        return Ok(if negated {
+          AstNode::IsNotNull(Box::new(left_op))
+        } else {
+          AstNode::IsNull(Box::new(left_op))
+        });
+      }
+      Token::In => return Ok(AstNode::In(Box::new(left_op), self.parse_arguments()?)),
+      Token::Eq => AstNode::Eq,
+      Token::Nq => AstNode::Nq,
+      Token::Ge => AstNode::Ge,
+      Token::Gt => AstNode::Gt,
+      Token::Le => AstNode::Le,
+      Token::Lt => AstNode::Lt,
+      other => {
+        let expected = vec![
+          TokenKind::Eq,
+          TokenKind::Nq,
+          TokenKind::Gt,
+          TokenKind::Ge,
+          TokenKind::Lt,
+          TokenKind::Le,
+          TokenKind::Between,
+          TokenKind::In,
+          TokenKind::Is,
+        ];
+        return Err(SecelError::parse(expected, other, span.start));
+      }
+    };
+    let right_op = self.parse_sum()?;
+    Ok(node(Box::new(left_op), Box::new(right_op)))
+  }
   ///
   fn parse_expression(&mut self) -> Result<AstNode> {
     self.trace("expression");
-    let position = self.lexer.get_position();
-    if let result @ Ok(_) = self.parse_value() {
-      return result;
+    self.nested(|parser| match parser.tokens.peek_token()? {
+      Token::If => parser.parse_if_expression(),
+      Token::LeftParen => {
+        parser.tokens.next_token()?;
+        let node = parser.parse_expression()?;
+        parser.consume_token(Token::RightParen)?;
+        Ok(node)
+      }
+      _ => parser.parse_value(),
+    })
+  }
+  ///
+  fn parse_sum(&mut self) -> Result<AstNode> {
+    self.trace("sum");
+    self.nested(|parser| {
+      let mut left_node = parser.parse_product()?;
+      loop {
+        left_node = match parser.tokens.peek_token()? {
+          Token::Plus => {
+            parser.tokens.next_token()?;
+            AstNode::Add(Box::new(left_node), Box::new(parser.parse_product()?))
+          }
+          Token::Minus => {
+            parser.tokens.next_token()?;
+            AstNode::Sub(Box::new(left_node), Box::new(parser.parse_product()?))
+          }
+          _ => return Ok(left_node),
+        }
+      }
+    })
+  }
+  ///
+  fn parse_product(&mut self) -> Result<AstNode> {
+    self.trace("product");
+    let mut left_node = self.parse_operand()?;
+    loop {
+      left_node = match self.tokens.peek_token()? {
+        Token::Asterisk => {
+          self.tokens.next_token()?;
+          AstNode::Mul(Box::new(left_node), Box::new(self.parse_operand()?))
+        }
+        Token::Slash => {
+          self.tokens.next_token()?;
+          AstNode::Div(Box::new(left_node), Box::new(self.parse_operand()?))
+        }
+        _ => return Ok(left_node),
+      }
     }
-    self.lexer.set_position(position);
-    if let result @ Ok(_) = self.parse_if_expression() {
-      return result;
+  }
+  ///
+  fn parse_operand(&mut self) -> Result<AstNode> {
+    self.trace("operand");
+    match self.tokens.peek_token()? {
+      Token::LeftParen => {
+        self.tokens.next_token()?;
+        let node = self.parse_sum()?;
+        self.consume_token(Token::RightParen)?;
+        Ok(node)
+      }
+      Token::If => self.parse_if_expression(),
+      _ => self.parse_value(),
     }
-    self.lexer.set_position(position);
-    Err(SecelError::new("expected 'value' or 'if expression`"))
   }
   ///
   fn parse_value(&mut self) -> Result<AstNode> {
     self.trace("value");
-    let position = self.lexer.get_position();
-    match self.lexer.next_token() {
+    let position = self.tokens.get_position();
+    let value = self.read_value();
+    if value.is_err() {
+      self.tokens.set_position(position);
+    }
+    value
+  }
+  /// Reads the tokens of a single value, position is restored by the caller on failure.
+  fn read_value(&mut self) -> Result<AstNode> {
+    let (token, span) = self.tokens.next_token_spanned()?;
+    match token {
       Token::Null => Ok(AstNode::Null),
       Token::Number(n) => Ok(AstNode::Number(n)),
-      other => {
-        self.lexer.set_position(position);
-        Err(SecelError::new(&format!("expected null or number but encountered {:?}", other)))
+      Token::Decimal(d) => Ok(AstNode::Literal(d)),
+      Token::Minus => {
+        let position = self.tokens.get_position();
+        match self.tokens.next_token_spanned()? {
+          (Token::Decimal(d), _) => Ok(AstNode::Literal(-d)),
+          // `-` immediately followed by digits is a negative literal, `- 2` negates the value at index 2
+          (Token::Number(n), next) if next.start == span.end => Ok(AstNode::Literal(-Decimal::from(n))),
+          (Token::Number(_) | Token::Identifier(_) | Token::Minus, _) => {
+            self.tokens.set_position(position);
+            Ok(AstNode::Neg(Box::new(self.nested(Self::read_value)?)))
+          }
+          (Token::Undef(ch, position), _) => Err(err_unexpected_character(ch, position)),
+          (other, span) => {
+            let expected = vec![TokenKind::Decimal, TokenKind::Number, TokenKind::Identifier, TokenKind::Minus];
+            Err(SecelError::parse(expected, other, span.start))
+          }
+        }
       }
+      Token::Str(s) => Ok(AstNode::StrLiteral(s)),
+      #[cfg(feature = "chrono")]
+      Token::Date(d) => Ok(AstNode::DateLiteral(d)),
+      Token::Abs => {
+        self.consume_token(Token::LeftParen)?;
+        let node = self.parse_sum()?;
+        self.consume_token(Token::RightParen)?;
+        Ok(AstNode::Abs(Box::new(node)))
+      }
+      Token::Coalesce => Ok(AstNode::Coalesce(self.parse_arguments()?)),
+      Token::Round => {
+        self.consume_token(Token::LeftParen)?;
+        let node = self.parse_sum()?;
+        self.consume_token(Token::Semicolon)?;
+        let scale = match self.tokens.peek_token()? {
+          Token::Number(scale) => {
+            self.tokens.next_token()?;
+            AstNode::Literal(Decimal::from(scale))
+          }
+          _ => self.parse_sum()?,
+        };
+        self.consume_token(Token::RightParen)?;
+        Ok(AstNode::Round(Box::new(node), Box::new(scale)))
+      }
+      Token::Avg => self.parse_aggregate(AstNode::Avg),
+      Token::Count => self.parse_aggregate(AstNode::Count),
+      Token::Sum => self.parse_aggregate(AstNode::Sum),
+      Token::Max => Ok(AstNode::Max(self.parse_arguments()?)),
+      Token::Min => Ok(AstNode::Min(self.parse_arguments()?)),
+      Token::Identifier(name) if self.tokens.peek_token()? == Token::LeftParen => Ok(AstNode::Call(name, self.parse_call_arguments()?)),
+      Token::Identifier(name) => match self.symbols.get(&name) {
+        Some(key) => Ok(AstNode::Number(*key)),
+        None => Err(SecelError::syntax(&format!("unknown identifier '{}'", name), Some(span.start))),
+      },
+      Token::Undef(ch, position) => Err(err_unexpected_character(ch, position)),
+      other => Err(SecelError::parse(operand_tokens(), other, span.start)),
+    }
+  }
+  /// Parses parenthesized list of arguments separated with semicolons.
+  fn parse_arguments(&mut self) -> Result<Vec<AstNode>> {
+    self.trace("arguments");
+    self.consume_token(Token::LeftParen)?;
+    let mut arguments = vec![self.parse_sum()?];
+    while self.consume_token(Token::Semicolon).is_ok() {
+      arguments.push(self.parse_sum()?);
     }
+    self.consume_token(Token::RightParen)?;
+    Ok(arguments)
+  }
+  /// Parses empty parentheses following the name of the aggregate function.
+  fn parse_aggregate(&mut self, node: AstNode) -> Result<AstNode> {
+    self.consume_token(Token::LeftParen)?;
+    self.consume_token(Token::RightParen)?;
+    Ok(node)
+  }
+  /// Parses parenthesized, possibly empty, list of arguments of the called function.
+  fn parse_call_arguments(&mut self) -> Result<Vec<AstNode>> {
+    let position = self.tokens.get_position();
+    self.consume_token(Token::LeftParen)?;
+    if self.consume_token(Token::RightParen).is_ok() {
+      return Ok(vec![]);
+    }
+    self.tokens.set_position(position);
+    self.parse_arguments()
   }
   ///
   fn consume_token(&mut self, expected: Token) -> Result<()> {
-    let position = self.lexer.get_position();
-    let token = self.lexer.next_token();
-    if token == expected {
-      Ok(())
-    } else {
-      self.lexer.set_position(position);
-      Err(SecelError::new(&format!("expected token '{:?}', actual token: '{:?}'", expected, token)))
+    let position = self.tokens.get_position();
+    match self.tokens.next_token_spanned() {
+      Ok((token, _)) if token == expected => Ok(()),
+      Ok((Token::Undef(ch, undef_position), _)) => {
+        self.tokens.set_position(position);
+        Err(err_unexpected_character(ch, undef_position))
+      }
+      Ok((token, span)) => {
+        self.tokens.set_position(position);
+        Err(SecelError::parse(vec![expected.kind()], token, span.start))
+      }
+      Err(reason) => {
+        self.tokens.set_position(position);
+        Err(reason)
+      }
+    }
+  }
+  /// Invokes specified parsing function one nesting level deeper,
+  /// fails when the maximum nesting depth is exceeded.
+  fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+    if self.depth >= self.max_depth {
+      return Err(SecelError::syntax("maximum nesting depth exceeded", None));
+    }
+    self.depth += 1;
+    let result = parse(self);
+    self.depth -= 1;
+    result
+  }
+  /// Returns the current position in input and the number of collected errors.
+  fn checkpoint(&self) -> (usize, usize) {
+    (self.tokens.get_position(), self.errors.as_ref().map_or(0, Vec::len))
+  }
+  /// Restores the position in input and drops errors collected since the checkpoint.
+  fn backtrack(&mut self, (position, errors_len): (usize, usize)) {
+    self.tokens.set_position(position);
+    if let Some(errors) = &mut self.errors {
+      errors.truncate(errors_len);
+    }
+  }
+  /// Returns the parsed node, or when collecting errors, records the error,
+  /// skips to the nearest synchronization point and returns `null` node in place of the erroneous one.
+  fn recover_node(&mut self, result: Result<AstNode>) -> Result<AstNode> {
+    match (result, &mut self.errors) {
+      (Err(reason), Some(errors)) => {
+        errors.push(reason);
+        self.skip_to_synchronization_point();
+        Ok(AstNode::Null)
+      }
+      (result, _) => result,
+    }
+  }
+  /// Consumes the expected token, or when collecting errors, records the error,
+  /// skips to the nearest synchronization point and consumes the expected token when found there.
+  fn recover_token(&mut self, expected: Token) -> Result<()> {
+    match (self.consume_token(expected.clone()), &mut self.errors) {
+      (Err(reason), Some(errors)) => {
+        errors.push(reason);
+        self.skip_to_synchronization_point();
+        let _ = self.consume_token(expected);
+        Ok(())
+      }
+      (result, _) => result,
+    }
+  }
+  /// Skips tokens up to (but not including) the nearest `;` or `)` not nested in parentheses,
+  /// unrecognized characters (which the lexer does not step past) are skipped explicitly.
+  fn skip_to_synchronization_point(&mut self) {
+    let mut depth = 0_usize;
+    loop {
+      let position = self.tokens.get_position();
+      match self.tokens.next_token_spanned() {
+        Ok((Token::Eof, _)) => return,
+        Ok((Token::Semicolon | Token::RightParen, _)) if depth == 0 => {
+          self.tokens.set_position(position);
+          return;
+        }
+        Ok((Token::RightParen, _)) => depth -= 1,
+        Ok((Token::LeftParen, _)) => depth += 1,
+        Ok((Token::Undef(..), span)) => {
+          self.tokens.set_position(span.start + 1);
+          if self.tokens.get_position() == position {
+            return;
+          }
+        }
+        Ok(_) => {}
+        Err(_) if self.tokens.get_position() == position => return,
+        Err(_) => {}
+      }
     }
   }
   ///
-  fn trace(&self, name: &str) {
+  fn trace(&mut self, name: &str) {
     if self.trace {
-      print!("{:14}", name);
-      self.lexer.trace();
-      println!();
+      let mut stdout = std::io::stdout();
+      let output: &mut dyn Write = match &mut self.trace_output {
+        Some(output) => output.as_mut(),
+        None => &mut stdout,
+      };
+      let _ = write!(output, "{:14}", name);
+      let _ = self.tokens.trace_to(output);
+      let _ = writeln!(output);
     }
   }
 }
+
+/// Returns tokens starting an operand (a value, parenthesized sum or `if` expression),
+/// reported as expected when none of them was found.
+fn operand_tokens() -> Vec<TokenKind> {
+  vec![
+    TokenKind::Null,
+    TokenKind::Number,
+    TokenKind::Decimal,
+    TokenKind::Str,
+    TokenKind::Minus,
+    TokenKind::Abs,
+    TokenKind::Avg,
+    TokenKind::Coalesce,
+    TokenKind::Count,
+    TokenKind::Max,
+    TokenKind::Min,
+    TokenKind::Round,
+    TokenKind::Sum,
+    TokenKind::Identifier,
+    TokenKind::LeftParen,
+    TokenKind::If,
+    #[cfg(feature = "chrono")]
+    TokenKind::Date,
+  ]
+}
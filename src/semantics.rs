@@ -0,0 +1,236 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Semantics of operators and functions shared by all evaluators.
+//!
+//! Evaluators differ only in how they walk the expression (closures, tree-walking, bytecode),
+//! values of operands are always combined by functions defined in this module
+//! and by the arithmetic operators implemented for [Value].
+
+use crate::evaluator::EvaluatorConfig;
+use crate::values::Value;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+
+/// Compares two values following the semantics of `=` operator.
+pub(crate) fn eq_values(lhv: &Value, rhv: &Value) -> Value {
+  match (lhv, rhv) {
+    (Value::Number(lhv), Value::Number(rhv)) => Value::Bool(lhv == rhv),
+    (Value::Int(lhv), Value::Int(rhv)) => Value::Bool(lhv == rhv),
+    (Value::Int(_) | Value::Number(_), Value::Int(_) | Value::Number(_)) => Value::Bool(lhv.as_decimal() == rhv.as_decimal()),
+    (Value::Str(lhv), Value::Str(rhv)) => Value::Bool(lhv == rhv),
+    (Value::Bool(lhv), Value::Bool(rhv)) => Value::Bool(lhv == rhv),
+    (Value::Int(_) | Value::Number(_) | Value::Str(_), Value::Null) => Value::Bool(false),
+    (Value::Null, Value::Int(_) | Value::Number(_) | Value::Str(_)) => Value::Bool(false),
+    (Value::Null, Value::Null) => Value::Bool(true),
+    #[cfg(feature = "chrono")]
+    (Value::Date(lhv), Value::Date(rhv)) => Value::Bool(lhv == rhv),
+    #[cfg(feature = "chrono")]
+    (Value::Date(_), Value::Null) | (Value::Null, Value::Date(_)) => Value::Bool(false),
+    _ => Value::Null,
+  }
+}
+
+/// Compares two values following the semantics of ordering operators, numbers are compared
+/// numerically, strings lexicographically and dates chronologically, `None` for other combinations of types.
+pub(crate) fn compare_values(lhv: &Value, rhv: &Value) -> Option<Ordering> {
+  match (lhv, rhv) {
+    (Value::Number(lhv), Value::Number(rhv)) => Some(lhv.cmp(rhv)),
+    (Value::Int(lhv), Value::Int(rhv)) => Some(lhv.cmp(rhv)),
+    (Value::Int(_) | Value::Number(_), Value::Int(_) | Value::Number(_)) => Some(lhv.as_decimal()?.cmp(&rhv.as_decimal()?)),
+    (Value::Str(lhv), Value::Str(rhv)) => Some(lhv.cmp(rhv)),
+    #[cfg(feature = "chrono")]
+    (Value::Date(lhv), Value::Date(rhv)) => Some(lhv.cmp(rhv)),
+    _ => None,
+  }
+}
+
+/// Semantics of `=` operator taken from the evaluator configuration.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Equality {
+  /// Comparing `Null` with `Null` evaluates to `true` when set, otherwise to `Null`.
+  null_equals_null: bool,
+  /// Numbers differing by no more than the tolerance are equal.
+  tolerance: Option<Decimal>,
+}
+
+impl Equality {
+  /// Creates the semantics of `=` operator from the evaluator configuration.
+  pub(crate) fn from(config: &EvaluatorConfig) -> Self {
+    Self {
+      null_equals_null: config.null_equals_null,
+      tolerance: config.eq_tolerance,
+    }
+  }
+
+  /// Compares two values following the semantics of `=` operator.
+  pub(crate) fn eq(&self, lhv: &Value, rhv: &Value) -> Value {
+    match (lhv, rhv, self.tolerance) {
+      (Value::Null, Value::Null, _) if !self.null_equals_null => Value::Null,
+      (Value::Int(_) | Value::Number(_), Value::Int(_) | Value::Number(_), Some(tolerance)) => match (lhv.as_decimal(), rhv.as_decimal()) {
+        (Some(lhv), Some(rhv)) => Value::Bool(lhv.checked_sub(rhv).is_some_and(|difference| difference.abs() <= tolerance)),
+        _ => Value::Null,
+      },
+      _ => eq_values(lhv, rhv),
+    }
+  }
+
+  /// Compares two values following the semantics of `<>` operator, the negated result of `=` operator.
+  pub(crate) fn nq(&self, lhv: &Value, rhv: &Value) -> Value {
+    match self.eq(lhv, rhv) {
+      Value::Bool(result) => Value::Bool(!result),
+      other => other,
+    }
+  }
+
+  /// Evaluates `in` operator, evaluates to `true` when any list element is equal to the tested value,
+  /// to `false` when none is equal and no `Null` was involved in comparisons, otherwise to `Null`.
+  /// List elements are consumed until the first equal one.
+  pub(crate) fn contains(&self, lhv: &Value, rhvs: impl IntoIterator<Item = Value>) -> Value {
+    let mut null_involved = *lhv == Value::Null;
+    for rhv in rhvs {
+      match self.eq(lhv, &rhv) {
+        Value::Bool(true) => return Value::Bool(true),
+        Value::Bool(false) if rhv != Value::Null => {}
+        _ => null_involved = true,
+      }
+    }
+    if null_involved {
+      Value::Null
+    } else {
+      Value::Bool(false)
+    }
+  }
+}
+
+/// Semantics of `=` operator with default configuration.
+impl Default for Equality {
+  fn default() -> Self {
+    Self::from(&EvaluatorConfig::default())
+  }
+}
+
+/// Evaluates ordering operator, evaluates to `Null` when operands can not be compared, see [compare_values].
+pub(crate) fn ordering(lhv: &Value, rhv: &Value, compare: fn(Ordering) -> bool) -> Value {
+  compare_values(lhv, rhv).map_or(Value::Null, |ordering| Value::Bool(compare(ordering)))
+}
+
+/// Returns the value when it is a `Bool`, otherwise `Null`.
+pub(crate) fn to_bool(value: Value) -> Value {
+  match value {
+    Value::Bool(value) => Value::Bool(value),
+    _ => Value::Null,
+  }
+}
+
+/// Evaluates `and` operator over evaluated operands using three-valued (Kleene) logic,
+/// non-boolean operands are treated as unknown (`Null`).
+pub(crate) fn and(lhv: Value, rhv: Value) -> Value {
+  match (lhv, rhv) {
+    (Value::Bool(false), _) | (_, Value::Bool(false)) => Value::Bool(false),
+    (Value::Bool(true), Value::Bool(true)) => Value::Bool(true),
+    _ => Value::Null,
+  }
+}
+
+/// Evaluates `or` operator over evaluated operands using three-valued (Kleene) logic,
+/// non-boolean operands are treated as unknown (`Null`).
+pub(crate) fn or(lhv: Value, rhv: Value) -> Value {
+  match (lhv, rhv) {
+    (Value::Bool(true), _) | (_, Value::Bool(true)) => Value::Bool(true),
+    (Value::Bool(false), Value::Bool(false)) => Value::Bool(false),
+    _ => Value::Null,
+  }
+}
+
+/// Evaluates `xor` operator, evaluates to `Null` when any operand is not a `Bool`.
+pub(crate) fn xor(lhv: Value, rhv: Value) -> Value {
+  match (lhv, rhv) {
+    (Value::Bool(lhv), Value::Bool(rhv)) => Value::Bool(lhv ^ rhv),
+    _ => Value::Null,
+  }
+}
+
+/// Evaluates `abs` function, evaluates to `Null` when the operand is not a number.
+pub(crate) fn abs(value: Value) -> Value {
+  value.as_decimal().map_or(Value::Null, |value| Value::Number(value.abs()))
+}
+
+/// Evaluates unary `-` operator, evaluates to `Null` when the operand is not a number.
+pub(crate) fn neg(value: Value) -> Value {
+  value.as_decimal().map_or(Value::Null, |value| Value::Number(-value))
+}
+
+/// Evaluates `round` function, midpoint values are rounded to the nearest even number (banker's rounding),
+/// evaluates to `Null` when the operand is not a number.
+pub(crate) fn round(value: Value, scale: u32) -> Value {
+  value.as_decimal().map_or(Value::Null, |value| Value::Number(value.round_dp(scale)))
+}
+
+/// Evaluates `between` operator, both bounds are inclusive,
+/// evaluates to `Null` when any operand is not a number.
+pub(crate) fn between(lhv: Value, mhv: Value, rhv: Value) -> Value {
+  match (lhv.as_decimal(), mhv.as_decimal(), rhv.as_decimal()) {
+    (Some(lhv), Some(mhv), Some(rhv)) => Value::Bool(mhv <= lhv && lhv <= rhv),
+    _ => Value::Null,
+  }
+}
+
+/// Evaluates `min` and `max` functions, evaluates to `Null` when any operand is not a number,
+/// `Null` operands are skipped when requested. Operands are consumed until the first one that is not a number.
+pub(crate) fn extremum(mhvs: impl IntoIterator<Item = Value>, select: fn(Decimal, Decimal) -> Decimal, skip_nulls: bool) -> Value {
+  let mut result = None;
+  for mhv in mhvs {
+    match (mhv.as_decimal(), mhv) {
+      (Some(mhv), _) => result = Some(result.map_or(mhv, |value| select(value, mhv))),
+      (None, Value::Null) if skip_nulls => {}
+      _ => return Value::Null,
+    }
+  }
+  result.map_or(Value::Null, Value::Number)
+}
+
+/// Computes the sum of all numbers in specified values, `0` when there are none, `Null` on overflow.
+pub(crate) fn sum_numbers(values: Box<dyn Iterator<Item = &Value> + '_>) -> Value {
+  values
+    .filter_map(Value::as_decimal)
+    .try_fold(Decimal::ZERO, |sum, value| sum.checked_add(value))
+    .map_or(Value::Null, Value::Number)
+}
+
+/// Computes the average of all numbers in specified values, `Null` when there are none or on overflow.
+pub(crate) fn avg_numbers(values: Box<dyn Iterator<Item = &Value> + '_>) -> Value {
+  match values
+    .filter_map(Value::as_decimal)
+    .try_fold((Decimal::ZERO, 0_usize), |(sum, count), value| Some((sum.checked_add(value)?, count + 1)))
+  {
+    Some((sum, count)) if count > 0 => Value::Number(sum / Decimal::from(count)),
+    _ => Value::Null,
+  }
+}
+
+/// Computes the count of all numbers in specified values.
+pub(crate) fn count_numbers(values: Box<dyn Iterator<Item = &Value> + '_>) -> Value {
+  Value::Number(Decimal::from(values.filter_map(Value::as_decimal).count()))
+}
@@ -0,0 +1,191 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Slot evaluator implementation.
+//!
+//! Slot evaluator resolves all indexes referenced in the expression into positional slots
+//! once per evaluation, so every reference to an index is evaluated as an array access
+//! instead of a lookup in the [Context]. Aggregate functions are evaluated over all values
+//! in the context once per evaluation, into slots following the slots of indexes.
+
+use crate::ast::AstNode;
+use crate::errors::Result;
+use crate::evaluator::{build_context_evaluator, check_depth, Context, ContextEvaluator, EvaluatorConfig, IndexKey};
+use crate::semantics::{avg_numbers, count_numbers, sum_numbers};
+use crate::values::Value;
+
+/// Aggregate function evaluated over all values in the context.
+type Aggregate = fn(Box<dyn Iterator<Item = &Value> + '_>) -> Value;
+
+/// Evaluator of the expression with indexes resolved into positional slots.
+pub struct SlotEvaluator {
+  /// Indexes referenced in the expression, the position of the index is its slot.
+  keys: Vec<IndexKey>,
+  /// Aggregate functions referenced in the expression, slots of aggregates follow the slots of indexes.
+  aggregates: Vec<Aggregate>,
+  /// Value used in place of values missing in the context.
+  missing: Value,
+  /// Evaluator of the expression with indexes replaced by slots.
+  evaluator: ContextEvaluator<[Value]>,
+}
+
+impl SlotEvaluator {
+  /// Evaluates the expression, each referenced index is looked up in the context exactly once
+  /// and each referenced aggregate function is evaluated exactly once.
+  pub fn evaluate<C: Context + ?Sized>(&self, context: &C) -> Value {
+    let slots = self
+      .keys
+      .iter()
+      .map(|key| context.get(*key).unwrap_or_else(|| self.missing.clone()))
+      .chain(self.aggregates.iter().map(|aggregate| aggregate(context.values())))
+      .collect::<Vec<Value>>();
+    (self.evaluator)(&slots)
+  }
+
+  /// Returns indexes referenced in the expression, in order of the first reference.
+  pub fn keys(&self) -> &[IndexKey] {
+    &self.keys
+  }
+}
+
+/// Builds a slot evaluator for given [AstNode] using specified configuration.
+pub fn build_slot_evaluator(node: &AstNode, config: &EvaluatorConfig) -> Result<SlotEvaluator> {
+  check_depth(node, config.max_depth)?;
+  let mut node = node.clone();
+  let mut keys = vec![];
+  assign_slots(&mut node, &mut keys);
+  let mut aggregates = vec![];
+  assign_aggregates(&mut node, keys.len(), &mut aggregates);
+  Ok(SlotEvaluator {
+    keys,
+    aggregates: aggregates.into_iter().map(|(_, aggregate)| aggregate).collect(),
+    missing: config.missing.clone(),
+    evaluator: build_context_evaluator(&node, config)?,
+  })
+}
+
+/// Replaces indexes in `Number` nodes with slots, collecting the replaced indexes.
+fn assign_slots(node: &mut AstNode, keys: &mut Vec<IndexKey>) {
+  if let AstNode::Number(key) = node {
+    let slot = keys.iter().position(|k| k == key).unwrap_or_else(|| {
+      keys.push(*key);
+      keys.len() - 1
+    });
+    *key = slot as IndexKey;
+  }
+  for child in node.children_mut() {
+    assign_slots(child, keys);
+  }
+}
+
+/// Replaces aggregate functions with slots starting from specified slot, collecting the replaced functions with their kinds.
+fn assign_aggregates(node: &mut AstNode, first: usize, aggregates: &mut Vec<(&'static str, Aggregate)>) {
+  let aggregate: Aggregate = match node {
+    AstNode::Avg => avg_numbers,
+    AstNode::Count => count_numbers,
+    AstNode::Sum => sum_numbers,
+    _ => {
+      for child in node.children_mut() {
+        assign_aggregates(child, first, aggregates);
+      }
+      return;
+    }
+  };
+  let kind = node.kind();
+  let slot = aggregates.iter().position(|(k, _)| *k == kind).unwrap_or_else(|| {
+    aggregates.push((kind, aggregate));
+    aggregates.len() - 1
+  });
+  *node = AstNode::Number((first + slot) as IndexKey);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+  use crate::IndexedValues;
+  use rust_decimal::Decimal;
+  use std::cell::Cell;
+
+  /// Context counting the lookups.
+  struct CountingContext {
+    values: IndexedValues,
+    lookups: Cell<usize>,
+  }
+
+  impl Context for CountingContext {
+    fn get(&self, key: IndexKey) -> Option<Value> {
+      self.lookups.set(self.lookups.get() + 1);
+      self.values.get(&key).cloned()
+    }
+    fn values(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+      Context::values(&self.values)
+    }
+  }
+
+  #[test]
+  fn test_single_lookup_per_index() {
+    let node = Parser::new("1 + 1 + 1 + 1 + 1 > 2 and 1 < 3 and 1 <> 4 and 1 >= 2 and 1 <= 3 and 1 between 2 and 3")
+      .parse()
+      .unwrap();
+    let evaluator = build_slot_evaluator(&node, &EvaluatorConfig::default()).unwrap();
+    assert_eq!(&[1, 2, 3, 4], evaluator.keys());
+    let mut values = IndexedValues::new();
+    values.insert(1, Value::Number(Decimal::new(5, 0)));
+    values.insert(2, Value::Number(Decimal::new(1, 0)));
+    values.insert(3, Value::Number(Decimal::new(9, 0)));
+    let context = CountingContext { values, lookups: Cell::new(0) };
+    assert_eq!(Value::Bool(true), evaluator.evaluate(&context));
+    assert_eq!(4, context.lookups.get());
+    assert_eq!(Value::Bool(true), evaluator.evaluate(&context));
+    assert_eq!(8, context.lookups.get());
+  }
+
+  #[test]
+  fn test_missing() {
+    let node = Parser::new("coalesce(7; 2)").parse().unwrap();
+    let config = EvaluatorConfig {
+      missing: Value::Number(Decimal::ZERO),
+      ..EvaluatorConfig::default()
+    };
+    let evaluator = build_slot_evaluator(&node, &config).unwrap();
+    assert_eq!(Value::Number(Decimal::ZERO), evaluator.evaluate(&IndexedValues::new()));
+  }
+
+  #[test]
+  fn test_aggregates() {
+    let node = Parser::new("sum() + count() - avg() + 2 + sum()").parse().unwrap();
+    let evaluator = build_slot_evaluator(&node, &EvaluatorConfig::default()).unwrap();
+    assert_eq!(&[2], evaluator.keys());
+    let mut values = IndexedValues::new();
+    values.insert(1, Value::Number(Decimal::new(1, 0)));
+    values.insert(2, Value::Number(Decimal::new(5, 0)));
+    values.insert(3, Value::Number(Decimal::new(3, 0)));
+    let context = CountingContext { values, lookups: Cell::new(0) };
+    assert_eq!(Value::Number(Decimal::new(23, 0)), evaluator.evaluate(&context));
+    assert_eq!(1, context.lookups.get());
+    assert_eq!(crate::evaluator::build_evaluator(&node).unwrap()(&context.values), evaluator.evaluate(&context));
+    assert_eq!(Value::Null, evaluator.evaluate(&IndexedValues::new()));
+  }
+}
@@ -0,0 +1,489 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Strict evaluator implementation.
+//!
+//! Strict evaluator reports type mismatches, missing indexes and failed arithmetic operations
+//! as errors, where the (lenient) evaluator silently evaluates to `Null`.
+
+use crate::ast::AstNode;
+use crate::errors::{Result, SecelError};
+use crate::evaluator::{check_depth, round_scale, Context, Functions, IndexKey, IndexedValues, DEFAULT_MAX_EVALUATOR_DEPTH};
+use crate::semantics::{avg_numbers, compare_values, count_numbers, eq_values, sum_numbers};
+use crate::values::Value;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+
+/// Type alias for the strict evaluator of the expression.
+pub type StrictEvaluator = Box<dyn Fn(&IndexedValues) -> Result<Value> + Send + Sync>;
+
+/// Evaluates the strict evaluator, distinguishing the `Null` result (`Some(Value::Null)`)
+/// from the failed evaluation, like type mismatch or missing index (`None`).
+pub fn evaluate_opt(evaluator: &StrictEvaluator, values: &IndexedValues) -> Option<Value> {
+  evaluator(values).ok()
+}
+
+/// Builds a strict expression evaluator for given [AstNode],
+/// trees deeper than [DEFAULT_MAX_EVALUATOR_DEPTH] are reported as errors.
+pub fn build_evaluator_strict(node: &AstNode) -> Result<StrictEvaluator> {
+  build_evaluator_strict_with_functions(node, &Functions::default())
+}
+
+/// Builds a strict expression evaluator for given [AstNode] calling specified user-registered functions,
+/// calls of unknown functions are reported as errors.
+pub fn build_evaluator_strict_with_functions(node: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  check_depth(node, DEFAULT_MAX_EVALUATOR_DEPTH)?;
+  build_node(node, functions)
+}
+
+/// Builds a strict evaluator for given [AstNode], dispatching on node type.
+fn build_node(node: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  match node {
+    AstNode::Abs(mhs) => build_abs(mhs, functions),
+    AstNode::Add(lhs, rhs) => build_arithmetic(lhs, rhs, "+", Decimal::checked_add, functions),
+    AstNode::And(lhs, rhs) => build_and(lhs, rhs, functions),
+    AstNode::Avg => build_aggregate(avg_numbers),
+    AstNode::Between(lhs, mhs, rhs) => build_between(lhs, mhs, rhs, functions),
+    AstNode::Bool(mhs) => build_value(Value::Bool(*mhs)),
+    AstNode::Call(name, mhs) => build_call(name, mhs, functions),
+    AstNode::Coalesce(mhs) => build_coalesce(mhs, functions),
+    AstNode::Count => build_aggregate(count_numbers),
+    AstNode::Div(lhs, rhs) => build_arithmetic(lhs, rhs, "/", Decimal::checked_div, functions),
+    AstNode::Eq(lhs, rhs) => build_equality(lhs, rhs, "=", false, functions),
+    AstNode::Ge(lhs, rhs) => build_ordering(lhs, rhs, ">=", Ordering::is_ge, functions),
+    AstNode::Gt(lhs, rhs) => build_ordering(lhs, rhs, ">", Ordering::is_gt, functions),
+    AstNode::If(mhs, lhs, rhs) => build_if(mhs, lhs, rhs, functions),
+    AstNode::In(lhs, rhs) => build_in(lhs, rhs, functions),
+    AstNode::IsNotNull(mhs) => build_is_null(mhs, true, functions),
+    AstNode::IsNull(mhs) => build_is_null(mhs, false, functions),
+    AstNode::Le(lhs, rhs) => build_ordering(lhs, rhs, "<=", Ordering::is_le, functions),
+    AstNode::Literal(mhs) => build_value(Value::Number(*mhs)),
+    AstNode::Lt(lhs, rhs) => build_ordering(lhs, rhs, "<", Ordering::is_lt, functions),
+    AstNode::Max(mhs) => build_extremum(mhs, "max", Decimal::max, functions),
+    AstNode::Min(mhs) => build_extremum(mhs, "min", Decimal::min, functions),
+    AstNode::Mul(lhs, rhs) => build_arithmetic(lhs, rhs, "*", Decimal::checked_mul, functions),
+    AstNode::Neg(mhs) => build_neg(mhs, functions),
+    AstNode::Nq(lhs, rhs) => build_equality(lhs, rhs, "<>", true, functions),
+    AstNode::Null => build_value(Value::Null),
+    AstNode::Number(mhs) => build_number(*mhs),
+    AstNode::Or(lhs, rhs) => build_or(lhs, rhs, functions),
+    AstNode::Round(lhs, rhs) => build_round(lhs, rhs, functions),
+    AstNode::StrLiteral(mhs) => build_value(Value::Str(mhs.clone())),
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(mhs) => build_value(Value::Date(*mhs)),
+    AstNode::Sub(lhs, rhs) => build_arithmetic(lhs, rhs, "-", Decimal::checked_sub, functions),
+    AstNode::Sum => build_aggregate(sum_numbers),
+    AstNode::Xor(lhs, rhs) => build_xor(lhs, rhs, functions),
+  }
+}
+
+/// Builds a strict evaluator for `abs` function.
+fn build_abs(mhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let mhe = build_node(mhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    Ok(expect_number(mhe(iv)?, "abs")?.map_or(Value::Null, |mhv| Value::Number(mhv.abs())))
+  }))
+}
+
+/// Builds a strict evaluator for `sum`, `avg` and `count` functions, values other than numbers are skipped.
+fn build_aggregate(aggregate: fn(Box<dyn Iterator<Item = &Value> + '_>) -> Value) -> Result<StrictEvaluator> {
+  Ok(Box::new(move |iv: &IndexedValues| Ok(aggregate(Context::values(iv)))))
+}
+
+/// Builds a strict evaluator for `and` operator, the right-side operand
+/// is not evaluated when the left-side operand evaluates to `false`.
+fn build_and(lhs: &AstNode, rhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| match expect_bool(lhe(iv)?, "and")? {
+    Some(false) => Ok(Value::Bool(false)),
+    Some(true) => Ok(expect_bool(rhe(iv)?, "and")?.map_or(Value::Null, Value::Bool)),
+    None => Ok(Value::Null),
+  }))
+}
+
+/// Builds a strict evaluator for arithmetic operator.
+fn build_arithmetic(
+  lhs: &AstNode,
+  rhs: &AstNode,
+  operator: &'static str,
+  operation: fn(Decimal, Decimal) -> Option<Decimal>,
+  functions: &Functions,
+) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    let (lhv, rhv) = (lhe(iv)?, rhe(iv)?);
+    match (lhv.as_decimal(), rhv.as_decimal()) {
+      (Some(lhv), Some(rhv)) => match operation(lhv, rhv) {
+        Some(result) => Ok(Value::Number(result)),
+        None if operator == "/" && rhv.is_zero() => Err(SecelError::new("division by zero")),
+        None => Err(SecelError::new(&format!("arithmetic overflow in '{}'", operator))),
+      },
+      _ if is_number_or_null(&lhv) && is_number_or_null(&rhv) => Ok(Value::Null),
+      _ => Err(err_operand_types(operator, &lhv, &rhv)),
+    }
+  }))
+}
+
+/// Builds a strict evaluator for `between` operator, both bounds are inclusive.
+fn build_between(lhs: &AstNode, mhs: &AstNode, rhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let mhe = build_node(mhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    let values = [lhe(iv)?, mhe(iv)?, rhe(iv)?];
+    match values.map(|value| expect_number(value, "between")) {
+      [Ok(Some(lhv)), Ok(Some(mhv)), Ok(Some(rhv))] => Ok(Value::Bool(mhv <= lhv && lhv <= rhv)),
+      [lhv, mhv, rhv] => lhv.and(mhv).and(rhv).map(|_| Value::Null),
+    }
+  }))
+}
+
+/// Builds a strict evaluator for the call of user-registered function, unknown function is an error.
+fn build_call(name: &str, mhs: &[AstNode], functions: &Functions) -> Result<StrictEvaluator> {
+  let function = match functions.get(name) {
+    Some(function) => function.clone(),
+    None => return Err(SecelError::new(&format!("unknown function '{}'", name))),
+  };
+  let mhes = mhs.iter().map(|node| build_node(node, functions)).collect::<Result<Vec<StrictEvaluator>>>()?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    let values = mhes.iter().map(|mhe| mhe(iv)).collect::<Result<Vec<Value>>>()?;
+    Ok(function(&values))
+  }))
+}
+
+/// Builds a strict evaluator for `coalesce` function.
+fn build_coalesce(mhs: &[AstNode], functions: &Functions) -> Result<StrictEvaluator> {
+  let mhes = mhs.iter().map(|node| build_node(node, functions)).collect::<Result<Vec<StrictEvaluator>>>()?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    for mhe in &mhes {
+      let value = mhe(iv)?;
+      if value != Value::Null {
+        return Ok(value);
+      }
+    }
+    Ok(Value::Null)
+  }))
+}
+
+/// Builds a strict evaluator for `=` and `<>` operators.
+fn build_equality(lhs: &AstNode, rhs: &AstNode, operator: &'static str, negated: bool, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| match strict_eq(&lhe(iv)?, &rhe(iv)?, operator)? {
+    Value::Bool(result) => Ok(Value::Bool(result != negated)),
+    other => Ok(other),
+  }))
+}
+
+/// Builds a strict evaluator for `min` and `max` functions.
+fn build_extremum(mhs: &[AstNode], operator: &'static str, select: fn(Decimal, Decimal) -> Decimal, functions: &Functions) -> Result<StrictEvaluator> {
+  let mhes = mhs.iter().map(|node| build_node(node, functions)).collect::<Result<Vec<StrictEvaluator>>>()?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    let mut result = None;
+    let mut null_involved = false;
+    for mhe in &mhes {
+      match expect_number(mhe(iv)?, operator)? {
+        Some(mhv) => result = Some(result.map_or(mhv, |value| select(value, mhv))),
+        None => null_involved = true,
+      }
+    }
+    Ok(match result {
+      Some(result) if !null_involved => Value::Number(result),
+      _ => Value::Null,
+    })
+  }))
+}
+
+/// Builds a strict evaluator for `if` expression.
+fn build_if(mhs: &AstNode, lhs: &AstNode, rhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let mhe = build_node(mhs, functions)?;
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| match expect_bool(mhe(iv)?, "if")? {
+    Some(true) => lhe(iv),
+    Some(false) => rhe(iv),
+    None => Ok(Value::Null),
+  }))
+}
+
+/// Builds a strict evaluator for `in` operator.
+fn build_in(lhs: &AstNode, rhs: &[AstNode], functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhes = rhs.iter().map(|node| build_node(node, functions)).collect::<Result<Vec<StrictEvaluator>>>()?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    let lhv = lhe(iv)?;
+    let mut null_involved = lhv == Value::Null;
+    for rhe in &rhes {
+      let rhv = rhe(iv)?;
+      match strict_eq(&lhv, &rhv, "in")? {
+        Value::Bool(true) => return Ok(Value::Bool(true)),
+        Value::Bool(false) if rhv != Value::Null => {}
+        _ => null_involved = true,
+      }
+    }
+    Ok(if null_involved { Value::Null } else { Value::Bool(false) })
+  }))
+}
+
+/// Builds a strict evaluator for `is null` and `is not null` tests.
+fn build_is_null(mhs: &AstNode, negated: bool, functions: &Functions) -> Result<StrictEvaluator> {
+  let mhe = build_node(mhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| Ok(Value::Bool((mhe(iv)? == Value::Null) != negated))))
+}
+
+/// Builds a strict evaluator for unary `-` operator.
+fn build_neg(mhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let mhe = build_node(mhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    Ok(expect_number(mhe(iv)?, "-")?.map_or(Value::Null, |mhv| Value::Number(-mhv)))
+  }))
+}
+
+/// Builds a strict evaluator for `Number` node, missing index is reported as an error.
+fn build_number(key: IndexKey) -> Result<StrictEvaluator> {
+  Ok(Box::new(move |iv: &IndexedValues| {
+    iv.get(&key).cloned().ok_or_else(|| SecelError::new(&format!("missing value at index {}", key)))
+  }))
+}
+
+/// Builds a strict evaluator for `or` operator, the right-side operand
+/// is not evaluated when the left-side operand evaluates to `true`.
+fn build_or(lhs: &AstNode, rhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| match expect_bool(lhe(iv)?, "or")? {
+    Some(true) => Ok(Value::Bool(true)),
+    Some(false) => Ok(expect_bool(rhe(iv)?, "or")?.map_or(Value::Null, Value::Bool)),
+    None => Ok(Value::Null),
+  }))
+}
+
+/// Builds a strict evaluator for ordering comparison operators, comparing two numbers or two strings.
+fn build_ordering(lhs: &AstNode, rhs: &AstNode, operator: &'static str, compare: fn(Ordering) -> bool, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    let (lhv, rhv) = (lhe(iv)?, rhe(iv)?);
+    match (compare_values(&lhv, &rhv), &lhv, &rhv) {
+      (Some(ordering), _, _) => Ok(Value::Bool(compare(ordering))),
+      (None, Value::Null, other) | (None, other, Value::Null) if !matches!(other, Value::Bool(_)) => Ok(Value::Null),
+      _ => Err(err_comparison_types(operator, &lhv, &rhv)),
+    }
+  }))
+}
+
+/// Builds a strict evaluator for `round` function, the scale must be a non-negative integer literal.
+fn build_round(lhs: &AstNode, rhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let scale = round_scale(rhs)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    Ok(expect_number(lhe(iv)?, "round")?.map_or(Value::Null, |lhv| Value::Number(lhv.round_dp(scale))))
+  }))
+}
+
+/// Builds a strict evaluator returning constant value.
+fn build_value(value: Value) -> Result<StrictEvaluator> {
+  Ok(Box::new(move |_: &IndexedValues| Ok(value.clone())))
+}
+
+/// Builds a strict evaluator for `xor` operator.
+fn build_xor(lhs: &AstNode, rhs: &AstNode, functions: &Functions) -> Result<StrictEvaluator> {
+  let lhe = build_node(lhs, functions)?;
+  let rhe = build_node(rhs, functions)?;
+  Ok(Box::new(move |iv: &IndexedValues| {
+    match (expect_bool(lhe(iv)?, "xor")?, expect_bool(rhe(iv)?, "xor")?) {
+      (Some(lhv), Some(rhv)) => Ok(Value::Bool(lhv ^ rhv)),
+      _ => Ok(Value::Null),
+    }
+  }))
+}
+
+/// Compares two values following the semantics of `=` operator,
+/// comparing values of different types (other than `Null`) is an error.
+fn strict_eq(lhv: &Value, rhv: &Value, operator: &str) -> Result<Value> {
+  match (lhv, rhv) {
+    (Value::Null, _) | (_, Value::Null) => Ok(eq_values(lhv, rhv)),
+    (Value::Int(_) | Value::Number(_), Value::Int(_) | Value::Number(_)) => Ok(eq_values(lhv, rhv)),
+    _ if lhv.typename() == rhv.typename() => Ok(eq_values(lhv, rhv)),
+    _ => Err(err_comparison_types(operator, lhv, rhv)),
+  }
+}
+
+/// Returns the boolean operand value, `None` for `Null`, error for other types.
+fn expect_bool(value: Value, operator: &str) -> Result<Option<bool>> {
+  match value {
+    Value::Bool(value) => Ok(Some(value)),
+    Value::Null => Ok(None),
+    other => Err(SecelError::new(&format!("expected bool operand of '{}', found {}", operator, other.typename()))),
+  }
+}
+
+/// Returns the numeric operand value (`Int` is promoted to decimal), `None` for `Null`, error for other types.
+fn expect_number(value: Value, operator: &str) -> Result<Option<Decimal>> {
+  match (value.as_decimal(), value) {
+    (Some(value), _) => Ok(Some(value)),
+    (None, Value::Null) => Ok(None),
+    (None, other) => Err(SecelError::new(&format!(
+      "expected number operand of '{}', found {}",
+      operator,
+      other.typename()
+    ))),
+  }
+}
+
+/// Returns `true` for numbers and `Null`, operands of arithmetic operators evaluating to `Null` without an error.
+fn is_number_or_null(value: &Value) -> bool {
+  matches!(value, Value::Int(_) | Value::Number(_) | Value::Null)
+}
+
+/// Creates an error reporting operands of types not supported by specified operator.
+fn err_operand_types(operator: &str, lhv: &Value, rhv: &Value) -> SecelError {
+  SecelError::new(&format!("cannot apply '{}' to {} and {}", operator, lhv.typename(), rhv.typename()))
+}
+
+/// Creates an error reporting values of types that can not be compared by specified operator.
+fn err_comparison_types(operator: &str, lhv: &Value, rhv: &Value) -> SecelError {
+  SecelError::new(&format!("cannot compare {} to {} with '{}'", lhv.typename(), rhv.typename(), operator))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+
+  fn eval(input: &str, values: &[Value]) -> Result<Value> {
+    let node = Parser::new(input).parse().unwrap();
+    let iv = values
+      .iter()
+      .enumerate()
+      .map(|(i, value)| ((i + 1) as IndexKey, value.clone()))
+      .collect::<IndexedValues>();
+    build_evaluator_strict(&node).unwrap()(&iv)
+  }
+
+  fn err(input: &str, values: &[Value]) -> String {
+    err_node(Parser::new(input).parse().unwrap(), values)
+  }
+
+  fn err_node(node: AstNode, values: &[Value]) -> String {
+    let iv = values
+      .iter()
+      .enumerate()
+      .map(|(i, value)| ((i + 1) as IndexKey, value.clone()))
+      .collect::<IndexedValues>();
+    build_evaluator_strict(&node).unwrap()(&iv).unwrap_err().to_string()
+  }
+
+  #[test]
+  fn test_values() {
+    let values = [Value::Number(Decimal::new(5, 0)), Value::Number(Decimal::new(2, 0)), Value::Null];
+    assert_eq!(Ok(Value::Number(Decimal::new(5, 0))), eval("if(1 > 2; 1; 2)", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(7, 0))), eval("1 + 2", &values));
+    assert_eq!(Ok(Value::Null), eval("1 + 3", &values));
+    assert_eq!(Ok(Value::Null), eval("1 > 3", &values));
+    assert_eq!(Ok(Value::Bool(false)), eval("1 = 3", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("3 is null", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(5, 0))), eval("max(1; 2)", &values));
+    assert_eq!(Ok(Value::Null), eval("min(1; 2; 3)", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("1 between 2 and 1 + 2", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("1 in (2; 1)", &values));
+    assert_eq!(Ok(Value::Bool(false)), eval("1 < 2 and 4 > 1", &values));
+  }
+
+  #[test]
+  fn test_int() {
+    let values = [Value::Int(2), Value::Number(Decimal::new(20, 1)), Value::Str("a".to_string())];
+    assert_eq!(Ok(Value::Bool(true)), eval("1 = 2", &values));
+    assert_eq!(Ok(Value::Bool(false)), eval("1 < 2", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("1 >= 2.0", &values));
+    assert_eq!("cannot compare int to str with '='", err("1 = 3", &values));
+    assert_eq!("cannot compare int to str with '>'", err("1 > 3", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(40, 1))), eval("1 + 2", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(-2, 0))), eval("- 1", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(2, 0))), eval("abs(1)", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(2, 0))), eval("round(1; 1)", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("1 between 2 and 1", &values));
+    assert_eq!(Ok(Value::Number(Decimal::new(2, 0))), eval("max(1; 2)", &values));
+    assert_eq!("cannot apply '*' to int and str", err("1 * 3", &values));
+    assert_eq!("expected number operand of 'min', found str", err("min(1; 3)", &values));
+  }
+
+  #[test]
+  fn test_errors() {
+    let values = [
+      Value::Number(Decimal::new(5, 0)),
+      Value::Bool(true),
+      Value::Str("a".to_string()),
+      Value::Number(Decimal::ZERO),
+    ];
+    assert_eq!("missing value at index 9", err("9 > 1", &values));
+    assert_eq!("missing value at index 9", err("9 is null", &values));
+    assert_eq!("cannot compare bool to number with '>'", err("2 > 1", &values));
+    assert_eq!("cannot compare number to str with '<='", err("1 <= 3", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("3 < \"b\"", &values));
+    assert_eq!("cannot compare bool to number with '='", err("2 = 1", &values));
+    assert_eq!("cannot compare number to str with '<>'", err("1 <> 3", &values));
+    assert_eq!(Ok(Value::Bool(true)), eval("2 = 2", &values));
+    assert_eq!("cannot compare number to bool with 'in'", err("1 in (2; 1)", &values));
+    assert_eq!("cannot apply '+' to number and bool", err("1 + 2", &values));
+    assert_eq!("division by zero", err("1 / 4", &values));
+    assert_eq!("expected number operand of 'min', found str", err("min(1; 3)", &values));
+    assert_eq!("expected number operand of 'abs', found str", err("abs(3)", &values));
+    assert_eq!("expected number operand of 'round', found bool", err("round(2; 1)", &values));
+    assert_eq!("expected number operand of '-', found str", err("- 3", &values));
+    assert_eq!("expected number operand of 'between', found bool", err("1 between 2 and 1", &values));
+    let number = || Box::new(AstNode::Number(1));
+    let bool = || Box::new(AstNode::Bool(true));
+    assert_eq!(
+      "expected bool operand of 'and', found number",
+      err_node(AstNode::And(bool(), number()), &values)
+    );
+    assert_eq!("expected bool operand of 'or', found number", err_node(AstNode::Or(number(), bool()), &values));
+    assert_eq!(
+      "expected bool operand of 'xor', found number",
+      err_node(AstNode::Xor(bool(), number()), &values)
+    );
+    assert_eq!(
+      "expected bool operand of 'if', found number",
+      err_node(AstNode::If(number(), number(), number()), &values)
+    );
+  }
+
+  #[test]
+  fn test_evaluate_opt() {
+    let evaluator = build_evaluator_strict(&Parser::new("1 + 2").parse().unwrap()).unwrap();
+    let mut values = IndexedValues::new();
+    values.insert(1, Value::Number(Decimal::new(5, 0)));
+    values.insert(2, Value::Null);
+    assert_eq!(Some(Value::Null), evaluate_opt(&evaluator, &values));
+    values.insert(2, Value::Str("a".to_string()));
+    assert_eq!(None, evaluate_opt(&evaluator, &values));
+    values.remove(&2);
+    assert_eq!(None, evaluate_opt(&evaluator, &values));
+    values.insert(2, Value::Number(Decimal::new(2, 0)));
+    assert_eq!(Some(Value::Number(Decimal::new(7, 0))), evaluate_opt(&evaluator, &values));
+  }
+}
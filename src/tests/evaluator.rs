@@ -26,118 +26,765 @@
 
 use crate::parser::Parser;
 use crate::values::Value;
-use crate::{evaluator, IndexKey};
+use crate::{
+  build_evaluator_strict, build_evaluator_strict_with_functions, build_slot_evaluator, compile, evaluate_into, evaluator, interpreter, AstNode,
+  EvaluatorConfig, Function, Functions, IndexKey, IndexedValues, Program, DEFAULT_MAX_EVALUATOR_DEPTH,
+};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::sync::Arc;
 
-fn eq(input: &str, values: &[Value], expected: Value) {
+fn eq(input: &str, values: &[&Value], expected: &Value) {
+  assert_eq!(*expected, eval_with_config(input, values, &EvaluatorConfig::default()));
+}
+
+/// Evaluates the input using all evaluators with specified configuration,
+/// asserts that all evaluators agree and returns the result.
+fn eval_with_config(input: &str, values: &[&Value], config: &EvaluatorConfig) -> Value {
   let node = Parser::new(input).parse().unwrap();
-  let evaluator = evaluator::build_evaluator(&node).unwrap();
+  let evaluator = evaluator::build_evaluator_with_config(&node, config).unwrap();
   let mut results = HashMap::new();
   for (i, value) in values.iter().enumerate() {
-    results.insert((i + 1) as IndexKey, *value);
+    results.insert((i + 1) as IndexKey, (*value).clone());
   }
-  assert_eq!(expected, evaluator(&results));
+  let expected = evaluator(&results);
+  assert_eq!(expected, interpreter::eval_with_config(&node, &results, config), "{}", input);
+  assert_eq!(expected, Program::compile(&node).unwrap().run_with_config(&results, config), "{}", input);
+  assert_eq!(expected, compile(&node).unwrap().eval_with_config(&results, config), "{}", input);
+  expected
 }
 
 #[test]
 fn test_0001() {
-  let p1 = Value::Number(Decimal::new(100, 0));
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(110, 0));
-  eq("if(1=2;1;2)", &[p1, p2], p1);
-  eq("if(1=2;1;2)", &[p1, p3], p3);
+  let p1 = Value::from(100);
+  let p2 = Value::from(100);
+  let p3 = Value::from(110);
+  eq("if(1=2;1;2)", &[&p1, &p2], &p1);
+  eq("if(1=2;1;2)", &[&p1, &p3], &p3);
 }
 
 #[test]
 fn test_0002() {
-  let p1 = Value::Number(Decimal::new(100, 0));
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(110, 0));
-  eq("if(1<>2;1;2)", &[p1, p3], p1);
-  eq("if(1<>2;1;2)", &[p1, p2], p2);
+  let p1 = Value::from(100);
+  let p2 = Value::from(100);
+  let p3 = Value::from(110);
+  eq("if(1<>2;1;2)", &[&p1, &p3], &p1);
+  eq("if(1<>2;1;2)", &[&p1, &p2], &p2);
 }
 
 #[test]
 fn test_0003() {
-  let p1 = Value::Number(Decimal::new(100, 0));
-  let p2 = Value::Number(Decimal::new(110, 0));
-  eq("if(1>2;1;2)", &[p1, p2], p2);
-  eq("if(1>2;1;2)", &[p2, p1], p2);
+  let p1 = Value::from(100);
+  let p2 = Value::from(110);
+  eq("if(1>2;1;2)", &[&p1, &p2], &p2);
+  eq("if(1>2;1;2)", &[&p2, &p1], &p2);
 }
 
 #[test]
 fn test_0004() {
-  let p1 = Value::Number(Decimal::new(100, 0));
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(110, 0));
-  eq("if(1>=2;1;2)", &[p1, p3], p3);
-  eq("if(1>=2;1;2)", &[p3, p1], p3);
-  eq("if(1>=2;1;2)", &[p2, p1], p2);
+  let p1 = Value::from(100);
+  let p2 = Value::from(100);
+  let p3 = Value::from(110);
+  eq("if(1>=2;1;2)", &[&p1, &p3], &p3);
+  eq("if(1>=2;1;2)", &[&p3, &p1], &p3);
+  eq("if(1>=2;1;2)", &[&p2, &p1], &p2);
 }
 
 #[test]
 fn test_0005() {
-  let p1 = Value::Number(Decimal::new(100, 0));
-  let p2 = Value::Number(Decimal::new(110, 0));
-  eq("if(1<2;1;2)", &[p1, p2], p1);
-  eq("if(1<2;1;2)", &[p2, p1], p1);
+  let p1 = Value::from(100);
+  let p2 = Value::from(110);
+  eq("if(1<2;1;2)", &[&p1, &p2], &p1);
+  eq("if(1<2;1;2)", &[&p2, &p1], &p1);
 }
 
 #[test]
 fn test_0006() {
-  let p1 = Value::Number(Decimal::new(100, 0));
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(110, 0));
-  eq("if(1<=2;1;2)", &[p3, p1], p1);
-  eq("if(1<=2;1;2)", &[p1, p2], p1);
-  eq("if(1<=2;1;2)", &[p1, p3], p1);
+  let p1 = Value::from(100);
+  let p2 = Value::from(100);
+  let p3 = Value::from(110);
+  eq("if(1<=2;1;2)", &[&p3, &p1], &p1);
+  eq("if(1<=2;1;2)", &[&p1, &p2], &p1);
+  eq("if(1<=2;1;2)", &[&p1, &p3], &p1);
 }
 
 #[test]
 fn test_0007() {
   let p1 = Value::Null;
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(110, 0));
-  eq("if(1=null;2;1)", &[p1, p2], p2);
-  eq("if(null=1;2;1)", &[p1, p2], p2);
-  eq("if(1=null;2;1)", &[p2, p3], p2);
-  eq("if(null=1;2;1)", &[p2, p3], p2);
-  eq("if(null=null;1;2)", &[p2, p3], p2);
+  let p2 = Value::from(100);
+  let p3 = Value::from(110);
+  eq("if(1=null;2;1)", &[&p1, &p2], &p2);
+  eq("if(null=1;2;1)", &[&p1, &p2], &p2);
+  eq("if(1=null;2;1)", &[&p2, &p3], &p2);
+  eq("if(null=1;2;1)", &[&p2, &p3], &p2);
+  eq("if(null=null;1;2)", &[&p2, &p3], &p2);
 }
 
 #[test]
 fn test_0008() {
   let p1 = Value::Null;
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(110, 0));
-  eq("if(1<>null;1;2)", &[p2, p3], p2);
-  eq("if(null<>1;1;2)", &[p2, p3], p2);
-  eq("if(1<>null;1;2)", &[p1, p3], p3);
-  eq("if(null<>1;1;2)", &[p1, p3], p3);
-  eq("if(null<>null;1;2)", &[p2, p3], p3);
+  let p2 = Value::from(100);
+  let p3 = Value::from(110);
+  eq("if(1<>null;1;2)", &[&p2, &p3], &p2);
+  eq("if(null<>1;1;2)", &[&p2, &p3], &p2);
+  eq("if(1<>null;1;2)", &[&p1, &p3], &p3);
+  eq("if(null<>1;1;2)", &[&p1, &p3], &p3);
+  eq("if(null<>null;1;2)", &[&p2, &p3], &p3);
 }
 
 #[test]
 fn test_0009() {
-  let p1 = Value::Number(Decimal::new(101, 0));
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(201, 0));
-  let p4 = Value::Number(Decimal::new(200, 0));
-  let p5 = Value::Number(Decimal::new(512, 0));
-  eq("if(1>2 and 3>4;5;1)", &[p1, p2, p3, p4, p5], p5);
-  eq("if(1>2 and 3>4;5;2)", &[p2, p1, p3, p4, p5], p1);
-  eq("if(1>2 and 3>4;5;3)", &[p1, p2, p4, p3, p5], p4);
+  let p1 = Value::from(101);
+  let p2 = Value::from(100);
+  let p3 = Value::from(201);
+  let p4 = Value::from(200);
+  let p5 = Value::from(512);
+  eq("if(1>2 and 3>4;5;1)", &[&p1, &p2, &p3, &p4, &p5], &p5);
+  eq("if(1>2 and 3>4;5;2)", &[&p2, &p1, &p3, &p4, &p5], &p1);
+  eq("if(1>2 and 3>4;5;3)", &[&p1, &p2, &p4, &p3, &p5], &p4);
 }
 
 #[test]
 fn test_0010() {
-  let p1 = Value::Number(Decimal::new(101, 0));
-  let p2 = Value::Number(Decimal::new(100, 0));
-  let p3 = Value::Number(Decimal::new(201, 0));
-  let p4 = Value::Number(Decimal::new(200, 0));
-  let p5 = Value::Number(Decimal::new(512, 0));
-  eq("if(1>2 or 3>4;5;1)", &[p1, p2, p3, p4, p5], p5);
-  eq("if(1>2 or 3>4;5;2)", &[p2, p1, p3, p4, p5], p5);
-  eq("if(1>2 or 3>4;5;3)", &[p2, p1, p4, p3, p5], p4);
+  let p1 = Value::from(101);
+  let p2 = Value::from(100);
+  let p3 = Value::from(201);
+  let p4 = Value::from(200);
+  let p5 = Value::from(512);
+  eq("if(1>2 or 3>4;5;1)", &[&p1, &p2, &p3, &p4, &p5], &p5);
+  eq("if(1>2 or 3>4;5;2)", &[&p2, &p1, &p3, &p4, &p5], &p5);
+  eq("if(1>2 or 3>4;5;3)", &[&p2, &p1, &p4, &p3, &p5], &p4);
+}
+
+#[test]
+fn test_0011() {
+  let p1 = Value::from(100);
+  let p2 = Value::from(99);
+  eq("if(1>99.5;1;0.5)", &[&p1], &p1);
+  eq("if(1>99.5;1;0.5)", &[&p2], &Value::Number(Decimal::new(5, 1)));
+}
+
+#[test]
+fn test_0012() {
+  let p1 = Value::from(-3);
+  let p2 = Value::from(-1);
+  eq("if(1>-2;1;-2)", &[&p1], &Value::from(-2));
+  eq("if(1>-2;1;-2)", &[&p2], &p2);
+}
+
+#[test]
+fn test_0013() {
+  let p1 = Value::from(100);
+  let p2 = Value::from(110);
+  eq("if(true;1;2)", &[&p1, &p2], &p1);
+  eq("if(false;1;2)", &[&p1, &p2], &p2);
+  eq("if(false or 1=2;1;2)", &[&p1, &p1], &p1);
+  eq("if(true and 1=2;1;2)", &[&p1, &p2], &p2);
+}
+
+#[test]
+fn test_0014() {
+  let node = Parser::new("if(1000>1;1000;1)").parse().unwrap();
+  let evaluator = evaluator::build_evaluator(&node).unwrap();
+  let mut values = HashMap::new();
+  values.insert(1, 1.into());
+  values.insert(1000, 2.into());
+  assert_eq!(Value::from(2), evaluator(&values));
+}
+
+#[test]
+fn test_0015() {
+  let p1 = Value::Str("A1".to_string());
+  let p2 = Value::Str("B2".to_string());
+  let p3 = Value::from(100);
+  eq(r#"if(1="A1";1;2)"#, &[&p1, &p2], &p1);
+  eq(r#"if(1="A1";1;2)"#, &[&p2, &p1], &p1);
+  eq(r#"if(1=2;1;3)"#, &[&p1, &p1, &p3], &p1);
+  eq(r#"if(1<>2;1;3)"#, &[&p1, &p2, &p3], &p1);
+  eq(r#"if(1<>"A1";1;2)"#, &[&p1, &p2], &p2);
+  eq(r#"if(1=null;1;2)"#, &[&p1, &p2], &p2);
+  eq(r#"if(1=2;1;2)"#, &[&p1, &p3], &Value::Null);
+  eq(r#"if(1<>2;1;2)"#, &[&p3, &p1], &Value::Null);
+}
+
+#[test]
+fn test_0016() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(2);
+  let p3 = Value::from(3);
+  let p4 = Value::Number(Decimal::ZERO);
+  eq("if(1 + 2 > 3; 1; 3)", &[&p1, &p2, &p3], &p3);
+  eq("if(1 + 2 * 3 > 3 * 2; 1; 3)", &[&p1, &p2, &p3], &p1);
+  eq("if((1 + 2) * 3 = 9.0; 1; 3)", &[&p1, &p2, &p3], &p1);
+  eq("if(3 - 2 - 1 = 0.0; 1; 3)", &[&p1, &p2, &p3], &p1);
+  eq("if(3 / 2 = 1.5; 1; 3)", &[&p1, &p2, &p3], &p1);
+  eq("if(3 / 4 = 1; 1; 3)", &[&p1, &p2, &p3, &p4], &p3);
+  eq("if(3 / 4 > 1; 1; 3)", &[&p1, &p2, &p3, &p4], &Value::Null);
+  eq("if(3 + null = 1; 1; 3)", &[&p1, &p2, &p3], &p3);
+  eq("if(3 + null > 1; 1; 3)", &[&p1, &p2, &p3], &Value::Null);
+}
+
+#[test]
+fn test_0017() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(2);
+  eq("1", &[&p1, &p2], &p1);
+  eq("null", &[&p1, &p2], &Value::Null);
+  eq("1 > 2", &[&p1, &p2], &Value::Bool(false));
+  eq("1 + 2", &[&p1, &p2], &Value::from(3));
+}
+
+#[test]
+fn test_0018() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(5);
+  eq("- 2", &[&p1, &p2], &Value::from(-5));
+  eq("- - 2", &[&p1, &p2], &p2);
+  eq("- -2", &[&p1, &p2], &Value::from(2));
+  eq("-2", &[&p1, &p2], &Value::from(-2));
+  eq("if(1 > - 2; 1; 2)", &[&p1, &p2], &p1);
+  eq("if(1 > - 2; - 2; 2)", &[&p1, &p2], &Value::from(-5));
+  eq("- 3", &[&p1, &p2], &Value::Null);
+}
+
+#[test]
+fn test_0019() {
+  let p1 = Value::from(5);
+  let p2 = Value::from(1);
+  let p3 = Value::from(10);
+  eq("1 between 2 and 3", &[&p1, &p2, &p3], &Value::Bool(true));
+  eq("1 between 2 and 3", &[&p2, &p1, &p3], &Value::Bool(false));
+  eq("1 between 2 and 3", &[&p3, &p2, &p3], &Value::Bool(true));
+  eq("if(1 between 2 and 3; 1; 2)", &[&p1, &p2, &p3], &p1);
+  eq("1 between 2 and null", &[&p1, &p2], &Value::Null);
+  eq("1 between null and 3", &[&p1, &p2, &p3], &Value::Null);
+}
+
+#[test]
+fn test_0020() {
+  let p1 = Value::from(3);
+  let p2 = Value::from(1);
+  let p3 = Value::from(2);
+  let p4 = Value::from(3);
+  eq("if(1 in (2;3;4); 1; null)", &[&p1, &p2, &p3, &p4], &p1);
+  eq("if(1 in (2;3); 1; null)", &[&p1, &p2, &p3, &p4], &Value::Null);
+  eq("1 in (2;3)", &[&p1, &p2, &p3, &p4], &Value::Bool(false));
+  eq("1 in (2;null)", &[&p1, &p2, &p3, &p4], &Value::Null);
+  eq("1 in (2;null;4)", &[&p1, &p2, &p3, &p4], &Value::Bool(true));
+  eq("1 in (\"a\")", &[&p1], &Value::Null);
+}
+
+#[test]
+fn test_0021() {
+  let p1 = Value::from(1);
+  let p2 = Value::Bool(true);
+  eq("1 is null", &[&p1], &Value::Bool(false));
+  eq("1 is not null", &[&p1], &Value::Bool(true));
+  eq("3 is null", &[&p1], &Value::Bool(true));
+  eq("3 is not null", &[&p1], &Value::Bool(false));
+  // unlike `is null`, comparison with `null` evaluates to `Null` for non-comparable values
+  eq("2 is null", &[&p1, &p2], &Value::Bool(false));
+  eq("2 = null", &[&p1, &p2], &Value::Null);
+  eq("1 + 2 is null", &[&p1, &p2], &Value::Bool(true));
+  eq("1 + 2 = null", &[&p1, &p2], &Value::Bool(true));
+}
+
+#[test]
+fn test_0022() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(2);
+  let p3 = Value::Bool(true);
+  eq("if(1=1 xor 1=2; 1; 2)", &[&p1, &p2], &p1);
+  eq("if(1=1 xor 2=2; 1; 2)", &[&p1, &p2], &p2);
+  eq("1=2 xor 2=1", &[&p1, &p2], &Value::Bool(false));
+  eq("1 > 2 xor true", &[&p1, &p3], &Value::Null);
+}
+
+#[test]
+fn test_0023() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(2);
+  eq("if(1<2;1)", &[&p1, &p2], &p1);
+  eq("if(1>2;1)", &[&p1, &p2], &Value::Null);
+  eq("if(1>2;1;if(2>1;2))", &[&p1, &p2], &p2);
+}
+
+#[test]
+fn test_0024() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(2);
+  let p3 = Value::from(3);
+  eq("if(if(1=2;1;3) > 2;1;2)", &[&p1, &p2, &p3], &p1);
+  eq("if(if(1<2;1;3) > 2;1;2)", &[&p1, &p2, &p3], &p2);
+}
+
+#[test]
+fn test_0025() {
+  let p1 = Value::from(6);
+  let p2 = Value::Number(Decimal::ZERO);
+  eq("1 / 2", &[&p1, &p2], &Value::Null);
+  eq("1 / 0.0", &[&p1], &Value::Null);
+  eq("if(1 / 2 > 1; 1; 2)", &[&p1, &p2], &Value::Null);
+  eq("1 + null", &[&p1], &Value::Null);
+  eq("null * 1", &[&p1], &Value::Null);
+}
+
+#[test]
+fn test_0026() {
+  let p1 = Value::from(1);
+  let p2 = Value::Bool(true);
+  eq("1 > 1 and 1 > 2", &[&p1, &p2], &Value::Bool(false));
+  eq("1 = 1 or 1 > 2", &[&p1, &p2], &Value::Bool(true));
+  eq("1 = 1 and 1 > 2", &[&p1, &p2], &Value::Null);
+  eq("1 > 1 or 1 > 2", &[&p1, &p2], &Value::Null);
+}
+
+#[test]
+fn test_0027() {
+  let node = Parser::new("if(0 > 1; 0; 2)").parse().unwrap();
+  let evaluator = evaluator::build_context_evaluator::<Vec<Value>>(&node, &EvaluatorConfig::default()).unwrap();
+  let values = vec![Value::from(1), Value::from(2), Value::from(3)];
+  assert_eq!(Value::from(3), evaluator(&values));
+  let values = vec![Value::from(3), Value::from(2)];
+  assert_eq!(Value::from(3), evaluator(&values));
+  let values = vec![Value::from(1)];
+  assert_eq!(Value::Null, evaluator(&values));
+  let evaluator = evaluator::build_context_evaluator::<[Value]>(&node, &EvaluatorConfig::default()).unwrap();
+  let values = [Value::from(1), Value::from(2), Value::Null];
+  assert_eq!(Value::Null, evaluator(&values[..]));
+}
+
+#[test]
+fn test_0028() {
+  let node = Parser::new("1 + 2").parse().unwrap();
+  let config = EvaluatorConfig {
+    missing: Value::from(0),
+    ..EvaluatorConfig::default()
+  };
+  let evaluator = evaluator::build_evaluator_with_config(&node, &config).unwrap();
+  let mut values = HashMap::new();
+  values.insert(1, 5.into());
+  assert_eq!(Value::from(5), evaluator(&values));
+  let evaluator = evaluator::build_evaluator(&node).unwrap();
+  assert_eq!(Value::Null, evaluator(&values));
+}
+
+#[test]
+fn test_0029() {
+  let t = Value::Bool(true);
+  let f = Value::Bool(false);
+  let n = Value::from(1);
+  eq("1 = 2", &[&t, &t], &Value::Bool(true));
+  eq("1 = 2", &[&t, &f], &Value::Bool(false));
+  eq("1 <> 2", &[&t, &f], &Value::Bool(true));
+  eq("1 <> 2", &[&f, &f], &Value::Bool(false));
+  eq("1 = 2", &[&t, &n], &Value::Null);
+  eq("1 <> 2", &[&n, &f], &Value::Null);
+}
+
+#[test]
+fn test_0030() {
+  let inputs = [
+    "1 + 2 * 3 - 4 / 2",
+    "-1 + 2.5",
+    "if(1 > 2; 1; if(1 = 2; 2; 3))",
+    "1 between 2 and 3 or 1 in (2; 3; null)",
+    "1 is null xor 2 is not null",
+    "1 <> 2 and 2 <= 3 and 3 < 4 and 1 >= 4",
+    "\"a\" = 2 or false",
+    "null = 1",
+  ];
+  let value_sets = [
+    vec![],
+    vec![Value::from(1), Value::from(2), Value::from(3), Value::from(4)],
+    vec![Value::from(5), Value::Null, Value::from(0), Value::from(4)],
+    vec![Value::Bool(true), Value::Str("a".to_string()), Value::Bool(false), Value::Null],
+  ];
+  for input in inputs {
+    let node = Parser::new(input).parse().unwrap();
+    let evaluator = evaluator::build_evaluator(&node).unwrap();
+    for value_set in &value_sets {
+      let values = value_set.iter().enumerate().map(|(i, value)| ((i + 1) as IndexKey, value.clone())).collect();
+      assert_eq!(evaluator(&values), interpreter::eval(&node, &values), "{}", input);
+      assert_eq!(evaluator(&values), Program::compile(&node).unwrap().run(&values), "{}", input);
+      assert_eq!(evaluator(&values), compile(&node).unwrap().eval(&values), "{}", input);
+    }
+  }
+}
+
+#[test]
+fn test_0031() {
+  let node = Parser::new("if(1 > 2; 1; 2)").parse().unwrap();
+  let evaluator = std::sync::Arc::new(evaluator::build_evaluator(&node).unwrap());
+  let handles = (0..2)
+    .map(|i| {
+      let evaluator = evaluator.clone();
+      std::thread::spawn(move || {
+        let mut values = HashMap::new();
+        values.insert(1, Value::Number(Decimal::new(i, 0)));
+        values.insert(2, 1.into());
+        evaluator(&values)
+      })
+    })
+    .collect::<Vec<_>>();
+  let results = handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<Value>>();
+  assert_eq!(vec![Value::from(1), Value::from(1)], results);
+}
+
+#[test]
+fn test_0032() {
+  let config = EvaluatorConfig {
+    optimize: true,
+    ..EvaluatorConfig::default()
+  };
+  let inputs = [
+    "if(null = null; 1; 2)",
+    "if(1.0 > 2.0 or 1 > 2; 1; 2)",
+    "1.0 + 2.0 * 3.0 - 1",
+    "false and 1 = 2 or null <> null",
+  ];
+  let mut values = HashMap::new();
+  values.insert(1, 3.into());
+  values.insert(2, 2.into());
+  for input in inputs {
+    let node = Parser::new(input).parse().unwrap();
+    let expected = evaluator::build_evaluator(&node).unwrap()(&values);
+    assert_eq!(expected, evaluator::build_evaluator_with_config(&node, &config).unwrap()(&values), "{}", input);
+  }
+}
+
+#[test]
+fn test_0033() {
+  let p1 = Value::from(1);
+  let p2 = Value::from(2);
+  let p3 = Value::Str("a".to_string());
+  eq("coalesce(1; 2)", &[&Value::Null, &p2], &p2);
+  eq("coalesce(1; 2)", &[&p1, &p2], &p1);
+  eq("coalesce(1; 2; 3)", &[&Value::Null, &Value::Null, &p3], &p3);
+  eq("coalesce(1; 2)", &[&Value::Null, &Value::Null], &Value::Null);
+  eq("coalesce(3; 1.5)", &[], &Value::Number(Decimal::new(15, 1)));
+  eq("coalesce(1; 2) + 2", &[&Value::Null, &p2], &Value::from(4));
+  eq("coalesce(1; 2) = 2", &[&Value::Null, &p2], &Value::Bool(true));
+}
+
+#[test]
+fn test_0034() {
+  let p1 = Value::from(1);
+  let p2 = Value::Number(Decimal::new(25, 1));
+  let p3 = Value::from(-3);
+  eq("min(1; 2)", &[&p1, &p2], &p1);
+  eq("max(1; 2)", &[&p1, &p2], &p2);
+  eq("min(1; 2; 3)", &[&p1, &p2, &p3], &p3);
+  eq("max(1; 2; 3)", &[&p1, &p2, &p3], &p2);
+  eq("max(1)", &[&p1], &p1);
+  eq("min(1; 2; 3)", &[&p1, &Value::Null, &p3], &Value::Null);
+  eq("max(1; 2)", &[&p1, &Value::Str("a".to_string())], &Value::Null);
+  eq("if(1 > max(2; 3); 1; 2)", &[&p1, &p2, &p3], &p2);
+  let config = EvaluatorConfig {
+    skip_nulls_in_min_max: true,
+    ..EvaluatorConfig::default()
+  };
+  let node = Parser::new("min(1; 2; 3)").parse().unwrap();
+  let evaluator = evaluator::build_evaluator_with_config(&node, &config).unwrap();
+  let mut values = HashMap::new();
+  values.insert(1, p1.clone());
+  values.insert(2, Value::Null);
+  values.insert(3, p2.clone());
+  assert_eq!(p1, evaluator(&values));
+  values.insert(1, Value::Null);
+  values.insert(3, Value::Null);
+  assert_eq!(Value::Null, evaluator(&values));
+}
+
+#[test]
+fn test_0035() {
+  let p1 = Value::from(-3);
+  let p2 = Value::Number(Decimal::new(1115, 3));
+  let p3 = Value::Number(Decimal::new(1125, 3));
+  let p4 = Value::Number(Decimal::new(1126, 3));
+  eq("abs(1)", &[&p1], &Value::from(3));
+  eq("abs(-3.0)", &[], &Value::from(3));
+  eq("abs(- 1) = 3.0", &[&p1], &Value::Bool(true));
+  eq("abs(1)", &[&Value::Null], &Value::Null);
+  // midpoint values are rounded to the nearest even number (banker's rounding)
+  eq("round(2; 2)", &[&p1, &p2], &Value::Number(Decimal::new(112, 2)));
+  eq("round(3; 2)", &[&p1, &p2, &p3], &Value::Number(Decimal::new(112, 2)));
+  eq("round(4; 2)", &[&p1, &p2, &p3, &p4], &Value::Number(Decimal::new(113, 2)));
+  eq("round(1.115; 2.0)", &[], &Value::Number(Decimal::new(112, 2)));
+  eq("round(2; 0)", &[&p1, &p2], &Value::from(1));
+  eq("round(1; 2)", &[&Value::Str("a".to_string())], &Value::Null);
+  for input in ["round(1; 2.5)", "round(1; -2.0)", "round(1; 2.0 + 1.0)", "round(1; null)"] {
+    let node = Parser::new(input).parse().unwrap();
+    assert_eq!(
+      "scale of 'round' must be a non-negative integer literal",
+      evaluator::build_evaluator(&node).err().unwrap().to_string(),
+      "{}",
+      input
+    );
+  }
+}
+
+#[test]
+fn test_0036() {
+  let sql = EvaluatorConfig {
+    null_equals_null: false,
+    ..EvaluatorConfig::default()
+  };
+  let eval = |input: &str, config: &EvaluatorConfig| eval_with_config(input, &[], config);
+  assert_eq!(Value::Bool(true), eval("null = null", &EvaluatorConfig::default()));
+  assert_eq!(Value::Bool(false), eval("null <> null", &EvaluatorConfig::default()));
+  assert_eq!(Value::Bool(true), eval("null in (1.0; null)", &EvaluatorConfig::default()));
+  assert_eq!(Value::Null, eval("null = null", &sql));
+  assert_eq!(Value::Null, eval("null <> null", &sql));
+  assert_eq!(Value::Null, eval("1 = 2", &sql));
+  assert_eq!(Value::Null, eval("null in (1.0; null)", &sql));
+  assert_eq!(Value::Bool(false), eval("null = 1.0", &sql));
+  assert_eq!(Value::Bool(true), eval("null <> 1.0", &sql));
+  assert_eq!(Value::Null, eval("if(null = null; 1.0; 2.0)", &sql));
+}
+
+#[test]
+fn test_0037() {
+  let p1 = Value::Number(Decimal::new(100, 2));
+  let p2 = Value::Number(Decimal::new(1009, 3));
+  let p3 = Value::Number(Decimal::new(1011, 3));
+  let tolerant = EvaluatorConfig {
+    eq_tolerance: Some(Decimal::new(1, 2)),
+    ..EvaluatorConfig::default()
+  };
+  let eval = eval_with_config;
+  assert_eq!(Value::Bool(false), eval("1 = 2", &[&p1, &p2], &EvaluatorConfig::default()));
+  assert_eq!(Value::Bool(true), eval("1 <> 2", &[&p1, &p2], &EvaluatorConfig::default()));
+  assert_eq!(Value::Bool(true), eval("1 = 2", &[&p1, &p2], &tolerant));
+  assert_eq!(Value::Bool(true), eval("2 = 1", &[&p1, &p2], &tolerant));
+  assert_eq!(Value::Bool(false), eval("1 <> 2", &[&p1, &p2], &tolerant));
+  assert_eq!(Value::Bool(false), eval("1 = 3", &[&p1, &p2, &p3], &tolerant));
+  assert_eq!(Value::Bool(true), eval("1 <> 3", &[&p1, &p2, &p3], &tolerant));
+  assert_eq!(Value::Bool(true), eval("1 in (3; 2)", &[&p1, &p2, &p3], &tolerant));
+  assert_eq!(Value::Bool(false), eval("1 = null", &[&p1], &tolerant));
+}
+
+/// Builds right-leaning chain of alternating `and` and `or` operators with specified number of comparisons.
+fn chain(length: usize) -> AstNode {
+  let mut node = AstNode::Eq(Box::new(AstNode::Number(1)), Box::new(AstNode::Number(2)));
+  for i in 1..length {
+    let comparison = AstNode::Eq(Box::new(AstNode::Number(1)), Box::new(AstNode::Number(2)));
+    node = if i % 2 == 0 {
+      AstNode::And(Box::new(comparison), Box::new(node))
+    } else {
+      AstNode::Or(Box::new(comparison), Box::new(node))
+    };
+  }
+  node
+}
+
+/// Drops the chain without recursion, dropping [AstNode] recurses on children.
+fn drop_chain(mut node: AstNode) {
+  while let AstNode::And(_, rhs) | AstNode::Or(_, rhs) = node {
+    node = *rhs;
+  }
+}
+
+#[test]
+fn test_0038() {
+  let node = chain(20_000);
+  assert_eq!(20_001, node.depth());
+  let message = "expression depth 20001 exceeds the maximum depth 1024";
+  assert_eq!(message, evaluator::build_evaluator(&node).err().unwrap().to_string());
+  assert_eq!(message, build_evaluator_strict(&node).err().unwrap().to_string());
+  assert_eq!(message, compile(&node).unwrap_err().to_string());
+  assert_eq!(message, build_slot_evaluator(&node, &EvaluatorConfig::default()).err().unwrap().to_string());
+  let program = Program::compile(&node).unwrap();
+  let mut values = HashMap::new();
+  values.insert(1, 1.into());
+  values.insert(2, 1.into());
+  assert_eq!(Value::Bool(true), program.run(&values));
+  drop_chain(node);
+  let node = chain(DEFAULT_MAX_EVALUATOR_DEPTH - 1);
+  assert_eq!(DEFAULT_MAX_EVALUATOR_DEPTH, node.depth());
+  assert_eq!(Value::Bool(true), evaluator::build_evaluator(&node).unwrap()(&values));
+  drop_chain(node);
+}
+
+#[test]
+fn test_0039() {
+  let double: Function = Arc::new(|arguments: &[Value]| match arguments {
+    [Value::Number(value)] => Value::Number(*value * Decimal::new(2, 0)),
+    _ => Value::Null,
+  });
+  let mut functions = HashMap::new();
+  functions.insert("double".to_string(), double);
+  let config = EvaluatorConfig::default().with_functions(functions.clone());
+  let mut values = HashMap::new();
+  values.insert(1, 21.into());
+  let eval = |input: &str| evaluator::build_evaluator_with_config(&Parser::new(input).parse().unwrap(), &config).unwrap()(&values);
+  assert_eq!(Value::from(42), eval("double(1)"));
+  assert_eq!(Value::Bool(true), eval("double(1) + 1 = 63.0"));
+  assert_eq!(Value::Null, eval("double(1; 1)"));
+  assert_eq!(Value::Null, eval("triple(1)"));
+  let node = Parser::new("double(1) > 40.0").parse().unwrap();
+  assert_eq!(
+    Ok(Value::Bool(true)),
+    build_evaluator_strict_with_functions(&node, &Functions::from(functions)).unwrap()(&values)
+  );
+  assert_eq!("unknown function 'double'", build_evaluator_strict(&node).err().unwrap().to_string());
+}
+
+#[test]
+fn test_0040() {
+  let p1 = Value::from(40);
+  let p2 = Value::Null;
+  let p3 = Value::from(80);
+  eq("sum()", &[&p1, &p2, &p3], &Value::from(120));
+  eq("avg()", &[&p1, &p2, &p3], &Value::from(60));
+  eq("count()", &[&p1, &p2, &p3], &Value::from(2));
+  eq("if(sum() > 100.0; 1; 2)", &[&p1, &p2, &p3], &p1);
+  eq("if(avg() > 100.0; 1; 2)", &[&p1, &p2, &p3], &p2);
+  eq("sum()", &[], &Value::Number(Decimal::ZERO));
+  eq("avg()", &[], &Value::Null);
+  eq("count()", &[], &Value::Number(Decimal::ZERO));
+  eq("avg()", &[&p2], &Value::Null);
+  let node = Parser::new("sum() / count()").parse().unwrap();
+  let values = [(1, p1), (2, p2), (3, Value::Str("a".to_string()))].into_iter().collect();
+  assert_eq!(Ok(Value::from(40)), build_evaluator_strict(&node).unwrap()(&values));
+}
+
+#[test]
+fn test_0041() {
+  let evaluator = evaluator::build_evaluator(&Parser::new("1 + 2").parse().unwrap()).unwrap();
+  let rows = (0..6)
+    .map(|i| [(1, Value::Number(Decimal::new(i, 0))), (2, Value::from(10))].into_iter().collect())
+    .collect::<Vec<IndexedValues>>();
+  let mut out = vec![Value::Null; 3];
+  let (pointer, capacity) = (out.as_ptr(), out.capacity());
+  let mut results = vec![];
+  for chunk in rows.chunks(3) {
+    evaluate_into(&evaluator, chunk, &mut out);
+    assert_eq!(pointer, out.as_ptr());
+    assert_eq!(capacity, out.capacity());
+    results.extend(out.iter().cloned());
+  }
+  let expected = (10..16).map(|i| Value::Number(Decimal::new(i, 0))).collect::<Vec<Value>>();
+  assert_eq!(expected, results);
+}
+
+#[test]
+#[should_panic(expected = "number of rows must match the length of the output buffer")]
+fn test_0042() {
+  let evaluator = evaluator::build_evaluator(&Parser::new("1").parse().unwrap()).unwrap();
+  evaluate_into(&evaluator, &[IndexedValues::new()], &mut []);
+}
+
+#[test]
+fn test_0043() {
+  let p1 = Value::Str("apple".to_string());
+  let p2 = Value::Str("banana".to_string());
+  let p3 = Value::from(1);
+  eq("1 < 2", &[&p1, &p2, &p3], &Value::Bool(true));
+  eq("1 > 2", &[&p1, &p2, &p3], &Value::Bool(false));
+  eq("1 >= \"apple\"", &[&p1, &p2, &p3], &Value::Bool(true));
+  eq("2 <= \"b\"", &[&p1, &p2, &p3], &Value::Bool(false));
+  eq("\"Z\" < \"a\"", &[], &Value::Bool(true));
+  eq("1 > 3", &[&p1, &p2, &p3], &Value::Null);
+  eq("3 < 2", &[&p1, &p2, &p3], &Value::Null);
+  eq("if(1 < 3; 1; 2)", &[&p1, &p2, &p3], &Value::Null);
+}
+
+#[test]
+fn test_0044() {
+  let i1 = Value::Int(2);
+  let i2 = Value::Int(3);
+  let n1 = Value::Number(Decimal::new(200, 2));
+  let n2 = Value::Number(Decimal::new(25, 1));
+  let values = [&i1, &i2, &n1, &n2];
+  eq("1 = 1", &values, &Value::Bool(true));
+  eq("1 = 2", &values, &Value::Bool(false));
+  eq("1 <> 2", &values, &Value::Bool(true));
+  eq("1 < 2", &values, &Value::Bool(true));
+  eq("2 >= 1", &values, &Value::Bool(true));
+  eq("1 = 3", &values, &Value::Bool(true));
+  eq("3 = 1", &values, &Value::Bool(true));
+  eq("1 = 2.0", &values, &Value::Bool(true));
+  eq("1 < 4", &values, &Value::Bool(true));
+  eq("2 > 4", &values, &Value::Bool(true));
+  eq("4 <= 1", &values, &Value::Bool(false));
+  eq("2 in (3; 4; 3.0)", &values, &Value::Bool(true));
+  eq("1 = null", &values, &Value::Bool(false));
+  eq("1 > null", &values, &Value::Null);
+  eq("1 < \"a\"", &values, &Value::Null);
+}
+
+#[test]
+#[cfg(feature = "chrono")]
+fn test_0045() {
+  let d1 = Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+  let d2 = Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+  let n1 = Value::from(20240131);
+  let values = [&d1, &d2, &n1];
+  eq("1 < 2", &values, &Value::Bool(true));
+  eq("1 >= 2", &values, &Value::Bool(false));
+  eq("1 = @2024-01-31", &values, &Value::Bool(true));
+  eq("2 <> @2024-02-01", &values, &Value::Bool(false));
+  eq("@2024-02-01 > @2023-12-31", &[], &Value::Bool(true));
+  eq("1 between @2024-01-01 and 2", &values, &Value::Null);
+  eq("1 < 3", &values, &Value::Null);
+  eq("1 = 3", &values, &Value::Null);
+  eq("1 = null", &values, &Value::Bool(false));
+}
+
+#[test]
+fn test_0046() {
+  let i1 = Value::Int(-2);
+  let i2 = Value::Int(3);
+  let n1 = Value::Number(Decimal::new(25, 1));
+  let values = [&i1, &i2, &n1];
+  let n = |value: i64, scale: u32| Value::Number(Decimal::new(value, scale));
+  eq("1 + 2", &values, &n(1, 0));
+  eq("2 - 3", &values, &n(5, 1));
+  eq("1 * 3", &values, &n(-50, 1));
+  eq("2 / 1", &values, &n(-15, 1));
+  eq("abs(1)", &values, &n(2, 0));
+  eq("- 1", &values, &n(2, 0));
+  eq("round(2; 0)", &values, &n(3, 0));
+  eq("3 between 1 and 2", &values, &Value::Bool(true));
+  eq("2 between 1 and 3", &values, &Value::Bool(false));
+  eq("min(1; 2; 3)", &values, &n(-2, 0));
+  eq("max(1; 2; 3)", &values, &n(3, 0));
+  eq("sum()", &values, &n(35, 1));
+  eq("avg()", &values, &Value::Number(Decimal::new(35, 1) / Decimal::new(3, 0)));
+  eq("count()", &values, &n(3, 0));
+}
+
+#[test]
+fn test_0047() {
+  let t = Value::Bool(true);
+  let f = Value::Bool(false);
+  let values = [&Value::Str("a".to_string()), &Value::from(1), &Value::from(2)];
+  let kleene = EvaluatorConfig {
+    three_valued_logic: true,
+    ..EvaluatorConfig::default()
+  };
+  assert_eq!(Value::Null, eval_with_config("1 > 2 and 2 > 3", &values, &EvaluatorConfig::default()));
+  assert_eq!(f, eval_with_config("1 > 2 and 2 > 3", &values, &kleene));
+  assert_eq!(Value::Null, eval_with_config("1 > 2 and 3 > 2", &values, &kleene));
+  assert_eq!(Value::Null, eval_with_config("1 > 2 or 3 > 2", &values, &EvaluatorConfig::default()));
+  assert_eq!(t, eval_with_config("1 > 2 or 3 > 2", &values, &kleene));
+  assert_eq!(Value::Null, eval_with_config("1 > 2 or 2 > 3", &values, &kleene));
+  assert_eq!(f, eval_with_config("(1 > 2 and 2 > 3) or 2 > 3", &values, &kleene));
+  let missing = EvaluatorConfig {
+    missing: Value::from(0),
+    skip_nulls_in_min_max: true,
+    ..EvaluatorConfig::default()
+  };
+  let p1 = Value::from(5);
+  assert_eq!(Value::from(5), eval_with_config("1 + 2", &[&p1], &missing));
+  assert_eq!(Value::from(0), eval_with_config("min(1; 2; 3)", &[&p1, &Value::Null], &missing));
+  assert_eq!(Value::from(5), eval_with_config("max(1; 2)", &[&p1, &Value::Null], &missing));
+  let mut functions: HashMap<String, Function> = HashMap::new();
+  functions.insert("double".to_string(), Arc::new(|args: &[Value]| args[0].clone() + args[0].clone()));
+  let config = EvaluatorConfig::default().with_functions(functions);
+  let node = Parser::new("double(1) + 1").parse().unwrap();
+  let values = IndexedValues::from([(1, p1)]);
+  assert_eq!(Value::from(15), interpreter::eval_with_config(&node, &values, &config));
+  assert_eq!(Value::Null, interpreter::eval(&node, &values));
 }
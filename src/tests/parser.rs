@@ -25,11 +25,32 @@
 //! Parser tests.
 
 use crate::ast::ast_to_tree;
-use crate::parser::Parser;
+use crate::lexer::{Token, TokenKind};
+use crate::parser::{Parser, ParserConfig};
+use crate::{IndexKey, SecelError};
 use difference::Changeset;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+
+/// Returns the beginning of the error message reported when no operand was found.
+fn expected_operand() -> String {
+  let date = if cfg!(feature = "chrono") { ", `Date`" } else { "" };
+  format!(
+    "expected one of `Null`, `Number`, `Decimal`, `Str`, `Minus`, `Abs`, `Avg`, `Coalesce`, `Count`, `Max`, `Min`, `Round`, `Sum`, `Identifier`, `LeftParen`, `If`{}",
+    date
+  )
+}
 
 fn eq(input: &str, expected: &str) {
+  eq_parsed(Parser::new(input), expected);
   let node = Parser::new(input).parse().unwrap();
+  assert_eq!(node, Parser::new(&node.to_source()).parse().unwrap(), "source: {}", node.to_source());
+}
+
+fn eq_parsed(mut parser: Parser, expected: &str) {
+  let node = parser.parse().unwrap();
   let actual = ast_to_tree(&node);
   if actual != expected {
     println!("EXPECTED:\n------------------------------------------------------------{}\n", expected);
@@ -575,3 +596,997 @@ fn test_e_0001() {
 fn test_e_0002() {
   assert!(Parser::new("if(3 <> null;>;2)").parse().is_err());
 }
+
+#[test]
+fn test_0021() {
+  eq(
+    "if(1>1.5;0.25;2)",
+    r#"
+       If
+       ├─ Gt
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Literal
+       │     └─ `1.5`
+       ├─ Literal
+       │  └─ `0.25`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0022() {
+  eq(
+    "if(1>-1;-2.5;2)",
+    r#"
+       If
+       ├─ Gt
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Literal
+       │     └─ `-1`
+       ├─ Literal
+       │  └─ `-2.5`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0023() {
+  eq(
+    "if(true;1;if(false or 1=2;1;2))",
+    r#"
+       If
+       ├─ Bool
+       │  └─ `true`
+       ├─ Number
+       │  └─ `1`
+       └─ If
+          ├─ Or
+          │  ├─ Bool
+          │  │  └─ `false`
+          │  └─ Eq
+          │     ├─ Number
+          │     │  └─ `1`
+          │     └─ Number
+          │        └─ `2`
+          ├─ Number
+          │  └─ `1`
+          └─ Number
+             └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0003() {
+  assert_eq!(
+    "index 65536 exceeds maximum 65535 at position 5",
+    Parser::new("if(1=65536;1;2)").parse().unwrap_err().to_string()
+  );
+}
+
+fn symbols() -> HashMap<String, IndexKey> {
+  HashMap::from([("price".to_string(), 1), ("cost".to_string(), 2)])
+}
+
+#[test]
+fn test_0024() {
+  eq_parsed(
+    Parser::with_symbols("if(price > cost; price; 3)", symbols()),
+    r#"
+       If
+       ├─ Gt
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `3`
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0004() {
+  assert_eq!(
+    "unknown identifier 'tax' at position 11",
+    Parser::with_symbols("if(price > tax; price; cost)", symbols()).parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unknown identifier 'tax' at position 17",
+    Parser::with_symbols("if(price > cost; tax; cost)", symbols()).parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unknown identifier 'price' at position 3",
+    Parser::new("if(price > 1; 1; 2)").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_e_0005() {
+  assert_eq!(
+    "unexpected character ':' at position 6",
+    Parser::new("if(1=2:1;2)").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unexpected character '!' at position 3",
+    Parser::new("if(!1=2;1;2)").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unexpected character '@' at position 7",
+    Parser::new("if(1=2;@;2)").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_0025() {
+  eq(
+    "if(1+2*3>4-5/-6;1;2)",
+    r#"
+       If
+       ├─ Gt
+       │  ├─ Add
+       │  │  ├─ Number
+       │  │  │  └─ `1`
+       │  │  └─ Mul
+       │  │     ├─ Number
+       │  │     │  └─ `2`
+       │  │     └─ Number
+       │  │        └─ `3`
+       │  └─ Sub
+       │     ├─ Number
+       │     │  └─ `4`
+       │     └─ Div
+       │        ├─ Number
+       │        │  └─ `5`
+       │        └─ Literal
+       │           └─ `-6`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0026() {
+  eq(
+    "if((1+2)*3=(4) or (1-2-3>4);1;2)",
+    r#"
+       If
+       ├─ Or
+       │  ├─ Eq
+       │  │  ├─ Mul
+       │  │  │  ├─ Add
+       │  │  │  │  ├─ Number
+       │  │  │  │  │  └─ `1`
+       │  │  │  │  └─ Number
+       │  │  │  │     └─ `2`
+       │  │  │  └─ Number
+       │  │  │     └─ `3`
+       │  │  └─ Number
+       │  │     └─ `4`
+       │  └─ Gt
+       │     ├─ Sub
+       │     │  ├─ Sub
+       │     │  │  ├─ Number
+       │     │  │  │  └─ `1`
+       │     │  │  └─ Number
+       │     │  │     └─ `2`
+       │     │  └─ Number
+       │     │     └─ `3`
+       │     └─ Number
+       │        └─ `4`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0027() {
+  let expected = r#"
+       If
+       ├─ Eq
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#;
+  eq("if(1=2;1;2)  \n\t ", expected);
+  eq("if(1=2;1;2) # trailing comment", expected);
+}
+
+#[test]
+fn test_e_0006() {
+  assert_eq!(
+    "unexpected trailing input at position 11",
+    Parser::new("if(1=2;1;2)junk").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unexpected trailing input at position 12",
+    Parser::new("if(1=2;1;2) )").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unexpected trailing input at position 11",
+    Parser::new("if(1=2;1;2);").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_0028() {
+  eq(
+    "1",
+    r#"
+       Number
+       └─ `1`
+    "#,
+  );
+  eq(
+    "null",
+    r#"
+       Null
+    "#,
+  );
+  eq(
+    "1 > 2",
+    r#"
+       Gt
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+  eq(
+    "(1 + 2) * 3",
+    r#"
+       Mul
+       ├─ Add
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       └─ Number
+          └─ `3`
+    "#,
+  );
+  eq(
+    "true and (1 = 2 or false)",
+    r#"
+       And
+       ├─ Bool
+       │  └─ `true`
+       └─ Or
+          ├─ Eq
+          │  ├─ Number
+          │  │  └─ `1`
+          │  └─ Number
+          │     └─ `2`
+          └─ Bool
+             └─ `false`
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0007() {
+  assert_eq!(
+    format!("{} but encountered Eof at position 3", expected_operand()),
+    Parser::new("1 >").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "expected one of `Eq`, `Nq`, `Gt`, `Ge`, `Lt`, `Le`, `Between`, `In`, `Is` but encountered Number(2) at position 2",
+    Parser::new("1 2").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "unexpected trailing input at position 6",
+    Parser::new("1 > 2 3").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_0029() {
+  let expected = r#"
+       If
+       ├─ Eq
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#;
+  eq("if(1=2;1;2)", expected);
+  eq("if(1=2;(1);(2))", expected);
+  eq("if(1=2;((1));2)", expected);
+}
+
+#[test]
+fn test_0030() {
+  eq(
+    "if(1=2;(if(3=4;5;6));null)",
+    r#"
+       If
+       ├─ Eq
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ If
+       │  ├─ Eq
+       │  │  ├─ Number
+       │  │  │  └─ `3`
+       │  │  └─ Number
+       │  │     └─ `4`
+       │  ├─ Number
+       │  │  └─ `5`
+       │  └─ Number
+       │     └─ `6`
+       └─ Null
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0008() {
+  let (node, errors) = Parser::new("if(1 = ;2;3 4)").parse_all_errors();
+  assert!(node.is_none());
+  assert_eq!(2, errors.len());
+  assert_eq!(format!("{} but encountered Semicolon at position 7", expected_operand()), errors[0].to_string());
+  assert_eq!("expected RightParen but encountered Number(4) at position 12", errors[1].to_string());
+  let (node, errors) = Parser::new("if(1 = :;2;3)").parse_all_errors();
+  assert!(node.is_none());
+  assert_eq!(1, errors.len());
+  assert_eq!("unexpected character ':' at position 7", errors[0].to_string());
+  let (node, errors) = Parser::new("if(1 = 2;1;:").parse_all_errors();
+  assert!(node.is_none());
+  assert_eq!("unexpected character ':' at position 11", errors[0].to_string());
+  let (node, errors) = Parser::new("if(1=2;1;2)").parse_all_errors();
+  assert!(node.is_some());
+  assert!(errors.is_empty());
+  assert_eq!(
+    format!("{} but encountered Semicolon at position 7", expected_operand()),
+    Parser::new("if(1 = ;2;3 4)").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_e_0009() {
+  let error = Parser::new("if(1=2;1;2 3)").parse().unwrap_err();
+  assert_eq!(Some(11), error.position());
+  assert_eq!("expected RightParen but encountered Number(3)", error.message());
+  assert_eq!(None, SecelError::new("no position").position());
+}
+
+/// Trace output shared between the parser and the test.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.0.borrow_mut().write(buf)
+  }
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+#[test]
+fn test_0031() {
+  let output = SharedOutput::default();
+  Parser::new("1 > 2").with_trace(true).with_trace_output(output.clone()).parse().unwrap();
+  let trace = String::from_utf8(output.0.borrow().clone()).unwrap();
+  let mut lines = trace.lines();
+  assert_eq!(Some("statement        0 | 1 > 2"), lines.next());
+  assert_eq!(Some("condition        0 | 1 > 2"), lines.next());
+  assert!(trace.contains("comparison       0 | 1 > 2"));
+  assert!(trace.contains("value            3 |  2"));
+  let output = SharedOutput::default();
+  Parser::new("1 > 2").with_trace(false).with_trace_output(output.clone()).parse().unwrap();
+  assert!(output.0.borrow().is_empty());
+}
+
+#[test]
+fn test_0032() {
+  eq(
+    "if(1 > - 2; 1; - - 2)",
+    r#"
+       If
+       ├─ Gt
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Neg
+       │     └─ Number
+       │        └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Neg
+          └─ Neg
+             └─ Number
+                └─ `2`
+    "#,
+  );
+  eq(
+    "- -2",
+    r#"
+       Neg
+       └─ Literal
+          └─ `-2`
+    "#,
+  );
+  eq_parsed(
+    Parser::with_symbols("-price - -1.5", symbols()),
+    r#"
+       Sub
+       ├─ Neg
+       │  └─ Number
+       │     └─ `1`
+       └─ Literal
+          └─ `-1.5`
+    "#,
+  );
+}
+
+#[test]
+fn test_0033() {
+  eq(
+    "if(1 between 2 and 3 + 4 and true;1;2)",
+    r#"
+       If
+       ├─ And
+       │  ├─ Between
+       │  │  ├─ Number
+       │  │  │  └─ `1`
+       │  │  ├─ Number
+       │  │  │  └─ `2`
+       │  │  └─ Add
+       │  │     ├─ Number
+       │  │     │  └─ `3`
+       │  │     └─ Number
+       │  │        └─ `4`
+       │  └─ Bool
+       │     └─ `true`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0034() {
+  eq(
+    "if(1 in (2;3+4;null);1;null)",
+    r#"
+       If
+       ├─ In
+       │  ├─ Number
+       │  │  └─ `1`
+       │  ├─ Number
+       │  │  └─ `2`
+       │  ├─ Add
+       │  │  ├─ Number
+       │  │  │  └─ `3`
+       │  │  └─ Number
+       │  │     └─ `4`
+       │  └─ Null
+       ├─ Number
+       │  └─ `1`
+       └─ Null
+    "#,
+  );
+}
+
+#[test]
+fn test_0035() {
+  eq(
+    "if(1 is null or 2 + 3 is not null;1;2)",
+    r#"
+       If
+       ├─ Or
+       │  ├─ IsNull
+       │  │  └─ Number
+       │  │     └─ `1`
+       │  └─ IsNotNull
+       │     └─ Add
+       │        ├─ Number
+       │        │  └─ `2`
+       │        └─ Number
+       │           └─ `3`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+  eq(
+    "1 = null",
+    r#"
+       Eq
+       ├─ Number
+       │  └─ `1`
+       └─ Null
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0010() {
+  assert_eq!(
+    "chained comparisons are not allowed; use 'and' at position 6",
+    Parser::new("1 < 2 < 3").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "chained comparisons are not allowed; use 'and' at position 9",
+    Parser::new("if(1 = 2 = 3;1;2)").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "chained comparisons are not allowed; use 'and' at position 11",
+    Parser::new("(1 + 2 > 3 >= 4)").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "chained comparisons are not allowed; use 'and' at position 18",
+    Parser::new("1 between 2 and 3 = true").parse().unwrap_err().to_string()
+  );
+  assert!(Parser::new("1 < 2 and 2 < 3").parse().is_ok());
+}
+
+#[test]
+fn test_0036() {
+  eq(
+    "if(1=2 xor 3=4 or true;1;2)",
+    r#"
+       If
+       ├─ Or
+       │  ├─ Xor
+       │  │  ├─ Eq
+       │  │  │  ├─ Number
+       │  │  │  │  └─ `1`
+       │  │  │  └─ Number
+       │  │  │     └─ `2`
+       │  │  └─ Eq
+       │  │     ├─ Number
+       │  │     │  └─ `3`
+       │  │     └─ Number
+       │  │        └─ `4`
+       │  └─ Bool
+       │     └─ `true`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+  eq(
+    "true xor false and false",
+    r#"
+       Xor
+       ├─ Bool
+       │  └─ `true`
+       └─ And
+          ├─ Bool
+          │  └─ `false`
+          └─ Bool
+             └─ `false`
+    "#,
+  );
+}
+
+#[test]
+fn test_0037() {
+  let expected = r#"
+       If
+       ├─ Eq
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Null
+    "#;
+  eq("if(1=2;1)", expected);
+  eq("if(1=2;1;null)", expected);
+  eq("if(1=2;(1))", expected);
+}
+
+#[test]
+fn test_0038() {
+  let expected = ast_to_tree(&Parser::new("if(1=2;1;if(3=4;3;if(5=6;5;6)))").parse().unwrap());
+  eq("if(1=2;1;elseif 3=4;3;elseif 5=6;5;else 6)", &expected);
+  eq("if(1=2;1;elseif 3=4;3;elseif 5=6;5;6)", &expected);
+  eq("if(1=2; 1; elseif 3=4; 3; if(5=6; 5; 6))", &expected);
+  let expected = ast_to_tree(&Parser::new("if(1=2;1;if(3=4;3;null))").parse().unwrap());
+  eq("if(1=2;1;elseif 3=4;3)", &expected);
+  eq(
+    "if(1=2;1;else 2)",
+    r#"
+       If
+       ├─ Eq
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0039() {
+  eq(
+    "if(if(1=2;1;3) > 4;1;2)",
+    r#"
+       If
+       ├─ Gt
+       │  ├─ If
+       │  │  ├─ Eq
+       │  │  │  ├─ Number
+       │  │  │  │  └─ `1`
+       │  │  │  └─ Number
+       │  │  │     └─ `2`
+       │  │  ├─ Number
+       │  │  │  └─ `1`
+       │  │  └─ Number
+       │  │     └─ `3`
+       │  └─ Number
+       │     └─ `4`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+  eq(
+    "if(true;2;3) + 4 = 5",
+    r#"
+       Eq
+       ├─ Add
+       │  ├─ If
+       │  │  ├─ Bool
+       │  │  │  └─ `true`
+       │  │  ├─ Number
+       │  │  │  └─ `2`
+       │  │  └─ Number
+       │  │     └─ `3`
+       │  └─ Number
+       │     └─ `4`
+       └─ Number
+          └─ `5`
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0011() {
+  let input = format!("{}1=2{}", "(".repeat(10_000), ")".repeat(10_000));
+  assert_eq!("maximum nesting depth exceeded", Parser::new(&input).parse().unwrap_err().to_string());
+  let input = format!("if({}1{} > 2;1;2)", "(".repeat(10_000), ")".repeat(10_000));
+  assert_eq!("maximum nesting depth exceeded", Parser::new(&input).parse().unwrap_err().to_string());
+  let input = format!("{}1", "-".repeat(10_000));
+  assert_eq!("maximum nesting depth exceeded", Parser::new(&input).parse().unwrap_err().to_string());
+  let input = format!("{}1=2{}", "(".repeat(10), ")".repeat(10));
+  assert!(Parser::new(&input).parse().is_ok());
+  assert_eq!(
+    "maximum nesting depth exceeded",
+    Parser::new(&input).with_max_depth(5).parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_0040() {
+  let tokens = vec![
+    Token::If,
+    Token::LeftParen,
+    Token::Number(1),
+    Token::Eq,
+    Token::Number(2),
+    Token::Semicolon,
+    Token::Number(1),
+    Token::Semicolon,
+    Token::Number(2),
+    Token::RightParen,
+  ];
+  eq_parsed(
+    Parser::from_tokens(tokens),
+    r#"
+       If
+       ├─ Eq
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Number
+       │  └─ `1`
+       └─ Number
+          └─ `2`
+    "#,
+  );
+  assert_eq!(
+    "expected one of `Eq`, `Nq`, `Gt`, `Ge`, `Lt`, `Le`, `Between`, `In`, `Is` but encountered Number(2) at position 1",
+    Parser::from_tokens(vec![Token::Number(1), Token::Number(2), Token::Eof])
+      .parse()
+      .unwrap_err()
+      .to_string()
+  );
+}
+
+#[test]
+fn test_0041() {
+  eq_parsed(
+    Parser::with_symbols("price > 1 or cost > 2 and price < 3", symbols()),
+    r#"
+       Or
+       ├─ Gt
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `1`
+       └─ And
+          ├─ Gt
+          │  ├─ Number
+          │  │  └─ `2`
+          │  └─ Number
+          │     └─ `2`
+          └─ Lt
+             ├─ Number
+             │  └─ `1`
+             └─ Number
+                └─ `3`
+    "#,
+  );
+  eq_parsed(
+    Parser::with_symbols("price > 1 or cost > 2 and price < 3", symbols()).with_config(ParserConfig::left_to_right()),
+    r#"
+       And
+       ├─ Or
+       │  ├─ Gt
+       │  │  ├─ Number
+       │  │  │  └─ `1`
+       │  │  └─ Number
+       │  │     └─ `1`
+       │  └─ Gt
+       │     ├─ Number
+       │     │  └─ `2`
+       │     └─ Number
+       │        └─ `2`
+       └─ Lt
+          ├─ Number
+          │  └─ `1`
+          └─ Number
+             └─ `3`
+    "#,
+  );
+  let config = ParserConfig {
+    and_precedence: 1,
+    or_precedence: 2,
+    xor_precedence: 3,
+  };
+  eq_parsed(
+    Parser::new("true and false or true xor false").with_config(config),
+    r#"
+       And
+       ├─ Bool
+       │  └─ `true`
+       └─ Or
+          ├─ Bool
+          │  └─ `false`
+          └─ Xor
+             ├─ Bool
+             │  └─ `true`
+             └─ Bool
+                └─ `false`
+    "#,
+  );
+}
+
+#[test]
+fn test_0042() {
+  eq(
+    "coalesce(1; 2 + 3; 2.5) > 3",
+    r#"
+       Gt
+       ├─ Coalesce
+       │  ├─ Number
+       │  │  └─ `1`
+       │  ├─ Add
+       │  │  ├─ Number
+       │  │  │  └─ `2`
+       │  │  └─ Number
+       │  │     └─ `3`
+       │  └─ Literal
+       │     └─ `2.5`
+       └─ Number
+          └─ `3`
+    "#,
+  );
+  eq(
+    "if(1 > 2; coalesce(3); null)",
+    r#"
+       If
+       ├─ Gt
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Number
+       │     └─ `2`
+       ├─ Coalesce
+       │  └─ Number
+       │     └─ `3`
+       └─ Null
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0012() {
+  assert_eq!(
+    format!("{} but encountered RightParen at position 4", expected_operand()),
+    Parser::new("min() > 1").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_0043() {
+  eq(
+    "round(1; 2) > abs(- 2)",
+    r#"
+       Gt
+       ├─ Round
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Literal
+       │     └─ `2`
+       └─ Abs
+          └─ Neg
+             └─ Number
+                └─ `2`
+    "#,
+  );
+}
+
+#[test]
+fn test_0044() {
+  eq(
+    "fx(1; 2.5) > tax(now())",
+    r#"
+       Gt
+       ├─ Call `fx`
+       │  ├─ Number
+       │  │  └─ `1`
+       │  └─ Literal
+       │     └─ `2.5`
+       └─ Call `tax`
+          └─ Call `now`
+    "#,
+  );
+}
+
+#[test]
+fn test_0045() {
+  eq(
+    "sum() > avg() * count()",
+    r#"
+       Gt
+       ├─ Sum
+       └─ Mul
+          ├─ Avg
+          └─ Count
+    "#,
+  );
+}
+
+#[test]
+fn test_e_0013() {
+  assert_eq!(
+    "expected RightParen but encountered Number(1) at position 4",
+    Parser::new("sum(1)").parse().unwrap_err().to_string()
+  );
+}
+
+#[test]
+fn test_e_0014() {
+  match Parser::new("if(1=2;1;2 3)").parse().unwrap_err() {
+    SecelError::Parse { expected, found, position } => {
+      assert_eq!(vec![TokenKind::RightParen], expected);
+      assert_eq!(Token::Number(3), found);
+      assert_eq!(11, position);
+    }
+    other => panic!("unexpected error {:?}", other),
+  }
+  assert!(matches!(Parser::new("1 = $").parse().unwrap_err(), SecelError::Lex { position: Some(4), .. }));
+  assert!(matches!(Parser::new("1 = 01").parse().unwrap_err(), SecelError::Lex { position: Some(4), .. }));
+  assert!(matches!(
+    Parser::new("1 = 2 3").parse().unwrap_err(),
+    SecelError::Syntax { position: Some(6), .. }
+  ));
+  assert!(matches!(Parser::new("1 = tax").parse().unwrap_err(), SecelError::Syntax { .. }));
+  assert!(matches!(SecelError::new("division by zero"), SecelError::Eval { position: None, .. }));
+  assert_eq!("division by zero", SecelError::new("division by zero").to_string());
+}
+
+#[test]
+fn test_e_0015() {
+  let source = "if(1=2;\n  1;\n  2 3)";
+  let error = Parser::new(source).parse().unwrap_err();
+  assert_eq!(Some((3, 5)), error.line_column(source));
+  assert_eq!(
+    "error: expected RightParen but encountered Number(3)\n --> line 3, column 5\n  |\n3 |   2 3)\n  |     ^",
+    error.render(source)
+  );
+  let source = "1 =";
+  assert_eq!(
+    format!(
+      "error: {} but encountered Eof\n --> line 1, column 4\n  |\n1 | 1 =\n  |    ^",
+      expected_operand()
+    ),
+    Parser::new(source).parse().unwrap_err().render(source)
+  );
+  let source = "1 > 01";
+  assert_eq!(
+    "error: leading zero in number literal '01'\n --> line 1, column 5\n  |\n1 | 1 > 01\n  |     ^",
+    Parser::new(source).parse().unwrap_err().render(source)
+  );
+  let source = "1 > 99999";
+  assert_eq!(
+    "error: index 99999 exceeds maximum 65535\n --> line 1, column 5\n  |\n1 | 1 > 99999\n  |     ^",
+    Parser::new(source).parse().unwrap_err().render(source)
+  );
+  assert_eq!("error: division by zero", SecelError::new("division by zero").render(source));
+}
+
+#[test]
+fn test_e_0016() {
+  assert_eq!(
+    format!("{}, `True`, `False` but encountered Semicolon at position 3", expected_operand()),
+    Parser::new("if(;1;2)").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    format!("{} but encountered Semicolon at position 7", expected_operand()),
+    Parser::new("if(1=2;;2)").parse().unwrap_err().to_string()
+  );
+  assert_eq!(
+    "expected one of `Decimal`, `Number`, `Identifier`, `Minus` but encountered Null at position 5",
+    Parser::new("1 = -null").parse().unwrap_err().to_string()
+  );
+  match Parser::new("(1=2").parse().unwrap_err() {
+    SecelError::Parse { expected, found, position } => {
+      assert_eq!(vec![TokenKind::RightParen], expected);
+      assert_eq!(Token::Eof, found);
+      assert_eq!(4, position);
+    }
+    other => panic!("unexpected error {:?}", other),
+  }
+}
+
+#[test]
+fn test_e_0017() {
+  let config = ParserConfig {
+    and_precedence: u8::MAX,
+    ..ParserConfig::default()
+  };
+  assert_eq!(
+    "precedence of logical operator is out of range at position 5",
+    Parser::new("true and false").with_config(config).parse().unwrap_err().to_string()
+  );
+}
@@ -0,0 +1,107 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Token sources the parser reads tokens from.
+
+use crate::errors::Result;
+use crate::lexer::{Span, Token};
+use std::io::Write;
+
+/// Source of tokens consumed by the parser.
+pub trait TokenSource {
+  /// Returns the current position.
+  fn get_position(&self) -> usize;
+  /// Sets the current position, used for backtracking.
+  fn set_position(&mut self, position: usize);
+  /// Returns the next token starting from current position,
+  /// together with the span of positions it was read from.
+  fn next_token_spanned(&mut self) -> Result<(Token, Span)>;
+  /// Writes the current position and the remaining input to specified output.
+  fn trace_to(&self, output: &mut dyn Write) -> std::io::Result<()>;
+  /// Returns the next token starting from current position.
+  fn next_token(&mut self) -> Result<Token> {
+    self.next_token_spanned().map(|(token, _)| token)
+  }
+  /// Returns the next token starting from current position without advancing the position.
+  fn peek_token(&mut self) -> Result<Token> {
+    let position = self.get_position();
+    let token = self.next_token();
+    self.set_position(position);
+    token
+  }
+}
+
+/// Cursor over already tokenized input, positions are indexes of tokens.
+pub struct TokenCursor {
+  /// Tokens to be read.
+  tokens: Vec<Token>,
+  /// Index of the next token to be read.
+  position: usize,
+}
+
+impl TokenCursor {
+  /// Creates a cursor over specified tokens, reading past the last token returns [Token::Eof].
+  pub fn new(tokens: Vec<Token>) -> Self {
+    Self { tokens, position: 0 }
+  }
+}
+
+impl TokenSource for TokenCursor {
+  fn get_position(&self) -> usize {
+    self.position
+  }
+  fn set_position(&mut self, position: usize) {
+    self.position = position.min(self.tokens.len());
+  }
+  fn next_token_spanned(&mut self) -> Result<(Token, Span)> {
+    let start = self.position;
+    match self.tokens.get(start) {
+      Some(token) => {
+        self.position += 1;
+        Ok((token.clone(), Span { start, end: self.position }))
+      }
+      None => Ok((Token::Eof, Span { start, end: start })),
+    }
+  }
+  fn trace_to(&self, output: &mut dyn Write) -> std::io::Result<()> {
+    write!(output, "{:>4} | {:?}", self.position, &self.tokens[self.position..])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_token_cursor() {
+    let mut cursor = TokenCursor::new(vec![Token::Number(1), Token::Gt]);
+    assert_eq!(Token::Number(1), cursor.peek_token().unwrap());
+    assert_eq!(Token::Number(1), cursor.next_token().unwrap());
+    assert_eq!((Token::Gt, Span { start: 1, end: 2 }), cursor.next_token_spanned().unwrap());
+    assert_eq!(Token::Eof, cursor.next_token().unwrap());
+    assert_eq!(Token::Eof, cursor.next_token().unwrap());
+    cursor.set_position(1);
+    assert_eq!(Token::Gt, cursor.next_token().unwrap());
+  }
+}
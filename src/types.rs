@@ -0,0 +1,277 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Static type checking implementation.
+//!
+//! Type checker infers the type of the expression without evaluating it, reporting
+//! operands of types that do not make sense for the operator, like comparing the result
+//! of `and` with a number or using a number as the condition of `if` expression.
+
+use crate::ast::AstNode;
+use crate::errors::{Result, SecelError};
+use std::fmt;
+
+/// Statically inferred type of the expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprType {
+  /// Expression evaluates to boolean (or `null`).
+  Bool,
+  /// Expression evaluates to number (or `null`).
+  Number,
+  /// Expression always evaluates to `null`.
+  Null,
+  /// Type of the expression is not known before evaluation (values of indexes, strings, function calls).
+  Any,
+}
+
+impl fmt::Display for ExprType {
+  /// Implements [Display](std::fmt::Display) for [ExprType].
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ExprType::Bool => write!(f, "bool"),
+      ExprType::Number => write!(f, "number"),
+      ExprType::Null => write!(f, "null"),
+      ExprType::Any => write!(f, "any"),
+    }
+  }
+}
+
+impl ExprType {
+  /// Returns the type of expression evaluating to the value of either expression,
+  /// `Null` is absorbed by the other type, different types give `Any`.
+  fn join(self, other: ExprType) -> ExprType {
+    match (self, other) {
+      (ExprType::Null, other) | (other, ExprType::Null) => other,
+      (lht, rht) if lht == rht => lht,
+      _ => ExprType::Any,
+    }
+  }
+}
+
+/// Infers the type of the expression, reports an error for operands of unexpected types.
+pub(crate) fn type_check(node: &AstNode) -> Result<ExprType> {
+  match node {
+    AstNode::Abs(mhs) => expect_number(mhs, "abs"),
+    AstNode::Add(lhs, rhs) => expect_numbers(lhs, rhs, "+"),
+    AstNode::And(lhs, rhs) => expect_bools(lhs, rhs, "and"),
+    AstNode::Avg | AstNode::Count | AstNode::Sum => Ok(ExprType::Number),
+    AstNode::Between(lhs, mhs, rhs) => {
+      compare(lhs, mhs, "between")?;
+      compare(lhs, rhs, "between")
+    }
+    AstNode::Bool(_) => Ok(ExprType::Bool),
+    AstNode::Call(_, mhs) => {
+      for node in mhs {
+        type_check(node)?;
+      }
+      Ok(ExprType::Any)
+    }
+    AstNode::Coalesce(mhs) => {
+      let mut result = ExprType::Null;
+      for node in mhs {
+        result = result.join(type_check(node)?);
+      }
+      Ok(result)
+    }
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(_) => Ok(ExprType::Any),
+    AstNode::Div(lhs, rhs) => expect_numbers(lhs, rhs, "/"),
+    AstNode::Eq(lhs, rhs) => compare(lhs, rhs, "="),
+    AstNode::Ge(lhs, rhs) => compare(lhs, rhs, ">="),
+    AstNode::Gt(lhs, rhs) => compare(lhs, rhs, ">"),
+    AstNode::If(lhs, mhs, rhs) => {
+      expect_bool(lhs, "if")?;
+      Ok(type_check(mhs)?.join(type_check(rhs)?))
+    }
+    AstNode::In(lhs, rhs) => {
+      for node in rhs {
+        compare(lhs, node, "in")?;
+      }
+      Ok(ExprType::Bool)
+    }
+    AstNode::IsNotNull(mhs) | AstNode::IsNull(mhs) => {
+      type_check(mhs)?;
+      Ok(ExprType::Bool)
+    }
+    AstNode::Le(lhs, rhs) => compare(lhs, rhs, "<="),
+    AstNode::Literal(_) => Ok(ExprType::Number),
+    AstNode::Lt(lhs, rhs) => compare(lhs, rhs, "<"),
+    AstNode::Max(mhs) => expect_all_numbers(mhs, "max"),
+    AstNode::Min(mhs) => expect_all_numbers(mhs, "min"),
+    AstNode::Mul(lhs, rhs) => expect_numbers(lhs, rhs, "*"),
+    AstNode::Neg(mhs) => expect_number(mhs, "-"),
+    AstNode::Null => Ok(ExprType::Null),
+    AstNode::Number(_) => Ok(ExprType::Any),
+    AstNode::Nq(lhs, rhs) => compare(lhs, rhs, "<>"),
+    AstNode::Or(lhs, rhs) => expect_bools(lhs, rhs, "or"),
+    AstNode::Round(lhs, rhs) => {
+      expect_number(rhs, "round")?;
+      expect_number(lhs, "round")
+    }
+    AstNode::StrLiteral(_) => Ok(ExprType::Any),
+    AstNode::Sub(lhs, rhs) => expect_numbers(lhs, rhs, "-"),
+    AstNode::Xor(lhs, rhs) => expect_bools(lhs, rhs, "xor"),
+  }
+}
+
+/// Infers the type of the expression result without checking the operands, values of indexes
+/// are assumed to be numbers, strings, dates and results of function calls are of `Any` type.
+pub(crate) fn result_type(node: &AstNode) -> ExprType {
+  match node {
+    AstNode::And(..)
+    | AstNode::Between(..)
+    | AstNode::Bool(_)
+    | AstNode::Eq(..)
+    | AstNode::Ge(..)
+    | AstNode::Gt(..)
+    | AstNode::In(..)
+    | AstNode::IsNotNull(_)
+    | AstNode::IsNull(_)
+    | AstNode::Le(..)
+    | AstNode::Lt(..)
+    | AstNode::Nq(..)
+    | AstNode::Or(..)
+    | AstNode::Xor(..) => ExprType::Bool,
+    AstNode::Call(..) | AstNode::StrLiteral(_) => ExprType::Any,
+    #[cfg(feature = "chrono")]
+    AstNode::DateLiteral(_) => ExprType::Any,
+    AstNode::Coalesce(mhs) => mhs.iter().fold(ExprType::Null, |result, node| result.join(result_type(node))),
+    AstNode::If(_, lhs, rhs) => result_type(lhs).join(result_type(rhs)),
+    AstNode::Null => ExprType::Null,
+    _ => ExprType::Number,
+  }
+}
+
+/// Checks the operands of comparison operator, comparing boolean expressions is an error.
+fn compare(lhs: &AstNode, rhs: &AstNode, operator: &str) -> Result<ExprType> {
+  match (type_check(lhs)?, type_check(rhs)?) {
+    (lht @ ExprType::Bool, rht) | (lht, rht @ ExprType::Bool) => Err(SecelError::new(&format!("cannot compare {} to {} with '{}'", lht, rht, operator))),
+    _ => Ok(ExprType::Bool),
+  }
+}
+
+/// Checks the operand is a boolean expression, `null` or expression of unknown type.
+fn expect_bool(node: &AstNode, operator: &str) -> Result<ExprType> {
+  match type_check(node)? {
+    ExprType::Number => Err(SecelError::new(&format!("expected bool operand of '{}', found number", operator))),
+    _ => Ok(ExprType::Bool),
+  }
+}
+
+/// Checks both operands of logical operator.
+fn expect_bools(lhs: &AstNode, rhs: &AstNode, operator: &str) -> Result<ExprType> {
+  expect_bool(lhs, operator)?;
+  expect_bool(rhs, operator)
+}
+
+/// Checks the operand is a numeric expression, `null` or expression of unknown type.
+fn expect_number(node: &AstNode, operator: &str) -> Result<ExprType> {
+  match type_check(node)? {
+    ExprType::Bool => Err(SecelError::new(&format!("expected number operand of '{}', found bool", operator))),
+    _ => Ok(ExprType::Number),
+  }
+}
+
+/// Checks all operands of numeric function.
+fn expect_all_numbers(mhs: &[AstNode], operator: &str) -> Result<ExprType> {
+  for node in mhs {
+    expect_number(node, operator)?;
+  }
+  Ok(ExprType::Number)
+}
+
+/// Checks both operands of arithmetic operator.
+fn expect_numbers(lhs: &AstNode, rhs: &AstNode, operator: &str) -> Result<ExprType> {
+  expect_number(lhs, operator)?;
+  expect_number(rhs, operator)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+
+  fn check(input: &str) -> Result<ExprType> {
+    Parser::new(input).parse().unwrap().type_check()
+  }
+
+  fn boxed(node: AstNode) -> Box<AstNode> {
+    Box::new(node)
+  }
+
+  #[test]
+  fn test_well_typed() {
+    assert_eq!(Ok(ExprType::Number), check("if(1 > 2 and 3 = null; 1.5; abs(2))"));
+    assert_eq!(Ok(ExprType::Any), check("if(1 > 2; 1; 2)"));
+    assert_eq!(Ok(ExprType::Number), check("if(1 > 2; 1.5)"));
+    assert_eq!(Ok(ExprType::Bool), check("1 between 2 and 3 or 4 in (5; 6.0) xor 7 is null"));
+    assert_eq!(Ok(ExprType::Number), check("coalesce(null; max(1; 2.0); sum())"));
+    assert_eq!(Ok(ExprType::Null), check("null"));
+    assert_eq!(Ok(ExprType::Any), check("f(1; 2.0)"));
+  }
+
+  #[test]
+  fn test_result_type() {
+    let result_type = |input: &str| Parser::new(input).parse().unwrap().result_type();
+    assert_eq!(ExprType::Bool, result_type("1 > 2"));
+    assert_eq!(ExprType::Bool, result_type("1 is null or 2 in (3; 4)"));
+    assert_eq!(ExprType::Number, result_type("1"));
+    assert_eq!(ExprType::Number, result_type("1 + 2.5 * abs(3)"));
+    assert_eq!(ExprType::Null, result_type("null"));
+    assert_eq!(ExprType::Number, result_type("if(1 > 2; 1; 2.5)"));
+    assert_eq!(ExprType::Number, result_type("if(1 > 2; null; 2)"));
+    assert_eq!(ExprType::Any, result_type(r#"if(1 > 2; 1; "a")"#));
+    assert_eq!(ExprType::Any, result_type("f(1)"));
+  }
+
+  #[test]
+  fn test_mistyped() {
+    let and = || boxed(AstNode::And(boxed(AstNode::Bool(true)), boxed(AstNode::Number(1))));
+    let number = || boxed(AstNode::Literal(rust_decimal::Decimal::ONE));
+    assert_eq!(
+      "cannot compare bool to number with '='",
+      AstNode::Eq(and(), number()).type_check().unwrap_err().to_string()
+    );
+    assert_eq!(
+      "cannot compare number to bool with '<'",
+      AstNode::Lt(number(), and()).type_check().unwrap_err().to_string()
+    );
+    assert_eq!(
+      "expected bool operand of 'if', found number",
+      AstNode::If(number(), number(), number()).type_check().unwrap_err().to_string()
+    );
+    assert_eq!(
+      "expected number operand of '+', found bool",
+      AstNode::Add(number(), and()).type_check().unwrap_err().to_string()
+    );
+    assert_eq!(
+      "expected bool operand of 'or', found number",
+      AstNode::Or(and(), number()).type_check().unwrap_err().to_string()
+    );
+    assert_eq!(
+      "expected number operand of 'max', found bool",
+      AstNode::Max(vec![*number(), *and()]).type_check().unwrap_err().to_string()
+    );
+  }
+}
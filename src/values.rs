@@ -24,11 +24,23 @@
 
 //!
 
+use crate::errors::SecelError;
+#[cfg(feature = "chrono")]
+use chrono::NaiveDate;
 use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::de::{self, MapAccess, Visitor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
 use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
 
 /// Value definition.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///
+/// Hashing is consistent with equality, numbers equal regardless of the scale (`1.1` and `1.10`) have equal hashes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Value {
   /// Value representing a `NULL`.
   Null,
@@ -36,15 +48,296 @@ pub enum Value {
   Bool(bool),
   /// Value representing a decimal number.
   Number(Decimal),
+  /// Value representing an integer number, promoted to decimal by arithmetic operators and functions
+  /// and when compared with [Value::Number].
+  Int(i64),
+  /// Value representing a string.
+  Str(String),
+  /// Value representing a date, available with `chrono` feature.
+  #[cfg(feature = "chrono")]
+  Date(NaiveDate),
+}
+
+impl Value {
+  /// Returns the lowercase name of the value type (`"null"`, `"bool"`, `"number"`, ...), used in diagnostics.
+  pub fn typename(&self) -> &'static str {
+    match self {
+      Value::Null => "null",
+      Value::Bool(_) => "bool",
+      Value::Number(_) => "number",
+      Value::Int(_) => "int",
+      Value::Str(_) => "str",
+      #[cfg(feature = "chrono")]
+      Value::Date(_) => "date",
+    }
+  }
+
+  /// Returns the decimal representation of numeric values (`Number` and `Int`), `None` for other values.
+  pub(crate) fn as_decimal(&self) -> Option<Decimal> {
+    match self {
+      Value::Number(value) => Some(*value),
+      Value::Int(value) => Some(Decimal::from(*value)),
+      _ => None,
+    }
+  }
+
+  /// Applies arithmetic operation to numeric operands, evaluates to `Null` when any operand
+  /// is not numeric or the operation fails (overflow, division by zero).
+  fn arithmetic(self, rhs: Value, operation: fn(Decimal, Decimal) -> Option<Decimal>) -> Value {
+    match (self.as_decimal(), rhs.as_decimal()) {
+      (Some(lhv), Some(rhv)) => operation(lhv, rhv).map_or(Value::Null, Value::Number),
+      _ => Value::Null,
+    }
+  }
+}
+
+/// Adds numeric values (`Int` is promoted to decimal), `Null` for other operands or on overflow.
+impl Add for Value {
+  type Output = Value;
+
+  fn add(self, rhs: Value) -> Value {
+    self.arithmetic(rhs, Decimal::checked_add)
+  }
+}
+
+/// Subtracts numeric values (`Int` is promoted to decimal), `Null` for other operands or on overflow.
+impl Sub for Value {
+  type Output = Value;
+
+  fn sub(self, rhs: Value) -> Value {
+    self.arithmetic(rhs, Decimal::checked_sub)
+  }
+}
+
+/// Multiplies numeric values (`Int` is promoted to decimal), `Null` for other operands or on overflow.
+impl Mul for Value {
+  type Output = Value;
+
+  fn mul(self, rhs: Value) -> Value {
+    self.arithmetic(rhs, Decimal::checked_mul)
+  }
+}
+
+/// Divides numeric values (`Int` is promoted to decimal), `Null` for other operands or division by zero.
+impl Div for Value {
+  type Output = Value;
+
+  fn div(self, rhs: Value) -> Value {
+    self.arithmetic(rhs, Decimal::checked_div)
+  }
+}
+
+/// Converts an integer into `Number`, use [Value::Int] explicitly for integer values.
+impl From<i64> for Value {
+  fn from(value: i64) -> Self {
+    Value::Number(Decimal::from(value))
+  }
+}
+
+/// Converts an integer into `Number`, use [Value::Int] explicitly for integer values.
+impl From<i32> for Value {
+  fn from(value: i32) -> Self {
+    Value::Number(Decimal::from(value))
+  }
+}
+
+impl From<bool> for Value {
+  fn from(value: bool) -> Self {
+    Value::Bool(value)
+  }
+}
+
+impl From<Decimal> for Value {
+  fn from(value: Decimal) -> Self {
+    Value::Number(value)
+  }
+}
+
+impl From<&str> for Value {
+  fn from(value: &str) -> Self {
+    Value::Str(value.to_string())
+  }
+}
+
+/// Converts `Number` (or `Int` promoted to decimal) into decimal, error for other values.
+impl TryFrom<Value> for Decimal {
+  type Error = SecelError;
+
+  fn try_from(value: Value) -> Result<Self, Self::Error> {
+    value.as_decimal().ok_or_else(|| err_expected("number", &value))
+  }
+}
+
+/// Converts `Bool` into boolean, error for other values.
+impl TryFrom<Value> for bool {
+  type Error = SecelError;
+
+  fn try_from(value: Value) -> Result<Self, Self::Error> {
+    match value {
+      Value::Bool(value) => Ok(value),
+      other => Err(err_expected("bool", &other)),
+    }
+  }
+}
+
+/// Creates an error reporting the value of unexpected type.
+fn err_expected(expected: &str, found: &Value) -> SecelError {
+  SecelError::new(&format!("expected {}, found {}", expected, found.typename()))
+}
+
+/// Parses the value from string, recognized forms are checked in order:
+/// `null` is `Null`, `true` and `false` are `Bool` (keywords are case-sensitive),
+/// any other input must be a decimal number (like `-12.50`) and is parsed into `Number`.
+impl FromStr for Value {
+  type Err = SecelError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "null" => Ok(Value::Null),
+      "true" => Ok(Value::Bool(true)),
+      "false" => Ok(Value::Bool(false)),
+      _ => Decimal::from_str(s)
+        .map(Value::Number)
+        .map_err(|_| SecelError::new(&format!("invalid value '{}', expected null, true, false or decimal number", s))),
+    }
+  }
+}
+
+/// Partial order of values, only values of the same type are ordered:
+/// numbers (and integers) numerically, booleans with `false < true`, strings lexicographically
+/// and dates chronologically. Comparing values of different types, including `Int` with `Number`,
+/// gives `None`. `Null` is not ordered against other values, and is equal only to `Null`,
+/// to stay consistent with [PartialEq].
+impl PartialOrd for Value {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    match (self, other) {
+      (Value::Null, Value::Null) => Some(Ordering::Equal),
+      (Value::Bool(lhv), Value::Bool(rhv)) => Some(lhv.cmp(rhv)),
+      (Value::Number(lhv), Value::Number(rhv)) => Some(lhv.cmp(rhv)),
+      (Value::Int(lhv), Value::Int(rhv)) => Some(lhv.cmp(rhv)),
+      (Value::Str(lhv), Value::Str(rhv)) => Some(lhv.cmp(rhv)),
+      #[cfg(feature = "chrono")]
+      (Value::Date(lhv), Value::Date(rhv)) => Some(lhv.cmp(rhv)),
+      _ => None,
+    }
+  }
+}
+
+/// Serializes `Null` as unit (JSON `null`), `Bool` as boolean, `Int` as integer and `Number`
+/// as decimal string preserving the scale (`"1.10"`), `Str` and `Date` are serialized
+/// as newtype variants (`{"Str":"abc"}`), so they can not be mistaken for numbers.
+#[cfg(feature = "serde")]
+impl Serialize for Value {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match self {
+      Value::Null => serializer.serialize_unit(),
+      Value::Bool(value) => serializer.serialize_bool(*value),
+      Value::Number(value) => serializer.serialize_str(&value.to_string()),
+      Value::Int(value) => serializer.serialize_i64(*value),
+      Value::Str(value) => serializer.serialize_newtype_variant("Value", 4, "Str", value),
+      #[cfg(feature = "chrono")]
+      Value::Date(value) => serializer.serialize_newtype_variant("Value", 5, "Date", &value.to_string()),
+    }
+  }
+}
+
+/// Deserializes values serialized by [Serialize] implementation,
+/// floating point numbers are accepted as `Number` (without the guarantee of preserving the scale).
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Value {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(ValueVisitor)
+  }
+}
+
+/// Visitor building [Value] from deserialized data.
+#[cfg(feature = "serde")]
+struct ValueVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for ValueVisitor {
+  type Value = Value;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("null, boolean, integer, decimal string or single-entry map with 'Str' or 'Date' key")
+  }
+
+  fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+    Ok(Value::Bool(value))
+  }
+
+  fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+    Ok(Value::Int(value))
+  }
+
+  fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+    i64::try_from(value)
+      .map(Value::Int)
+      .map_err(|_| E::custom(format!("integer {} is out of range", value)))
+  }
+
+  fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+    Decimal::from_str(&value.to_string())
+      .map(Value::Number)
+      .map_err(|_| E::custom(format!("invalid number {}", value)))
+  }
+
+  fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+    Decimal::from_str(value)
+      .map(Value::Number)
+      .map_err(|_| E::custom(format!("invalid decimal number '{}'", value)))
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+    let value = match map.next_key::<String>()?.as_deref() {
+      Some("Str") => Value::Str(map.next_value()?),
+      #[cfg(feature = "chrono")]
+      Some("Date") => {
+        let text = map.next_value::<String>()?;
+        Value::Date(chrono::NaiveDate::from_str(&text).map_err(|_| de::Error::custom(format!("invalid date '{}'", text)))?)
+      }
+      Some(other) => return Err(de::Error::unknown_variant(other, &["Str", "Date"])),
+      None => return Err(de::Error::invalid_length(0, &self)),
+    };
+    if map.next_key::<String>()?.is_some() {
+      return Err(de::Error::invalid_length(2, &self));
+    }
+    Ok(value)
+  }
 }
 
 impl fmt::Display for Value {
-  /// Implements [Display](std::fmt::Display) for [Value].
+  /// Implements [Display](std::fmt::Display) for [Value], the default form includes the type
+  /// of the value (`Number: 1.11`), the alternate form (`{:#}`) prints just the raw value (`1.11`)
+  /// and an empty string for `Null`.
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if f.alternate() {
+      return match self {
+        Value::Null => Ok(()),
+        Value::Bool(v) => write!(f, "{}", v),
+        Value::Number(v) => write!(f, "{}", v),
+        Value::Int(v) => write!(f, "{}", v),
+        Value::Str(v) => write!(f, "{}", v),
+        #[cfg(feature = "chrono")]
+        Value::Date(v) => write!(f, "{}", v),
+      };
+    }
     match self {
       Value::Null => write!(f, "Null"),
       Value::Bool(v) => write!(f, "Bool: {}", v),
       Value::Number(v) => write!(f, "Number: {}", v),
+      Value::Int(v) => write!(f, "Int: {}", v),
+      Value::Str(v) => write!(f, "Str: {}", v),
+      #[cfg(feature = "chrono")]
+      Value::Date(v) => write!(f, "Date: {}", v),
     }
   }
 }
@@ -60,6 +353,26 @@ mod tests {
     assert_eq!("Bool: true", format!("{}", Value::Bool(true)));
     assert_eq!("Bool: false", format!("{}", Value::Bool(false)));
     assert_eq!("Number: 1.11", format!("{}", Value::Number(Decimal::new(111, 2))));
+    assert_eq!("Str: abc", format!("{}", Value::Str("abc".to_string())));
+    assert_eq!("Int: -42", format!("{}", Value::Int(-42)));
+  }
+
+  #[test]
+  #[cfg(feature = "chrono")]
+  fn test_display_date() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+    assert_eq!("Date: 2024-01-31", format!("{}", Value::Date(date)));
+    assert_eq!("2024-01-31", format!("{:#}", Value::Date(date)));
+  }
+
+  #[test]
+  fn test_display_alternate() {
+    assert_eq!("", format!("{:#}", Value::Null));
+    assert_eq!("true", format!("{:#}", Value::Bool(true)));
+    assert_eq!("false", format!("{:#}", Value::Bool(false)));
+    assert_eq!("1.11", format!("{:#}", Value::Number(Decimal::new(111, 2))));
+    assert_eq!("abc", format!("{:#}", Value::Str("abc".to_string())));
+    assert_eq!("-42", format!("{:#}", Value::Int(-42)));
   }
 
   #[test]
@@ -69,10 +382,11 @@ mod tests {
     assert_eq!("Bool(false)", format!("{:?}", Value::Bool(false)));
     let n = Decimal::new(111, 2);
     assert_eq!("Number(1.11)", format!("{:?}", Value::Number(n)));
+    assert_eq!(r#"Str("abc")"#, format!("{:?}", Value::Str("abc".to_string())));
+    assert_eq!("Int(42)", format!("{:?}", Value::Int(42)));
   }
 
   #[test]
-  #[allow(clippy::clone_on_copy)]
   fn test_comparison() {
     assert!((Value::Null == Value::Null));
     assert!((Value::Bool(true) == Value::Bool(true)));
@@ -82,5 +396,209 @@ mod tests {
     assert!((Value::Number(n1) == Value::Number(n1)));
     assert!((Value::Number(n1) != Value::Number(n2)));
     assert!((Value::Number(n1).clone() != Value::Number(n2).clone()));
+    assert!((Value::Str("a".to_string()) == Value::Str("a".to_string())));
+    assert!((Value::Str("a".to_string()) != Value::Str("b".to_string())));
+  }
+
+  #[test]
+  fn test_partial_ord() {
+    let n1 = Value::Number(Decimal::new(111, 2));
+    let n2 = Value::Number(Decimal::new(1110, 3));
+    let n3 = Value::Number(Decimal::new(222, 2));
+    assert_eq!(Some(Ordering::Less), n1.partial_cmp(&n3));
+    assert_eq!(Some(Ordering::Equal), n1.partial_cmp(&n2));
+    assert!(n3 > n1);
+    assert_eq!(Some(Ordering::Less), Value::Bool(false).partial_cmp(&Value::Bool(true)));
+    assert_eq!(Some(Ordering::Equal), Value::Bool(true).partial_cmp(&Value::Bool(true)));
+    assert_eq!(Some(Ordering::Greater), Value::Int(3).partial_cmp(&Value::Int(-3)));
+    assert_eq!(Some(Ordering::Less), Value::Str("a".to_string()).partial_cmp(&Value::Str("b".to_string())));
+    assert_eq!(Some(Ordering::Equal), Value::Null.partial_cmp(&Value::Null));
+    assert_eq!(None, Value::Null.partial_cmp(&n1));
+    assert_eq!(None, n1.partial_cmp(&Value::Null));
+    assert_eq!(None, Value::Null.partial_cmp(&Value::Bool(false)));
+    assert_eq!(None, n1.partial_cmp(&Value::Bool(true)));
+    assert_eq!(None, Value::Int(1).partial_cmp(&Value::Number(Decimal::ONE)));
+    assert_eq!(None, Value::Str("1".to_string()).partial_cmp(&n1));
+    let mut values = vec![n3.clone(), n1.clone(), Value::Number(Decimal::ZERO)];
+    values.sort_by(|lhv, rhv| lhv.partial_cmp(rhv).unwrap());
+    assert_eq!(vec![Value::Number(Decimal::ZERO), n1, n3], values);
+  }
+
+  #[test]
+  fn test_from() {
+    assert_eq!(Value::Number(Decimal::new(100, 0)), Value::from(100_i64));
+    assert_eq!(Value::Number(Decimal::new(-7, 0)), Value::from(-7_i32));
+    assert_eq!(Value::Bool(true), Value::from(true));
+    assert_eq!(Value::Bool(false), false.into());
+    assert_eq!(Value::Number(Decimal::new(110, 2)), Decimal::new(110, 2).into());
+    assert_eq!(Value::Str("abc".to_string()), "abc".into());
+    let value: Value = 100.into();
+    assert_eq!(Value::Number(Decimal::new(100, 0)), value);
+  }
+
+  #[test]
+  fn test_typename() {
+    assert_eq!("null", Value::Null.typename());
+    assert_eq!("bool", Value::Bool(true).typename());
+    assert_eq!("number", Value::Number(Decimal::ONE).typename());
+    assert_eq!("int", Value::Int(1).typename());
+    assert_eq!("str", Value::Str("abc".to_string()).typename());
+  }
+
+  #[test]
+  #[cfg(feature = "chrono")]
+  fn test_typename_date() {
+    assert_eq!("date", Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()).typename());
+  }
+
+  #[test]
+  fn test_add() {
+    assert_eq!(
+      Value::Number(Decimal::new(333, 2)),
+      Value::Number(Decimal::new(111, 2)) + Value::Number(Decimal::new(222, 2))
+    );
+    assert_eq!(Value::Number(Decimal::new(311, 2)), Value::Number(Decimal::new(111, 2)) + Value::Int(2));
+    assert_eq!(Value::Null, Value::Number(Decimal::ONE) + Value::Null);
+    assert_eq!(Value::Null, Value::Null + Value::Number(Decimal::ONE));
+    assert_eq!(Value::Null, Value::Bool(true) + Value::Number(Decimal::ONE));
+    assert_eq!(Value::Null, Value::Number(Decimal::MAX) + Value::Number(Decimal::ONE));
+  }
+
+  #[test]
+  fn test_sub() {
+    assert_eq!(
+      Value::Number(Decimal::new(-111, 2)),
+      Value::Number(Decimal::new(111, 2)) - Value::Number(Decimal::new(222, 2))
+    );
+    assert_eq!(Value::Number(Decimal::new(5, 0)), Value::Int(7) - Value::Int(2));
+    assert_eq!(Value::Null, Value::Number(Decimal::ONE) - Value::Null);
+    assert_eq!(Value::Null, Value::Str("1".to_string()) - Value::Number(Decimal::ONE));
+  }
+
+  #[test]
+  fn test_mul() {
+    assert_eq!(
+      Value::Number(Decimal::new(24642, 4)),
+      Value::Number(Decimal::new(111, 2)) * Value::Number(Decimal::new(222, 2))
+    );
+    assert_eq!(Value::Null, Value::Null * Value::Number(Decimal::ONE));
+    assert_eq!(Value::Null, Value::Number(Decimal::ONE) * Value::Bool(false));
+  }
+
+  #[test]
+  fn test_div() {
+    assert_eq!(
+      Value::Number(Decimal::new(5, 1)),
+      Value::Number(Decimal::ONE) / Value::Number(Decimal::new(2, 0))
+    );
+    assert_eq!(Value::Null, Value::Number(Decimal::ONE) / Value::Number(Decimal::ZERO));
+    assert_eq!(Value::Null, Value::Number(Decimal::ONE) / Value::Int(0));
+    assert_eq!(Value::Null, Value::Null / Value::Number(Decimal::ONE));
+    assert_eq!(Value::Null, Value::Number(Decimal::ONE) / Value::Null);
+  }
+
+  #[test]
+  fn test_hash() {
+    use std::collections::HashSet;
+    let values = [
+      Value::Null,
+      Value::Bool(true),
+      Value::Number(Decimal::new(11, 1)),
+      Value::Number(Decimal::new(110, 2)),
+      Value::Number(Decimal::new(12, 1)),
+      Value::Int(11),
+      Value::Str("1.1".to_string()),
+      Value::Null,
+    ];
+    let set = values.iter().cloned().collect::<HashSet<Value>>();
+    assert_eq!(6, set.len());
+    assert!(set.contains(&Value::Number(Decimal::new(1100, 3))));
+    assert!(!set.contains(&Value::Bool(false)));
+  }
+
+  #[test]
+  fn test_try_from_decimal() {
+    assert_eq!(Ok(Decimal::new(111, 2)), Decimal::try_from(Value::Number(Decimal::new(111, 2))));
+    assert_eq!(Ok(Decimal::new(-42, 0)), Value::Int(-42).try_into());
+    assert_eq!("expected number, found null", Decimal::try_from(Value::Null).unwrap_err().to_string());
+    assert_eq!("expected number, found bool", Decimal::try_from(Value::Bool(true)).unwrap_err().to_string());
+    assert_eq!(
+      "expected number, found str",
+      Decimal::try_from(Value::Str("1".to_string())).unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn test_try_from_bool() {
+    assert_eq!(Ok(true), bool::try_from(Value::Bool(true)));
+    assert_eq!(Ok(false), Value::Bool(false).try_into());
+    assert_eq!("expected bool, found null", bool::try_from(Value::Null).unwrap_err().to_string());
+    assert_eq!(
+      "expected bool, found number",
+      bool::try_from(Value::Number(Decimal::ONE)).unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn test_from_str() {
+    assert_eq!(Ok(Value::Null), "null".parse::<Value>());
+    assert_eq!(Ok(Value::Bool(true)), "true".parse::<Value>());
+    assert_eq!(Ok(Value::Bool(false)), "false".parse::<Value>());
+    assert_eq!(Ok(Value::Number(Decimal::new(-1250, 2))), "-12.50".parse::<Value>());
+    assert_eq!(Ok(Value::Number(Decimal::new(100, 0))), "100".parse::<Value>());
+    assert_eq!(
+      "invalid value 'True', expected null, true, false or decimal number",
+      "True".parse::<Value>().unwrap_err().to_string()
+    );
+    assert!("".parse::<Value>().is_err());
+    assert!("1.2.3".parse::<Value>().is_err());
+  }
+
+  #[cfg(feature = "serde")]
+  fn round_trip(value: Value, json: &str) {
+    assert_eq!(json, serde_json::to_string(&value).unwrap());
+    let deserialized = serde_json::from_str::<Value>(json).unwrap();
+    assert_eq!(value, deserialized);
+    assert_eq!(value.to_string(), deserialized.to_string());
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn test_serde() {
+    round_trip(Value::Null, "null");
+    round_trip(Value::Bool(true), "true");
+    round_trip(Value::Bool(false), "false");
+    round_trip(Value::Number(Decimal::new(110, 2)), r#""1.10""#);
+    round_trip(Value::Number(Decimal::new(-12345678901234567, 10)), r#""-1234567.8901234567""#);
+    round_trip(Value::Int(-42), "-42");
+    round_trip(Value::Str("1.10".to_string()), r#"{"Str":"1.10"}"#);
+    assert_eq!(Value::Number(Decimal::new(15, 1)), serde_json::from_str::<Value>("1.5").unwrap());
+    assert!(serde_json::from_str::<Value>(r#""abc""#).is_err());
+    assert!(serde_json::from_str::<Value>(r#"{"Text":"abc"}"#).is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn test_serde_evaluate() {
+    let values = serde_json::from_str::<Vec<Value>>(r#"[2, "1.5", null, {"Str":"a"}]"#).unwrap();
+    assert_eq!(Value::Int(2), values[0]);
+    let evaluate = |input: &str| {
+      let node = input.parse::<crate::AstNode>().unwrap();
+      let evaluator = crate::build_context_evaluator::<Vec<Value>>(&node, &crate::EvaluatorConfig::default()).unwrap();
+      serde_json::to_string(&evaluator(&values)).unwrap()
+    };
+    assert_eq!(r#""3.5""#, evaluate("0 + 1"));
+    assert_eq!(r#""-2""#, evaluate("- 0"));
+    assert_eq!("true", evaluate("0 > 1 and 0 = 2.00"));
+    assert_eq!(r#""2.5""#, evaluate("max(0; 1) - 1 + abs(0)"));
+    assert_eq!(r#""3.5""#, evaluate("sum()"));
+    assert_eq!("null", evaluate("0 * 2"));
+    assert_eq!("null", evaluate("0 + 3"));
+  }
+
+  #[test]
+  #[cfg(all(feature = "serde", feature = "chrono"))]
+  fn test_serde_date() {
+    round_trip(Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()), r#"{"Date":"2024-01-31"}"#);
   }
 }
@@ -0,0 +1,444 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2022 senees
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Bytecode virtual machine implementation.
+//!
+//! Expression is compiled into a flat sequence of operations executed by a stack machine.
+//! Programs are compiled and run without recursion, so the depth of the expression tree is not limited.
+
+use crate::ast::AstNode;
+use crate::errors::Result;
+use crate::evaluator::{err_unsupported_call, round_scale, Context, EvaluatorConfig, IndexKey, IndexedValues};
+use crate::semantics::{self, avg_numbers, count_numbers, sum_numbers, Equality};
+use crate::values::Value;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Operation of the stack machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+  /// Replaces the number on top of the stack with its absolute value.
+  Abs,
+  /// Pops two numbers and pushes their sum.
+  Add,
+  /// Pops two values and pushes the result of `and` operator, non-boolean operands are treated as `Null`.
+  And,
+  /// Pushes the average of all numbers in indexed values.
+  Avg,
+  /// Pops the tested value and both bounds and pushes the result of `between` test.
+  Between,
+  /// Pops the condition of `if` expression, continues with the next operation when `true`,
+  /// jumps to the false-branch when `false`, otherwise pushes `Null` and jumps to the end of the expression.
+  Branch {
+    /// Position of the first operation of the false-branch.
+    else_target: usize,
+    /// Position of the operation following the expression.
+    end_target: usize,
+  },
+  /// Pops specified number of values and pushes the first one that is not `Null`.
+  Coalesce(usize),
+  /// Pushes the count of all numbers in indexed values.
+  Count,
+  /// Pops two numbers and pushes their quotient.
+  Div,
+  /// Pops two values and pushes the result of `=` comparison.
+  Eq,
+  /// Pops two numbers and pushes the result of `>=` comparison.
+  Ge,
+  /// Pops two numbers and pushes the result of `>` comparison.
+  Gt,
+  /// Pops the tested value and specified number of list elements and pushes the result of `in` test.
+  In(usize),
+  /// Replaces the value on top of the stack with the result of `is not null` test.
+  IsNotNull,
+  /// Replaces the value on top of the stack with the result of `is null` test.
+  IsNull,
+  /// Jumps to specified position.
+  Jump(usize),
+  /// Continues with the next operation when the value on top of the stack is `true`, jumps to specified
+  /// position when it is `false`, otherwise replaces it with `Null` and jumps, unless three-valued logic is enabled.
+  JumpUnlessTrue(usize),
+  /// Continues with the next operation when the value on top of the stack is `false`, jumps to specified
+  /// position when it is `true`, otherwise replaces it with `Null` and jumps, unless three-valued logic is enabled.
+  JumpUnlessFalse(usize),
+  /// Pops two numbers and pushes the result of `<=` comparison.
+  Le,
+  /// Pops two numbers and pushes the result of `<` comparison.
+  Lt,
+  /// Pops specified number of numbers and pushes the greatest one.
+  Max(usize),
+  /// Pops specified number of numbers and pushes the least one.
+  Min(usize),
+  /// Pops two numbers and pushes their product.
+  Mul,
+  /// Replaces the number on top of the stack with its negation.
+  Neg,
+  /// Pops two values and pushes the result of `<>` comparison.
+  Nq,
+  /// Pops two values and pushes the result of `or` operator, non-boolean operands are treated as `Null`.
+  Or,
+  /// Pushes the value stored under specified index.
+  PushIndex(IndexKey),
+  /// Pushes specified constant value.
+  PushValue(Value),
+  /// Replaces the number on top of the stack with the number rounded to specified scale.
+  Round(u32),
+  /// Pops two numbers and pushes their difference.
+  Sub,
+  /// Pushes the sum of all numbers in indexed values.
+  Sum,
+  /// Pops two booleans and pushes the result of `xor` operator.
+  Xor,
+}
+
+/// Compiled program of the stack machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+  /// Operations of the program.
+  ops: Vec<Op>,
+}
+
+impl Program {
+  /// Compiles given [AstNode] into a program, calls of user-registered functions are not supported and reported as errors.
+  pub fn compile(node: &AstNode) -> Result<Self> {
+    let mut ops = vec![];
+    compile_node(node, &mut ops)?;
+    Ok(Self { ops })
+  }
+
+  /// Returns operations of the program.
+  pub fn ops(&self) -> &[Op] {
+    &self.ops
+  }
+
+  /// Runs the program against specified indexed values using default configuration.
+  pub fn run(&self, values: &IndexedValues) -> Value {
+    self.run_with_config(values, &EvaluatorConfig::default())
+  }
+
+  /// Runs the program against specified indexed values using specified configuration.
+  pub fn run_with_config(&self, values: &IndexedValues, config: &EvaluatorConfig) -> Value {
+    let mut stack: Vec<Value> = Vec::with_capacity(16);
+    let mut pc = 0;
+    while let Some(op) = self.ops.get(pc) {
+      pc += 1;
+      match op {
+        Op::Abs => unary(&mut stack, semantics::abs),
+        Op::Add => arithmetic(&mut stack, Value::add),
+        Op::And => logical(&mut stack, semantics::and),
+        Op::Avg => stack.push(avg_numbers(Context::values(values))),
+        Op::Between => {
+          let rhv = pop(&mut stack);
+          let mhv = pop(&mut stack);
+          let lhv = pop(&mut stack);
+          stack.push(semantics::between(lhv, mhv, rhv));
+        }
+        Op::Branch { else_target, end_target } => match pop(&mut stack) {
+          Value::Bool(true) => {}
+          Value::Bool(false) => pc = *else_target,
+          _ => {
+            stack.push(Value::Null);
+            pc = *end_target;
+          }
+        },
+        Op::Coalesce(count) => {
+          let items = stack.split_off(stack.len() - count);
+          stack.push(items.into_iter().find(|value| *value != Value::Null).unwrap_or(Value::Null));
+        }
+        Op::Count => stack.push(count_numbers(Context::values(values))),
+        Op::Div => arithmetic(&mut stack, Value::div),
+        Op::Eq => {
+          let rhv = pop(&mut stack);
+          let lhv = pop(&mut stack);
+          stack.push(Equality::from(config).eq(&lhv, &rhv));
+        }
+        Op::Ge => ordering(&mut stack, Ordering::is_ge),
+        Op::Gt => ordering(&mut stack, Ordering::is_gt),
+        Op::In(count) => {
+          let items = stack.split_off(stack.len() - count);
+          let lhv = pop(&mut stack);
+          stack.push(Equality::from(config).contains(&lhv, items));
+        }
+        Op::IsNotNull => unary(&mut stack, |value| Value::Bool(value != Value::Null)),
+        Op::IsNull => unary(&mut stack, |value| Value::Bool(value == Value::Null)),
+        Op::Jump(target) => pc = *target,
+        Op::JumpUnlessTrue(target) => match stack.last() {
+          Some(Value::Bool(true)) => {}
+          Some(Value::Bool(false)) => pc = *target,
+          _ => {
+            unary(&mut stack, |_| Value::Null);
+            if !config.three_valued_logic {
+              pc = *target;
+            }
+          }
+        },
+        Op::JumpUnlessFalse(target) => match stack.last() {
+          Some(Value::Bool(false)) => {}
+          Some(Value::Bool(true)) => pc = *target,
+          _ => {
+            unary(&mut stack, |_| Value::Null);
+            if !config.three_valued_logic {
+              pc = *target;
+            }
+          }
+        },
+        Op::Le => ordering(&mut stack, Ordering::is_le),
+        Op::Lt => ordering(&mut stack, Ordering::is_lt),
+        Op::Max(count) => extremum(&mut stack, *count, Decimal::max, config.skip_nulls_in_min_max),
+        Op::Min(count) => extremum(&mut stack, *count, Decimal::min, config.skip_nulls_in_min_max),
+        Op::Mul => arithmetic(&mut stack, Value::mul),
+        Op::Neg => unary(&mut stack, semantics::neg),
+        Op::Nq => {
+          let rhv = pop(&mut stack);
+          let lhv = pop(&mut stack);
+          stack.push(Equality::from(config).nq(&lhv, &rhv));
+        }
+        Op::Or => logical(&mut stack, semantics::or),
+        Op::PushIndex(key) => stack.push(values.get(key).unwrap_or(&config.missing).clone()),
+        Op::PushValue(value) => stack.push(value.clone()),
+        Op::Round(scale) => unary(&mut stack, |value| semantics::round(value, *scale)),
+        Op::Sub => arithmetic(&mut stack, Value::sub),
+        Op::Sum => stack.push(sum_numbers(Context::values(values))),
+        Op::Xor => {
+          let rhv = pop(&mut stack);
+          let lhv = pop(&mut stack);
+          stack.push(semantics::xor(lhv, rhv));
+        }
+      }
+    }
+    pop(&mut stack)
+  }
+}
+
+/// Task of the compiler.
+enum Task<'a> {
+  /// Compiles the node.
+  Node(&'a AstNode),
+  /// Appends the operation, jump targets are label identifiers until resolved.
+  Emit(Op),
+  /// Binds the label to the position of the next operation.
+  Label(usize),
+}
+
+/// Compiles the node using an explicit stack of tasks instead of recursion,
+/// so deeply nested expressions do not overflow the call stack.
+fn compile_node(node: &AstNode, ops: &mut Vec<Op>) -> Result<()> {
+  let mut tasks = vec![Task::Node(node)];
+  let mut labels: Vec<usize> = vec![];
+  let mut label = || {
+    labels.push(0);
+    labels.len() - 1
+  };
+  let mut bound = vec![];
+  while let Some(task) = tasks.pop() {
+    let node = match task {
+      Task::Node(node) => node,
+      Task::Emit(op) => {
+        ops.push(op);
+        continue;
+      }
+      Task::Label(id) => {
+        bound.push((id, ops.len()));
+        continue;
+      }
+    };
+    let sequence = match node {
+      AstNode::Abs(mhs) => vec![Task::Node(mhs), Task::Emit(Op::Abs)],
+      AstNode::Add(lhs, rhs) => binary(lhs, rhs, Op::Add),
+      AstNode::And(lhs, rhs) => {
+        let end = label();
+        vec![
+          Task::Node(lhs),
+          Task::Emit(Op::JumpUnlessTrue(end)),
+          Task::Node(rhs),
+          Task::Emit(Op::And),
+          Task::Label(end),
+        ]
+      }
+      AstNode::Between(lhs, mhs, rhs) => vec![Task::Node(lhs), Task::Node(mhs), Task::Node(rhs), Task::Emit(Op::Between)],
+      AstNode::Avg => vec![Task::Emit(Op::Avg)],
+      AstNode::Bool(mhs) => vec![Task::Emit(Op::PushValue(Value::Bool(*mhs)))],
+      AstNode::Call(name, _) => return Err(err_unsupported_call(name)),
+      AstNode::Coalesce(mhs) => list(mhs, Op::Coalesce(mhs.len())),
+      AstNode::Count => vec![Task::Emit(Op::Count)],
+      AstNode::Div(lhs, rhs) => binary(lhs, rhs, Op::Div),
+      AstNode::Eq(lhs, rhs) => binary(lhs, rhs, Op::Eq),
+      AstNode::Ge(lhs, rhs) => binary(lhs, rhs, Op::Ge),
+      AstNode::Gt(lhs, rhs) => binary(lhs, rhs, Op::Gt),
+      AstNode::If(mhs, lhs, rhs) => {
+        let (else_target, end_target) = (label(), label());
+        vec![
+          Task::Node(mhs),
+          Task::Emit(Op::Branch { else_target, end_target }),
+          Task::Node(lhs),
+          Task::Emit(Op::Jump(end_target)),
+          Task::Label(else_target),
+          Task::Node(rhs),
+          Task::Label(end_target),
+        ]
+      }
+      AstNode::In(lhs, rhs) => Some(Task::Node(lhs)).into_iter().chain(list(rhs, Op::In(rhs.len()))).collect(),
+      AstNode::IsNotNull(mhs) => vec![Task::Node(mhs), Task::Emit(Op::IsNotNull)],
+      AstNode::IsNull(mhs) => vec![Task::Node(mhs), Task::Emit(Op::IsNull)],
+      AstNode::Le(lhs, rhs) => binary(lhs, rhs, Op::Le),
+      AstNode::Literal(mhs) => vec![Task::Emit(Op::PushValue(Value::Number(*mhs)))],
+      AstNode::Lt(lhs, rhs) => binary(lhs, rhs, Op::Lt),
+      AstNode::Max(mhs) => list(mhs, Op::Max(mhs.len())),
+      AstNode::Min(mhs) => list(mhs, Op::Min(mhs.len())),
+      AstNode::Mul(lhs, rhs) => binary(lhs, rhs, Op::Mul),
+      AstNode::Neg(mhs) => vec![Task::Node(mhs), Task::Emit(Op::Neg)],
+      AstNode::Nq(lhs, rhs) => binary(lhs, rhs, Op::Nq),
+      AstNode::Null => vec![Task::Emit(Op::PushValue(Value::Null))],
+      AstNode::Number(mhs) => vec![Task::Emit(Op::PushIndex(*mhs))],
+      AstNode::Or(lhs, rhs) => {
+        let end = label();
+        vec![
+          Task::Node(lhs),
+          Task::Emit(Op::JumpUnlessFalse(end)),
+          Task::Node(rhs),
+          Task::Emit(Op::Or),
+          Task::Label(end),
+        ]
+      }
+      AstNode::Round(lhs, rhs) => vec![Task::Node(lhs), Task::Emit(Op::Round(round_scale(rhs)?))],
+      AstNode::StrLiteral(mhs) => vec![Task::Emit(Op::PushValue(Value::Str(mhs.clone())))],
+      #[cfg(feature = "chrono")]
+      AstNode::DateLiteral(mhs) => vec![Task::Emit(Op::PushValue(Value::Date(*mhs)))],
+      AstNode::Sub(lhs, rhs) => binary(lhs, rhs, Op::Sub),
+      AstNode::Sum => vec![Task::Emit(Op::Sum)],
+      AstNode::Xor(lhs, rhs) => binary(lhs, rhs, Op::Xor),
+    };
+    tasks.extend(sequence.into_iter().rev());
+  }
+  for (id, position) in bound {
+    labels[id] = position;
+  }
+  for op in ops.iter_mut() {
+    match op {
+      Op::Branch { else_target, end_target } => {
+        *else_target = labels[*else_target];
+        *end_target = labels[*end_target];
+      }
+      Op::Jump(target) | Op::JumpUnlessTrue(target) | Op::JumpUnlessFalse(target) => *target = labels[*target],
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+/// Returns tasks compiling both operands followed by binary operation.
+fn binary<'a>(lhs: &'a AstNode, rhs: &'a AstNode, op: Op) -> Vec<Task<'a>> {
+  vec![Task::Node(lhs), Task::Node(rhs), Task::Emit(op)]
+}
+
+/// Returns tasks compiling all operands followed by the operation on the list of operands.
+fn list(mhs: &[AstNode], op: Op) -> Vec<Task<'_>> {
+  mhs.iter().map(Task::Node).chain(Some(Task::Emit(op))).collect()
+}
+
+/// Pops the value from the stack, empty stack yields `Null`.
+fn pop(stack: &mut Vec<Value>) -> Value {
+  stack.pop().unwrap_or(Value::Null)
+}
+
+/// Replaces the value on top of the stack with the result of the operation.
+fn unary(stack: &mut Vec<Value>, operation: impl FnOnce(Value) -> Value) {
+  let value = pop(stack);
+  stack.push(operation(value));
+}
+
+/// Pops two numbers and pushes the result of arithmetic operation, see the arithmetic operators of [Value].
+fn arithmetic(stack: &mut Vec<Value>, operation: fn(Value, Value) -> Value) {
+  let rhv = pop(stack);
+  let lhv = pop(stack);
+  stack.push(operation(lhv, rhv));
+}
+
+/// Pops two values and pushes the result of logical operator, see [semantics::and] and [semantics::or].
+fn logical(stack: &mut Vec<Value>, operation: fn(Value, Value) -> Value) {
+  let rhv = pop(stack);
+  let lhv = pop(stack);
+  stack.push(operation(lhv, rhv));
+}
+
+/// Pops two values and pushes the result of ordering comparison, see [semantics::ordering].
+fn ordering(stack: &mut Vec<Value>, compare: fn(Ordering) -> bool) {
+  let rhv = pop(stack);
+  let lhv = pop(stack);
+  stack.push(semantics::ordering(&lhv, &rhv, compare));
+}
+
+/// Pops specified number of numbers and pushes the selected one, see [semantics::extremum].
+fn extremum(stack: &mut Vec<Value>, count: usize, select: fn(Decimal, Decimal) -> Decimal, skip_nulls: bool) {
+  let items = stack.split_off(stack.len() - count);
+  stack.push(semantics::extremum(items, select, skip_nulls));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::Parser;
+
+  #[test]
+  fn test_compile_if() {
+    let program = Program::compile(&Parser::new("if(1 > 2 and true; 1; 2)").parse().unwrap()).unwrap();
+    assert_eq!(
+      &[
+        Op::PushIndex(1),
+        Op::PushIndex(2),
+        Op::Gt,
+        Op::JumpUnlessTrue(6),
+        Op::PushValue(Value::Bool(true)),
+        Op::And,
+        Op::Branch {
+          else_target: 9,
+          end_target: 10
+        },
+        Op::PushIndex(1),
+        Op::Jump(10),
+        Op::PushIndex(2),
+      ],
+      program.ops()
+    );
+  }
+
+  #[test]
+  fn test_compile_call() {
+    let node = Parser::new("double(1) > 2").parse().unwrap();
+    assert_eq!("unsupported function call 'double'", Program::compile(&node).unwrap_err().to_string());
+  }
+
+  #[test]
+  fn test_run() {
+    let program = Program::compile(&Parser::new("if(1 > 2 and true; 1; 2)").parse().unwrap()).unwrap();
+    let mut values = IndexedValues::new();
+    assert_eq!(Value::Null, program.run(&values));
+    values.insert(1, Value::Number(Decimal::new(3, 0)));
+    values.insert(2, Value::Number(Decimal::new(2, 0)));
+    assert_eq!(Value::Number(Decimal::new(3, 0)), program.run(&values));
+    values.insert(1, Value::Number(Decimal::new(1, 0)));
+    assert_eq!(Value::Number(Decimal::new(2, 0)), program.run(&values));
+  }
+}